@@ -0,0 +1,164 @@
+use crate::{rfc5322, EmailThreadProcessor};
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use wasm_bindgen::prelude::*;
+
+const ADDRESS_BOOK_HEADERS: &[&str] = &[
+    "Identity",
+    "Person",
+    "DisplayNames",
+    "MessageCount",
+    "SentCount",
+    "ReceivedCount",
+    "Roles",
+    "FirstActivity",
+    "LastActivity",
+    "Classification",
+];
+
+/// One unique address across the senders and recipients of the in-scope
+/// emails.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressBookEntry {
+    // Lowercased bare address
+    pub identity: String,
+    // Per the identity map, see import_identity_map
+    pub person: Option<String>,
+    // Every distinct display name it appeared under
+    pub display_names: Vec<String>,
+    // Emails it sent or received
+    pub message_count: usize,
+    pub sent_count: usize,
+    pub received_count: usize,
+    // "sender" and/or "recipient"
+    pub roles: Vec<String>,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub first_activity: DateTime<Utc>,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub last_activity: DateTime<Utc>,
+    // Per set_internal_domains; false for everyone when none are set
+    pub is_internal: bool,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    #[wasm_bindgen]
+    pub fn get_address_book(&self) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let entries = self.address_book()?;
+        self.to_js(&entries)
+    }
+
+    /// Every unique address with the display names it was seen under, message
+    /// counts, roles, first and last activity and whether it is internal,
+    /// busiest first. `format` is "json" (default) or "csv".
+    #[wasm_bindgen]
+    pub fn export_address_book(&self, format: Option<String>) -> Result<String, JsValue> {
+        self.reset_cancellation();
+        let entries = self.address_book()?;
+        let format = format.unwrap_or_else(|| "json".to_string());
+        console_log!("Exporting {} address book entries as {}", entries.len(), format);
+
+        match format.as_str() {
+            "json" => self.to_json(&entries),
+            "csv" => to_csv(&entries),
+            other => Err(JsValue::from_str(&format!("Unknown export format: {}", other))),
+        }
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn address_book(&self) -> Result<Vec<AddressBookEntry>, JsValue> {
+        let mut book: IndexMap<String, (AddressBookEntry, BTreeSet<String>)> = IndexMap::new();
+
+        for email in self.included_emails() {
+            self.check_cancelled()?;
+            let senders = rfc5322::parse_named_addresses(&email.from);
+            let recipients = email
+                .to
+                .iter()
+                .chain(&email.cc)
+                .chain(&email.bcc)
+                .flat_map(|r| rfc5322::parse_named_addresses(r));
+
+            let mut seen_here = BTreeSet::new();
+            let mut sent_here = BTreeSet::new();
+            let mut received_here = BTreeSet::new();
+            for ((address, name), is_sender) in senders.into_iter().map(|s| (s, true)).chain(recipients.map(|r| (r, false))) {
+                let identity = address.to_lowercase();
+                let (entry, names) = book.entry(identity.clone()).or_insert_with(|| {
+                    (
+                        AddressBookEntry {
+                            is_internal: identity.rsplit_once('@').is_some_and(|(_, d)| self.is_internal_domain(d)),
+                            person: self.identity_map.person_of(&identity).map(String::from),
+                            identity: identity.clone(),
+                            display_names: Vec::new(),
+                            message_count: 0,
+                            sent_count: 0,
+                            received_count: 0,
+                            roles: Vec::new(),
+                            first_activity: email.date_sent,
+                            last_activity: email.date_sent,
+                        },
+                        BTreeSet::new(),
+                    )
+                });
+                names.extend(name);
+                entry.first_activity = entry.first_activity.min(email.date_sent);
+                entry.last_activity = entry.last_activity.max(email.date_sent);
+                if seen_here.insert(identity.clone()) {
+                    entry.message_count += 1;
+                }
+                if is_sender && sent_here.insert(identity.clone()) {
+                    entry.sent_count += 1;
+                } else if !is_sender && received_here.insert(identity) {
+                    entry.received_count += 1;
+                }
+            }
+        }
+
+        let mut entries: Vec<AddressBookEntry> = book
+            .into_values()
+            .map(|(mut entry, names)| {
+                entry.display_names = names.into_iter().collect();
+                if entry.sent_count > 0 {
+                    entry.roles.push("sender".to_string());
+                }
+                if entry.received_count > 0 {
+                    entry.roles.push("recipient".to_string());
+                }
+                entry
+            })
+            .collect();
+        entries.sort_by(|a, b| b.message_count.cmp(&a.message_count).then_with(|| a.identity.cmp(&b.identity)));
+        Ok(entries)
+    }
+}
+
+fn to_csv(entries: &[AddressBookEntry]) -> Result<String, JsValue> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let to_js = |e: csv::Error| JsValue::from_str(&format!("Error writing address book: {}", e));
+
+    writer.write_record(ADDRESS_BOOK_HEADERS).map_err(to_js)?;
+    for entry in entries {
+        writer
+            .write_record([
+                entry.identity.as_str(),
+                entry.person.as_deref().unwrap_or_default(),
+                &entry.display_names.join("; "),
+                &entry.message_count.to_string(),
+                &entry.sent_count.to_string(),
+                &entry.received_count.to_string(),
+                &entry.roles.join("; "),
+                &entry.first_activity.to_rfc3339(),
+                &entry.last_activity.to_rfc3339(),
+                if entry.is_internal { "Internal" } else { "External" },
+            ])
+            .map_err(to_js)?;
+    }
+
+    let data = writer.into_inner().map_err(|e| JsValue::from_str(&e.to_string()))?;
+    String::from_utf8(data).map_err(|e| JsValue::from_str(&e.to_string()))
+}
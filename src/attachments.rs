@@ -0,0 +1,177 @@
+use crate::filetypes::{extension_of, label};
+use crate::{coding, network, EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentUsage {
+    // Hash, or the document id when the attachment has no hash
+    pub key: String,
+    pub file_name: String,
+    pub file_type: String,
+    // Distinct parent emails carrying a copy
+    pub parent_count: usize,
+    pub thread_count: usize,
+    pub email_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FamilySize {
+    pub family_key: String,
+    pub parent_id: String,
+    pub subject: String,
+    // Parent plus attachments
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainAttachments {
+    pub domain: String,
+    // Attachment keys as in `AttachmentUsage`
+    pub attachments: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentStats {
+    pub total_attachments: usize,
+    pub most_forwarded: Vec<AttachmentUsage>,
+    pub by_file_type: IndexMap<String, usize>,
+    pub by_extension: IndexMap<String, usize>,
+    pub largest_families: Vec<FamilySize>,
+    pub external_domains: Vec<DomainAttachments>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Attachment analytics over email families: most-forwarded attachments by
+    /// hash, file type and extension breakdowns, the largest families and the
+    /// outside domains each attachment was sent to. `limit` (default 20) caps
+    /// the ranked lists.
+    #[wasm_bindgen]
+    pub fn get_attachment_stats(&self, limit: Option<usize>) -> Result<JsValue, JsValue> {
+        let stats = self.attachment_stats(limit.unwrap_or(20));
+        self.to_js(&stats)
+    }
+}
+
+impl EmailThreadProcessor {
+    fn attachment_stats(&self, limit: usize) -> AttachmentStats {
+        let mut families: IndexMap<String, Vec<&EmailMessage>> = IndexMap::new();
+        for (email, key) in self.emails.iter().zip(coding::family_keys(&self.emails)) {
+            if self.filtered_out(email) {
+                continue;
+            }
+            families.entry(key).or_default().push(email);
+        }
+
+        let mut usage: IndexMap<String, AttachmentUsage> = IndexMap::new();
+        let mut parents_of: IndexMap<String, BTreeSet<&str>> = IndexMap::new();
+        let mut threads_of: IndexMap<String, BTreeSet<String>> = IndexMap::new();
+        let mut by_file_type: IndexMap<String, usize> = IndexMap::new();
+        let mut by_extension: IndexMap<String, usize> = IndexMap::new();
+        let mut domains: IndexMap<String, BTreeSet<String>> = IndexMap::new();
+        let mut largest_families = Vec::new();
+        let mut total_attachments = 0;
+
+        for (family_key, members) in families.iter().filter(|(_, m)| m.len() > 1) {
+            let parent = family_parent(family_key, members);
+            largest_families.push(FamilySize {
+                family_key: family_key.clone(),
+                parent_id: parent.id.clone(),
+                subject: parent.subject.clone(),
+                size: members.len(),
+            });
+            let outside = external_domains(parent);
+
+            for attachment in members.iter().filter(|m| m.id != parent.id) {
+                total_attachments += 1;
+                let key = if attachment.hash.is_empty() {
+                    attachment.id.clone()
+                } else {
+                    attachment.hash.to_lowercase()
+                };
+                *by_file_type.entry(label(&attachment.file_type)).or_default() += 1;
+                *by_extension.entry(label(&extension_of(attachment))).or_default() += 1;
+
+                let entry = usage.entry(key.clone()).or_insert_with(|| AttachmentUsage {
+                    key: key.clone(),
+                    file_name: attachment.file_name.clone(),
+                    file_type: attachment.file_type.clone(),
+                    parent_count: 0,
+                    thread_count: 0,
+                    email_ids: Vec::new(),
+                });
+                entry.email_ids.push(attachment.id.clone());
+                parents_of.entry(key.clone()).or_default().insert(parent.id.as_str());
+                if let Some(thread) = self.thread_key(parent) {
+                    threads_of.entry(key.clone()).or_default().insert(thread);
+                }
+                for domain in &outside {
+                    domains.entry(domain.clone()).or_default().insert(key.clone());
+                }
+            }
+        }
+
+        let mut most_forwarded: Vec<AttachmentUsage> = usage
+            .into_values()
+            .map(|mut u| {
+                u.parent_count = parents_of.get(&u.key).map_or(0, |p| p.len());
+                u.thread_count = threads_of.get(&u.key).map_or(0, |t| t.len());
+                u
+            })
+            .collect();
+        most_forwarded.sort_by(|a, b| b.parent_count.cmp(&a.parent_count).then_with(|| b.thread_count.cmp(&a.thread_count)));
+        most_forwarded.truncate(limit);
+        largest_families.sort_by_key(|f| std::cmp::Reverse(f.size));
+        largest_families.truncate(limit);
+        by_file_type.sort_by(|_, a, _, b| b.cmp(a));
+        by_extension.sort_by(|_, a, _, b| b.cmp(a));
+        domains.sort_keys();
+
+        AttachmentStats {
+            total_attachments,
+            most_forwarded,
+            by_file_type,
+            by_extension,
+            largest_families,
+            external_domains: domains
+                .into_iter()
+                .map(|(domain, keys)| DomainAttachments {
+                    domain,
+                    attachments: keys.into_iter().collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+// The member the rest hang off: no parent of its own and, when the load file
+// has BegAttach, the document that starts the range
+fn family_parent<'a>(family_key: &str, members: &[&'a EmailMessage]) -> &'a EmailMessage {
+    members
+        .iter()
+        .find(|m| m.parent_id.is_none() && (m.beg_attach.is_empty() || m.beg_bates == family_key))
+        .or_else(|| members.iter().find(|m| m.parent_id.is_none()))
+        .unwrap_or(&members[0])
+}
+
+// Recipient domains other than the sender's, for emails that left the
+// organization
+fn external_domains(email: &EmailMessage) -> BTreeSet<String> {
+    if !email.is_external {
+        return BTreeSet::new();
+    }
+    let domain_of = |address: &str| address.rsplit_once('@').map(|(_, d)| d.to_string());
+    let sender_domain = network::identities(&email.from).first().and_then(|a| domain_of(a));
+    email
+        .to
+        .iter()
+        .chain(&email.cc)
+        .chain(&email.bcc)
+        .flat_map(|r| network::identities(r))
+        .filter_map(|a| domain_of(&a))
+        .filter(|d| Some(d) != sender_domain.as_ref())
+        .collect()
+}
@@ -0,0 +1,67 @@
+use crate::EmailThreadProcessor;
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use wasm_bindgen::prelude::*;
+
+/// One data-affecting operation: a load, a threading run or setting change,
+/// a tag or label edit, or a removal. Entries are append-only and numbered
+/// from 1 in the order they happened; `clear` is logged, not a reset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub sequence: usize,
+    pub timestamp: DateTime<Utc>,
+    // The API call, e.g. "load_emails_from_csv" or "tag_email"
+    pub operation: String,
+    pub details: String,
+    // Input name -> SHA-256 (lowercase hex) of the bytes loaded
+    pub input_hashes: IndexMap<String, String>,
+    // Corpus size once the operation finished
+    pub email_count: usize,
+    pub thread_count: usize,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    #[wasm_bindgen]
+    pub fn get_audit_log(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.audit_log)
+    }
+
+    /// The audit log as pretty-printed JSON, for filing alongside a
+    /// production or declaration. It is also kept in state snapshots.
+    #[wasm_bindgen]
+    pub fn export_audit_log(&self) -> Result<String, JsValue> {
+        serde_json::to_string_pretty(&self.audit_log).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn audit(&mut self, operation: &str, details: String) {
+        self.audit_inputs(operation, details, IndexMap::new());
+    }
+
+    /// Logs a load with the hash of each input, given as (name, bytes).
+    pub(crate) fn audit_load(&mut self, operation: &str, details: String, inputs: &[(&str, &[u8])]) {
+        let hashes = inputs.iter().map(|(name, data)| (name.to_string(), sha256_hex(data))).collect();
+        self.audit_inputs(operation, details, hashes);
+    }
+
+    pub(crate) fn audit_inputs(&mut self, operation: &str, details: String, input_hashes: IndexMap<String, String>) {
+        let entry = AuditEntry {
+            sequence: self.audit_log.len() + 1,
+            timestamp: Utc::now(),
+            operation: operation.to_string(),
+            details,
+            input_hashes,
+            email_count: self.emails.len(),
+            thread_count: self.threads.len(),
+        };
+        self.audit_log.push(entry);
+    }
+}
+
+pub(crate) fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
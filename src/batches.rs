@@ -0,0 +1,138 @@
+use crate::EmailThreadProcessor;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// How much each factor adds to a thread's estimated review effort:
+/// `documents` per email, `text_length` per 1,000 characters of text and
+/// `complexity` per level of depth or branch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EffortWeights {
+    pub documents: f64,
+    pub text_length: f64,
+    pub complexity: f64,
+}
+
+impl Default for EffortWeights {
+    fn default() -> Self {
+        EffortWeights {
+            documents: 1.0,
+            text_length: 0.5,
+            complexity: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadAssignment {
+    pub thread_id: String,
+    pub reviewer: String,
+    pub document_count: usize,
+    pub text_length: usize,
+    // Max depth plus branch count
+    pub complexity: usize,
+    pub effort: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewerLoad {
+    pub reviewer: String,
+    pub thread_count: usize,
+    pub document_count: usize,
+    pub text_length: usize,
+    pub effort: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchAssignment {
+    pub weights: EffortWeights,
+    // In reviewer order
+    pub reviewers: Vec<ReviewerLoad>,
+    // In thread listing order
+    pub assignments: Vec<ThreadAssignment>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Splits the listed threads among `reviewers` so each gets a similar
+    /// estimated effort, never dividing a thread. `weights` is an optional
+    /// `EffortWeights` object. The largest threads are placed first, each
+    /// with the reviewer carrying the least effort so far.
+    #[wasm_bindgen]
+    pub fn assign_batches(&self, reviewers: Vec<String>, weights: JsValue) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let reviewers: Vec<String> = reviewers
+            .iter()
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty())
+            .collect();
+        if reviewers.is_empty() {
+            return Err(JsValue::from_str("No reviewers given"));
+        }
+        let weights: EffortWeights = if weights.is_undefined() || weights.is_null() {
+            EffortWeights::default()
+        } else {
+            serde_wasm_bindgen::from_value(weights)?
+        };
+
+        let assignment = self.batch_assignment(&reviewers, weights)?;
+        console_log!("Assigned {} threads to {} reviewers", assignment.assignments.len(), reviewers.len());
+        self.to_js(&assignment)
+    }
+}
+
+impl EmailThreadProcessor {
+    fn batch_assignment(&self, reviewers: &[String], weights: EffortWeights) -> Result<BatchAssignment, JsValue> {
+        let threads: Vec<&String> = self.visible_threads().map(|(id, _)| id).collect();
+        let mut assignments = Vec::with_capacity(threads.len());
+        for (i, thread_id) in threads.iter().enumerate() {
+            self.check_cancelled()?;
+            let emails = &self.threads[*thread_id];
+            let document_count = emails.len();
+            let text_length: usize = emails.iter().map(|e| e.full_text.chars().count()).sum();
+            let outline = self.outline(emails);
+            let complexity = outline.max_depth() + outline.branch_count;
+            assignments.push(ThreadAssignment {
+                thread_id: thread_id.to_string(),
+                reviewer: String::new(),
+                document_count,
+                text_length,
+                complexity,
+                effort: weights.documents * document_count as f64
+                    + weights.text_length * text_length as f64 / 1000.0
+                    + weights.complexity * complexity as f64,
+            });
+            self.emit_progress("batching", i + 1, Some(threads.len()));
+        }
+
+        let mut loads: Vec<ReviewerLoad> = reviewers
+            .iter()
+            .map(|r| ReviewerLoad {
+                reviewer: r.clone(),
+                thread_count: 0,
+                document_count: 0,
+                text_length: 0,
+                effort: 0.0,
+            })
+            .collect();
+        let mut order: Vec<usize> = (0..assignments.len()).collect();
+        order.sort_by(|&a, &b| assignments[b].effort.total_cmp(&assignments[a].effort));
+        for i in order {
+            let assignment = &mut assignments[i];
+            // First reviewer wins ties, so the result is deterministic
+            let lightest = (1..loads.len()).fold(0, |best, j| if loads[j].effort < loads[best].effort { j } else { best });
+            let load = &mut loads[lightest];
+            load.thread_count += 1;
+            load.document_count += assignment.document_count;
+            load.text_length += assignment.text_length;
+            load.effort += assignment.effort;
+            assignment.reviewer = load.reviewer.clone();
+        }
+
+        Ok(BatchAssignment {
+            weights,
+            reviewers: loads,
+            assignments,
+        })
+    }
+}
@@ -0,0 +1,78 @@
+use crate::tree_options::TreeOptions;
+use crate::{EmailThreadProcessor, ThreadTree};
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// Handle for aborting a long-running load, bulk tree build or export. The
+/// processor runs synchronously, so `cancel()` is called from one of the
+/// event callbacks (typically `on_progress`) while the operation is running.
+#[wasm_bindgen]
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Rc<Cell<bool>>,
+}
+
+#[wasm_bindgen]
+impl CancellationToken {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    #[wasm_bindgen]
+    pub fn cancel(&self) {
+        self.cancelled.set(true);
+    }
+
+    #[wasm_bindgen]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.get()
+    }
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Returns the processor's cancellation token. Cancelled operations fail
+    /// with "Operation cancelled" and leave previously loaded data untouched.
+    /// A cancel raised between operations is dropped when the next one starts.
+    #[wasm_bindgen]
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /// Builds trees for the given threads (every visible thread when omitted) in one
+    /// call, reporting progress and honouring cancellation. `options` is a
+    /// `TreeOptions` object as for `build_thread_tree`.
+    #[wasm_bindgen]
+    pub fn build_thread_trees(&self, thread_ids: Option<Vec<String>>, options: JsValue) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let options = TreeOptions::from_js(options)?;
+        let thread_ids = thread_ids.unwrap_or_else(|| self.visible_threads().map(|(id, _)| id.clone()).collect());
+        console_log!("Building {} thread trees", thread_ids.len());
+
+        let mut trees: Vec<ThreadTree> = Vec::with_capacity(thread_ids.len());
+        for (i, thread_id) in thread_ids.iter().enumerate() {
+            self.check_cancelled()?;
+            trees.push(self.thread_tree_with(thread_id, &options)?);
+            self.emit_progress("trees", i + 1, Some(thread_ids.len()));
+        }
+        self.to_js(&trees)
+    }
+}
+
+impl EmailThreadProcessor {
+    // Consumes a pending cancellation so the next operation starts clean
+    pub(crate) fn check_cancelled(&self) -> Result<(), JsValue> {
+        if self.cancellation.cancelled.replace(false) {
+            console_log!("Operation cancelled");
+            return Err(JsValue::from_str("Operation cancelled"));
+        }
+        Ok(())
+    }
+
+    // Drops a cancellation left over from before this operation started
+    pub(crate) fn reset_cancellation(&self) {
+        self.cancellation.cancelled.set(false);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ChronologyFilter {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub custodians: Vec<String>,
+    pub thread_ids: Vec<String>,
+    // Tags that mark a hot document
+    pub hot_tags: Vec<String>,
+    pub include_inclusive: bool,
+    pub include_external: bool,
+    pub include_hot: bool,
+}
+
+impl Default for ChronologyFilter {
+    fn default() -> Self {
+        ChronologyFilter {
+            start: None,
+            end: None,
+            custodians: Vec::new(),
+            thread_ids: Vec::new(),
+            hot_tags: vec!["Hot".to_string()],
+            include_inclusive: true,
+            include_external: true,
+            include_hot: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChronologyEvent {
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub date: DateTime<Utc>,
+    pub email_id: String,
+    pub bates: String,
+    pub thread_id: String,
+    // "inclusive_email", "first_external_disclosure", "hot_document"
+    pub kinds: Vec<String>,
+    pub description: String,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Date-ordered key events across all threads: inclusive emails, the first
+    /// external email of each thread and hot-tagged documents. `filter` takes
+    /// `start`/`end` (RFC 3339), `custodians`, `thread_ids`, `hot_tags`
+    /// (default ["Hot"]) and `include_inclusive`/`include_external`/`include_hot`.
+    #[wasm_bindgen]
+    pub fn build_chronology(&self, filter: JsValue) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let filter: ChronologyFilter = if filter.is_undefined() || filter.is_null() {
+            ChronologyFilter::default()
+        } else {
+            serde_wasm_bindgen::from_value(filter)?
+        };
+
+        let inclusive: HashMap<String, bool> = if filter.include_inclusive {
+            self.overlay_rows()?.into_iter().map(|r| (r.email_id, r.is_inclusive)).collect()
+        } else {
+            HashMap::new()
+        };
+        let first_external: HashSet<&str> = if filter.include_external {
+            self.threads
+                .values()
+                .filter_map(|emails| emails.iter().filter(|e| e.is_external).min_by_key(|e| e.date_sent))
+                .map(|e| e.id.as_str())
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        let mut events = Vec::new();
+        for email in self.emails.iter().filter(|e| !self.filtered_out(e) && filter.matches(e, self.thread_key(e).as_deref())) {
+            let mut kinds = Vec::new();
+            if inclusive.get(&email.id).copied().unwrap_or(false) {
+                kinds.push("inclusive_email".to_string());
+            }
+            if first_external.contains(email.id.as_str()) {
+                kinds.push("first_external_disclosure".to_string());
+            }
+            if filter.include_hot && email.tags.iter().any(|t| filter.hot_tags.iter().any(|h| h.eq_ignore_ascii_case(t))) {
+                kinds.push("hot_document".to_string());
+            }
+            if kinds.is_empty() {
+                continue;
+            }
+
+            events.push(ChronologyEvent {
+                date: email.date_sent,
+                email_id: email.id.clone(),
+                bates: email.beg_bates.clone(),
+                thread_id: self.thread_key(email).unwrap_or_default(),
+                kinds,
+                description: describe(email),
+            });
+        }
+        events.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.bates.cmp(&b.bates)));
+
+        console_log!("Chronology has {} events", events.len());
+        self.to_js(&events)
+    }
+}
+
+impl ChronologyFilter {
+    fn matches(&self, email: &EmailMessage, thread_id: Option<&str>) -> bool {
+        self.start.is_none_or(|start| email.date_sent >= start)
+            && self.end.is_none_or(|end| email.date_sent <= end)
+            && (self.custodians.is_empty()
+                || crate::custodians::custodians_of(email)
+                    .iter()
+                    .any(|c| self.custodians.iter().any(|f| f.eq_ignore_ascii_case(c))))
+            && (self.thread_ids.is_empty() || thread_id.is_some_and(|t| self.thread_ids.iter().any(|f| f == t)))
+    }
+}
+
+fn describe(email: &EmailMessage) -> String {
+    let recipients = match email.to.len() {
+        0 => "(no recipients)".to_string(),
+        1 => email.to[0].clone(),
+        n => format!("{} and {} others", email.to[0], n - 1),
+    };
+    let subject = if email.subject.trim().is_empty() { "(no subject)" } else { email.subject.trim() };
+    let mut description = format!("{} to {}: {}", email.from, recipients, subject);
+    if email.is_forward {
+        description.push_str(" (forward)");
+    }
+    description
+}
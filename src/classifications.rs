@@ -0,0 +1,164 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// One external model's call on a document, e.g. a TAR relevance score.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Classification {
+    pub label: Option<String>,
+    pub score: Option<f64>,
+}
+
+/// A model's results over a thread's emails.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClassificationSummary {
+    // Label -> emails carrying it, most common first
+    pub label_counts: IndexMap<String, usize>,
+    pub scored_count: usize,
+    pub mean_score: Option<f64>,
+    pub max_score: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassificationResult {
+    pub applied: usize,
+    pub models: Vec<String>,
+    // Bates numbers or ids that matched no email
+    pub unmatched: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ClassificationRecord {
+    #[serde(default, alias = "beg_bates")]
+    bates: Option<String>,
+    #[serde(default)]
+    email_id: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    score: Option<f64>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Stores labels and scores computed outside the tool, given as a JSON
+    /// array of `{bates, model, label, score}` objects (`email_id` may stand in
+    /// for `bates`; `model` defaults to "default"). A later result for the same
+    /// email and model replaces the earlier one. Results can then be filtered
+    /// on, are summarized in thread stats and branch rollups, and export in
+    /// the DAT "Classifications" field.
+    #[wasm_bindgen]
+    pub fn apply_classifications(&mut self, json: &str) -> Result<JsValue, JsValue> {
+        let records: Vec<ClassificationRecord> = serde_json::from_str(json.trim_start_matches('\u{feff}'))
+            .map_err(|e| JsValue::from_str(&format!("Error reading classifications: {}", e)))?;
+
+        let mut by_key: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, email) in self.emails.iter().enumerate() {
+            by_key.entry(format!("bates:{}", email.beg_bates.trim().to_lowercase())).or_default().push(i);
+            by_key.entry(format!("id:{}", email.id)).or_default().push(i);
+        }
+
+        let mut applied = 0;
+        let mut models: Vec<String> = Vec::new();
+        let mut unmatched = Vec::new();
+        for (n, record) in records.into_iter().enumerate() {
+            let (key, lookup) = match (&record.bates, &record.email_id) {
+                (Some(bates), _) if !bates.trim().is_empty() => {
+                    (bates.trim().to_string(), format!("bates:{}", bates.trim().to_lowercase()))
+                }
+                (_, Some(id)) if !id.is_empty() => (id.clone(), format!("id:{}", id)),
+                _ => return Err(JsValue::from_str(&format!("Classification {} has no bates or email_id", n + 1))),
+            };
+            if record.score.is_some_and(|s| !s.is_finite()) {
+                return Err(JsValue::from_str(&format!("Classification for {} has an invalid score", key)));
+            }
+            let label = record.label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+            if label.is_none() && record.score.is_none() {
+                return Err(JsValue::from_str(&format!("Classification for {} has no label or score", key)));
+            }
+            let model = record
+                .model
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .unwrap_or_else(|| "default".to_string());
+
+            let Some(targets) = by_key.get(&lookup) else {
+                unmatched.push(key);
+                continue;
+            };
+            for &i in targets {
+                let classification = Classification {
+                    label: label.clone(),
+                    score: record.score,
+                };
+                self.emails[i].classifications.insert(model.clone(), classification);
+                applied += 1;
+            }
+            if !models.contains(&model) {
+                models.push(model);
+            }
+        }
+        self.refresh_thread_copies();
+
+        console_log!("Applied {} classifications, {} unmatched", applied, unmatched.len());
+        self.audit_load(
+            "apply_classifications",
+            format!("{} applied from {}, {} unmatched", applied, models.join(", "), unmatched.len()),
+            &[("classifications", json.as_bytes())],
+        );
+        let result = ClassificationResult {
+            applied,
+            models,
+            unmatched,
+        };
+        self.to_js(&result)
+    }
+}
+
+/// Per-model summaries over `emails`, models in first-seen order.
+pub(crate) fn summarize(emails: &[EmailMessage]) -> IndexMap<String, ClassificationSummary> {
+    let mut summaries: IndexMap<String, ClassificationSummary> = IndexMap::new();
+    let mut totals: HashMap<&str, f64> = HashMap::new();
+    for email in emails {
+        for (model, classification) in &email.classifications {
+            let summary = summaries.entry(model.clone()).or_default();
+            if let Some(label) = &classification.label {
+                *summary.label_counts.entry(label.clone()).or_default() += 1;
+            }
+            if let Some(score) = classification.score {
+                summary.scored_count += 1;
+                *totals.entry(model.as_str()).or_default() += score;
+                summary.max_score = Some(summary.max_score.map_or(score, |m| m.max(score)));
+            }
+        }
+    }
+    for (model, summary) in summaries.iter_mut() {
+        if summary.scored_count > 0 {
+            summary.mean_score = Some(totals[model.as_str()] / summary.scored_count as f64);
+        }
+        summary.label_counts.sort_by(|a, x, b, y| y.cmp(x).then_with(|| a.cmp(b)));
+    }
+    summaries
+}
+
+/// Every label and score as "model: label (score)", separated by semicolons.
+pub(crate) fn format_classifications(email: &EmailMessage) -> String {
+    email
+        .classifications
+        .iter()
+        .map(|(model, c)| {
+            let value = match (&c.label, c.score) {
+                (Some(label), Some(score)) => format!("{} ({})", label, score),
+                (Some(label), None) => label.clone(),
+                (None, Some(score)) => score.to_string(),
+                (None, None) => String::new(),
+            };
+            format!("{}: {}", model, value)
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
@@ -0,0 +1,309 @@
+use crate::{inclusive, EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use wasm_bindgen::prelude::*;
+
+/// Where `tag_email` copies a tag besides the email itself.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PropagationOptions {
+    // Same hash or Message-ID
+    pub duplicates: bool,
+    // Parent email and attachments
+    pub family: bool,
+    // Ancestors in the thread whose content this email quotes in full
+    pub ancestors: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodingConflict {
+    // "email", "duplicates" or "family"
+    pub scope: String,
+    pub key: String,
+    pub tags: Vec<String>,
+    pub email_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagResult {
+    pub tag: String,
+    pub tagged: Vec<String>,
+    // Conflicts involving any email this call touched
+    pub conflicts: Vec<CodingConflict>,
+}
+
+pub(crate) fn default_conflicting_tags() -> Vec<(String, String)> {
+    [("Responsive", "Not Responsive"), ("Privileged", "Not Privileged")]
+        .iter()
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+        .collect()
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Applies `tag` to an email and, per `options`
+    /// (`{duplicates, family, ancestors}`), to related emails. Returns the
+    /// emails tagged and any coding conflicts among them.
+    #[wasm_bindgen]
+    pub fn tag_email(&mut self, email_id: &str, tag: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: PropagationOptions = if options.is_undefined() || options.is_null() {
+            PropagationOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options)?
+        };
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(JsValue::from_str("Tag is empty"));
+        }
+        let idx = self
+            .email_index(email_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Email not found: {}", email_id)))?;
+
+        let mut targets = BTreeSet::from([idx]);
+        if options.duplicates {
+            targets.extend(self.duplicates_of(idx));
+        }
+        if options.family {
+            targets.extend(self.family_of(idx));
+        }
+        if options.ancestors {
+            targets.extend(self.contained_ancestors_of(idx));
+        }
+
+        for &i in &targets {
+            let email = &mut self.emails[i];
+            if !email.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                email.tags.push(tag.to_string());
+            }
+        }
+        self.refresh_thread_copies();
+        let ids: Vec<&str> = targets.iter().map(|&i| self.emails[i].id.as_str()).collect();
+        let details = format!("\"{}\" applied to {}", tag, ids.join(", "));
+        self.audit("tag_email", details);
+
+        let touched: Vec<&str> = targets.iter().map(|&i| self.emails[i].id.as_str()).collect();
+        let result = TagResult {
+            tag: tag.to_string(),
+            tagged: touched.iter().map(|id| id.to_string()).collect(),
+            conflicts: self
+                .coding_conflicts()
+                .into_iter()
+                .filter(|c| c.email_ids.iter().any(|id| touched.contains(&id.as_str())))
+                .collect(),
+        };
+        self.to_js(&result)
+    }
+
+    #[wasm_bindgen]
+    pub fn untag_email(&mut self, email_id: &str, tag: &str) -> Result<bool, JsValue> {
+        let idx = self
+            .email_index(email_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Email not found: {}", email_id)))?;
+        let tags = &mut self.emails[idx].tags;
+        let before = tags.len();
+        tags.retain(|t| !t.eq_ignore_ascii_case(tag));
+        let removed = tags.len() < before;
+        self.refresh_thread_copies();
+        if removed {
+            self.audit("untag_email", format!("\"{}\" removed from {}", tag, email_id));
+        }
+        Ok(removed)
+    }
+
+    /// Replaces the mutually exclusive tag pairs used for conflict detection,
+    /// e.g. `[["Responsive", "Not Responsive"]]`.
+    #[wasm_bindgen]
+    pub fn set_conflicting_tags(&mut self, pairs: JsValue) -> Result<(), JsValue> {
+        let pairs: Vec<(String, String)> = serde_wasm_bindgen::from_value(pairs)?;
+        self.conflicting_tags = pairs;
+        Ok(())
+    }
+
+    /// Emails, duplicate sets and families carrying both tags of a conflicting pair.
+    #[wasm_bindgen]
+    pub fn get_coding_conflicts(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.coding_conflicts())
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn email_index(&self, email_id: &str) -> Option<usize> {
+        let index = self
+            .id_index
+            .get_or_init(|| self.emails.iter().enumerate().map(|(i, e)| (e.id.clone(), i)).collect::<HashMap<_, _>>());
+        index.get(email_id).copied()
+    }
+
+    fn duplicates_of(&self, idx: usize) -> Vec<usize> {
+        let keys = self.duplicate_keys.get_or_init(|| duplicate_keys(&self.emails));
+        let Some(own) = &keys[idx] else {
+            return Vec::new();
+        };
+        keys.iter()
+            .enumerate()
+            .filter(|(i, key)| *i != idx && key.as_ref() == Some(own))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn family_of(&self, idx: usize) -> Vec<usize> {
+        let keys = family_keys(&self.emails);
+        keys.iter()
+            .enumerate()
+            .filter(|(i, key)| *i != idx && **key == keys[idx])
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    // Ancestors whose new content this email quotes; coding the later email
+    // covers them, which is what makes them non-inclusive
+    fn contained_ancestors_of(&self, idx: usize) -> Vec<usize> {
+        let email = &self.emails[idx];
+        let Some(thread) = self.thread_key(email).and_then(|key| self.threads.get(&key)) else {
+            return Vec::new();
+        };
+        let parents = self.resolve_parents(thread);
+
+        let mut ancestors = Vec::new();
+        let mut current = email.id.clone();
+        let mut seen = BTreeSet::new();
+        while let Some(parent) = parents.get(&current) {
+            if !seen.insert(parent.clone()) {
+                break;
+            }
+            // An ancestor with no new content of its own (empty, attachment
+            // only, a sequestered placeholder) is trivially contained, not
+            // covered by this email's coding
+            if let Some(parent_idx) = self.email_index(parent) {
+                let parent_text = &self.emails[parent_idx].full_text;
+                if !self.is_sequestered(parent)
+                    && !inclusive::normalized_words(inclusive::new_content(parent_text)).is_empty()
+                    && inclusive::is_contained(parent_text, &email.full_text)
+                {
+                    ancestors.push(parent_idx);
+                }
+            }
+            current = parent.clone();
+        }
+        ancestors
+    }
+
+    pub(crate) fn coding_conflicts(&self) -> Vec<CodingConflict> {
+        let mut conflicts = Vec::new();
+
+        for email in &self.emails {
+            self.check_group(&mut conflicts, "email", &email.id, &[email]);
+        }
+
+        let mut duplicates: IndexMap<String, Vec<&EmailMessage>> = IndexMap::new();
+        let mut families: IndexMap<String, Vec<&EmailMessage>> = IndexMap::new();
+        let duplicate_sets = self.duplicate_keys.get_or_init(|| duplicate_keys(&self.emails));
+        let keys = duplicate_sets.iter().zip(family_keys(&self.emails));
+        for (email, (duplicate, family)) in self.emails.iter().zip(keys) {
+            if let Some(duplicate) = duplicate {
+                duplicates.entry(duplicate.clone()).or_default().push(email);
+            }
+            families.entry(family).or_default().push(email);
+        }
+        for (key, group) in duplicates.iter().filter(|(_, g)| g.len() > 1) {
+            self.check_group(&mut conflicts, "duplicates", key, group);
+        }
+        for (key, group) in families.iter().filter(|(_, g)| g.len() > 1) {
+            self.check_group(&mut conflicts, "family", key, group);
+        }
+
+        conflicts
+    }
+
+    fn check_group(&self, conflicts: &mut Vec<CodingConflict>, scope: &str, key: &str, group: &[&EmailMessage]) {
+        let has = |email: &EmailMessage, tag: &str| email.tags.iter().any(|t| t.eq_ignore_ascii_case(tag));
+        for (a, b) in &self.conflicting_tags {
+            let with_a: Vec<&str> = group.iter().filter(|e| has(e, a)).map(|e| e.id.as_str()).collect();
+            let with_b: Vec<&str> = group.iter().filter(|e| has(e, b)).map(|e| e.id.as_str()).collect();
+            if with_a.is_empty() || with_b.is_empty() {
+                continue;
+            }
+            let email_ids: BTreeSet<&str> = with_a.into_iter().chain(with_b).collect();
+            // An email coded both ways is already reported on its own
+            if scope != "email" && email_ids.len() == 1 {
+                continue;
+            }
+            conflicts.push(CodingConflict {
+                scope: scope.to_string(),
+                key: key.to_string(),
+                tags: vec![a.clone(), b.clone()],
+                email_ids: email_ids.into_iter().map(|id| id.to_string()).collect(),
+            });
+        }
+    }
+}
+
+// Families are keyed by BegAttach when the load file has it, otherwise by the
+// top-level parent document
+pub(crate) fn family_keys(emails: &[EmailMessage]) -> Vec<String> {
+    let by_id: HashMap<&str, &EmailMessage> = emails.iter().map(|e| (e.id.as_str(), e)).collect();
+    emails
+        .iter()
+        .map(|email| {
+            if !email.beg_attach.is_empty() {
+                return email.beg_attach.clone();
+            }
+            let mut current = email;
+            for _ in 0..emails.len() {
+                let Some(parent) = current.parent_id.as_deref() else {
+                    break;
+                };
+                match by_id.get(parent) {
+                    Some(next) => current = next,
+                    None => return parent.to_string(),
+                }
+            }
+            current.id.clone()
+        })
+        .collect()
+}
+
+// Duplicates share a hash (case-insensitively) or a Message-ID, chained, so
+// an email matching one copy by hash and another by Message-ID joins both.
+// A set is keyed by its first hash, or its first Message-ID when no copy has
+// a hash; emails with neither have no key
+pub(crate) fn duplicate_keys(emails: &[EmailMessage]) -> Vec<Option<String>> {
+    let mut set_of: Vec<usize> = (0..emails.len()).collect();
+    let mut first_by_hash: HashMap<String, usize> = HashMap::new();
+    let mut first_by_message_id: HashMap<&str, usize> = HashMap::new();
+    for (i, email) in emails.iter().enumerate() {
+        if !email.hash.is_empty() {
+            let first = *first_by_hash.entry(email.hash.to_lowercase()).or_insert(i);
+            join(&mut set_of, first, i);
+        }
+        if !email.message_id.is_empty() {
+            let first = *first_by_message_id.entry(email.message_id.as_str()).or_insert(i);
+            join(&mut set_of, first, i);
+        }
+    }
+
+    let roots: Vec<usize> = (0..emails.len()).map(|i| find(&mut set_of, i)).collect();
+    let mut keys: HashMap<usize, String> = HashMap::new();
+    for (email, &root) in emails.iter().zip(&roots).filter(|(e, _)| !e.hash.is_empty()) {
+        keys.entry(root).or_insert_with(|| email.hash.to_lowercase());
+    }
+    for (email, &root) in emails.iter().zip(&roots).filter(|(e, _)| !e.message_id.is_empty()) {
+        keys.entry(root).or_insert_with(|| email.message_id.clone());
+    }
+    roots.iter().map(|root| keys.get(root).cloned()).collect()
+}
+
+fn join(set_of: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(set_of, a), find(set_of, b));
+    set_of[a.max(b)] = a.min(b);
+}
+
+fn find(set_of: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while set_of[root] != root {
+        root = set_of[root];
+    }
+    set_of[i] = root;
+    root
+}
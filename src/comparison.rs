@@ -0,0 +1,210 @@
+use crate::{inclusive, DateRange, EmailMessage, EmailThreadProcessor};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagePair {
+    pub email_a: String,
+    pub email_b: String,
+    // "message_id" or "hash" for duplicates; "a_in_b" or "b_in_a" for quotes
+    pub matched_on: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadComparison {
+    pub thread_a: String,
+    pub thread_b: String,
+    pub shared_participants: Vec<String>,
+    pub participants_only_a: Vec<String>,
+    pub participants_only_b: Vec<String>,
+    pub date_range_a: DateRange,
+    pub date_range_b: DateRange,
+    pub date_overlap: Option<DateRange>,
+    pub duplicated_messages: Vec<MessagePair>,
+    // One thread's message quoted in full by the other's
+    pub shared_quotes: Vec<MessagePair>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeCandidate {
+    pub thread_a: String,
+    pub thread_b: String,
+    pub size_a: usize,
+    pub size_b: usize,
+    // Emails in each thread whose Message-ID or hash also appears in the other
+    pub shared_a: usize,
+    pub shared_b: usize,
+    // Share of the more-contained thread found in the other, 0.0-1.0
+    pub confidence: f64,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// What two threads have in common, to judge whether they are fragments of
+    /// one conversation.
+    #[wasm_bindgen]
+    pub fn compare_threads(&self, thread_a: &str, thread_b: &str) -> Result<JsValue, JsValue> {
+        let a = self
+            .threads
+            .get(thread_a)
+            .ok_or_else(|| JsValue::from_str(&format!("Thread not found: {}", thread_a)))?;
+        let b = self
+            .threads
+            .get(thread_b)
+            .ok_or_else(|| JsValue::from_str(&format!("Thread not found: {}", thread_b)))?;
+
+        let comparison = compare(thread_a, a, thread_b, b);
+        self.to_js(&comparison)
+    }
+
+    /// Pairs of threads holding the same messages (by Message-ID or hash)
+    /// under different thread ids, most confident first. Pairs below
+    /// `min_confidence` (default 0.5) are left out.
+    #[wasm_bindgen]
+    pub fn get_merge_candidates(&self, min_confidence: Option<f64>) -> Result<JsValue, JsValue> {
+        let candidates = self.merge_candidates(min_confidence.unwrap_or(0.5));
+        console_log!("Found {} merge candidates", candidates.len());
+        self.to_js(&candidates)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn merge_candidates(&self, min_confidence: f64) -> Vec<MergeCandidate> {
+        let thread_ids: Vec<&String> = self.threads.keys().collect();
+        let keys: Vec<HashSet<String>> = self.threads.values().map(|emails| emails.iter().flat_map(message_keys).collect()).collect();
+
+        // Only threads sharing at least one key are compared
+        let mut by_key: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, thread_keys) in keys.iter().enumerate() {
+            for key in thread_keys {
+                by_key.entry(key.as_str()).or_default().push(i);
+            }
+        }
+        let mut pairs: BTreeSet<(usize, usize)> = BTreeSet::new();
+        for threads in by_key.values().filter(|t| t.len() > 1) {
+            for (n, &a) in threads.iter().enumerate() {
+                for &b in &threads[n + 1..] {
+                    pairs.insert((a.min(b), a.max(b)));
+                }
+            }
+        }
+
+        let shared = |emails: &[EmailMessage], other: &HashSet<String>| {
+            emails.iter().filter(|e| message_keys(e).iter().any(|k| other.contains(k))).count()
+        };
+        let mut candidates: Vec<MergeCandidate> = pairs
+            .into_iter()
+            .filter_map(|(a, b)| {
+                let emails_a = &self.threads[a];
+                let emails_b = &self.threads[b];
+                let shared_a = shared(emails_a, &keys[b]);
+                let shared_b = shared(emails_b, &keys[a]);
+                let confidence = (shared_a as f64 / emails_a.len() as f64).max(shared_b as f64 / emails_b.len() as f64);
+                (confidence >= min_confidence).then(|| MergeCandidate {
+                    thread_a: thread_ids[a].clone(),
+                    thread_b: thread_ids[b].clone(),
+                    size_a: emails_a.len(),
+                    size_b: emails_b.len(),
+                    shared_a,
+                    shared_b,
+                    confidence,
+                })
+            })
+            .collect();
+        candidates.sort_by(|x, y| y.confidence.total_cmp(&x.confidence).then_with(|| x.thread_a.cmp(&y.thread_a)));
+        candidates
+    }
+}
+
+fn message_keys(email: &EmailMessage) -> Vec<String> {
+    let mut keys = Vec::new();
+    if !email.message_id.is_empty() {
+        keys.push(format!("mid:{}", email.message_id));
+    }
+    if !email.hash.is_empty() {
+        keys.push(format!("hash:{}", email.hash.to_lowercase()));
+    }
+    keys
+}
+
+pub(crate) fn compare(id_a: &str, a: &[EmailMessage], id_b: &str, b: &[EmailMessage]) -> ThreadComparison {
+    let participants_a = participants(a);
+    let participants_b = participants(b);
+    let range_a = date_range(a);
+    let range_b = date_range(b);
+    let overlap_start = range_a.start.max(range_b.start);
+    let overlap_end = range_a.end.min(range_b.end);
+
+    let mut duplicated_messages = Vec::new();
+    let mut shared_quotes = Vec::new();
+    for x in a {
+        for y in b {
+            if let Some(matched_on) = duplicate_match(x, y) {
+                duplicated_messages.push(MessagePair {
+                    email_a: x.id.clone(),
+                    email_b: y.id.clone(),
+                    matched_on: matched_on.to_string(),
+                });
+                continue;
+            }
+            let quoted = |earlier: &EmailMessage, later: &EmailMessage| {
+                !inclusive::new_content(&earlier.full_text).trim().is_empty()
+                    && inclusive::is_contained(&earlier.full_text, &later.full_text)
+            };
+            let direction = if quoted(x, y) {
+                Some("a_in_b")
+            } else if quoted(y, x) {
+                Some("b_in_a")
+            } else {
+                None
+            };
+            if let Some(direction) = direction {
+                shared_quotes.push(MessagePair {
+                    email_a: x.id.clone(),
+                    email_b: y.id.clone(),
+                    matched_on: direction.to_string(),
+                });
+            }
+        }
+    }
+
+    ThreadComparison {
+        thread_a: id_a.to_string(),
+        thread_b: id_b.to_string(),
+        shared_participants: participants_a.intersection(&participants_b).cloned().collect(),
+        participants_only_a: participants_a.difference(&participants_b).cloned().collect(),
+        participants_only_b: participants_b.difference(&participants_a).cloned().collect(),
+        date_overlap: (overlap_start <= overlap_end).then_some(DateRange {
+            start: overlap_start,
+            end: overlap_end,
+        }),
+        date_range_a: range_a,
+        date_range_b: range_b,
+        duplicated_messages,
+        shared_quotes,
+    }
+}
+
+pub(crate) fn duplicate_match(x: &EmailMessage, y: &EmailMessage) -> Option<&'static str> {
+    if !x.message_id.is_empty() && x.message_id == y.message_id {
+        Some("message_id")
+    } else if !x.hash.is_empty() && x.hash.eq_ignore_ascii_case(&y.hash) {
+        Some("hash")
+    } else {
+        None
+    }
+}
+
+pub(crate) fn participants(emails: &[EmailMessage]) -> BTreeSet<String> {
+    emails
+        .iter()
+        .flat_map(|e| std::iter::once(&e.from).chain(&e.to).chain(&e.cc))
+        .map(|a| a.trim().to_lowercase())
+        .filter(|a| !a.is_empty())
+        .collect()
+}
+
+fn date_range(emails: &[EmailMessage]) -> DateRange {
+    DateRange::of(emails).unwrap_or_default()
+}
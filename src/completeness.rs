@@ -0,0 +1,94 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use wasm_bindgen::prelude::*;
+
+// Silence between consecutive emails long enough to suggest missing messages
+const DATE_GAP_DAYS: i64 = 30;
+
+/// How much of a conversation was produced, judged from its own headers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Completeness {
+    // 1.0 with nothing missing, falling toward 0 as gaps outnumber emails:
+    // emails / (emails + ghosts + (missing references + date gaps) / 2)
+    pub score: f64,
+    // Emails replying to a message that is not in the thread
+    pub missing_reference_count: usize,
+    // Distinct Message-IDs referenced (In-Reply-To or References) but not
+    // produced; each stands for a ghost node in the reconstructed tree
+    pub ghost_count: usize,
+    pub ghost_message_ids: Vec<String>,
+    // Consecutive emails more than DATE_GAP_DAYS apart
+    pub date_gap_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadCompleteness {
+    pub thread_id: String,
+    pub subject: String,
+    pub email_count: usize,
+    pub completeness: Completeness,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Threads ranked least complete first, to prioritize requests for
+    /// unproduced messages. Threads scoring 1.0 are left out.
+    #[wasm_bindgen]
+    pub fn get_completeness_ranking(&self, limit: Option<usize>) -> Result<JsValue, JsValue> {
+        let mut ranking: Vec<ThreadCompleteness> = self
+            .visible_threads()
+            .map(|(thread_id, emails)| ThreadCompleteness {
+                thread_id: thread_id.clone(),
+                subject: emails.first().map(|e| e.subject.clone()).unwrap_or_default(),
+                email_count: emails.len(),
+                completeness: completeness(emails),
+            })
+            .filter(|t| t.completeness.score < 1.0)
+            .collect();
+        ranking.sort_by(|a, b| {
+            a.completeness
+                .score
+                .total_cmp(&b.completeness.score)
+                .then_with(|| b.completeness.ghost_count.cmp(&a.completeness.ghost_count))
+                .then_with(|| a.thread_id.cmp(&b.thread_id))
+        });
+        ranking.truncate(limit.unwrap_or(ranking.len()));
+        self.to_js(&ranking)
+    }
+}
+
+/// Completeness of one thread's emails, sorted by date.
+pub(crate) fn completeness(emails: &[EmailMessage]) -> Completeness {
+    let produced: HashSet<&str> = emails
+        .iter()
+        .map(|e| e.message_id.as_str())
+        .filter(|id| !id.is_empty())
+        .collect();
+    let absent = |id: &&str| !id.is_empty() && !produced.contains(id);
+
+    let missing_reference_count = emails
+        .iter()
+        .filter(|e| e.in_reply_to.as_deref().is_some_and(|p| absent(&p)))
+        .count();
+    let ghost_message_ids: BTreeSet<&str> = emails
+        .iter()
+        .flat_map(|e| e.in_reply_to.as_deref().into_iter().chain(e.references.iter().map(|r| r.as_str())))
+        .filter(absent)
+        .collect();
+    let date_gap_count = emails
+        .windows(2)
+        .filter(|pair| pair[1].date_sent - pair[0].date_sent > Duration::days(DATE_GAP_DAYS))
+        .count();
+
+    let n = emails.len() as f64;
+    let penalty = ghost_message_ids.len() as f64 + (missing_reference_count + date_gap_count) as f64 / 2.0;
+    Completeness {
+        score: if n == 0.0 { 1.0 } else { n / (n + penalty) },
+        missing_reference_count,
+        ghost_count: ghost_message_ids.len(),
+        ghost_message_ids: ghost_message_ids.into_iter().map(String::from).collect(),
+        date_gap_count,
+    }
+}
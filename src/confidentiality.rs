@@ -0,0 +1,77 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use wasm_bindgen::prelude::*;
+
+/// Common vendor spellings, keyed as `designation_key` reduces them.
+pub(crate) fn default_confidentiality_map() -> IndexMap<String, String> {
+    [
+        ("CONF", "Confidential"),
+        ("Confidential", "Confidential"),
+        ("HC", "Highly Confidential"),
+        ("Highly Confidential", "Highly Confidential"),
+        ("AEO", "Highly Confidential - Attorneys' Eyes Only"),
+        ("Attorneys Eyes Only", "Highly Confidential - Attorneys' Eyes Only"),
+        ("Highly Confidential - Attorneys Eyes Only", "Highly Confidential - Attorneys' Eyes Only"),
+        ("HC-AEO", "Highly Confidential - Attorneys' Eyes Only"),
+    ]
+    .iter()
+    .map(|(raw, canonical)| (designation_key(raw), canonical.to_string()))
+    .collect()
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Maps confidentiality values as vendors write them to one canonical
+    /// designation, e.g. `{"CONF": "Confidential"}`. Case, spacing and
+    /// punctuation are ignored; values not in the map are kept as given.
+    /// Replaces the previous map, including the defaults, and re-normalizes
+    /// loaded emails; the value as loaded stays in `confidentiality_raw`.
+    #[wasm_bindgen]
+    pub fn set_confidentiality_map(&mut self, map: JsValue) -> Result<(), JsValue> {
+        let map: IndexMap<String, String> = serde_wasm_bindgen::from_value(map)?;
+        self.confidentiality_map = map
+            .iter()
+            .map(|(raw, canonical)| (designation_key(raw), canonical.trim().to_string()))
+            .filter(|(raw, _)| !raw.is_empty())
+            .collect();
+
+        let mut emails = std::mem::take(&mut self.emails);
+        self.normalize_confidentiality(&mut emails);
+        self.replace_emails(emails);
+        self.refresh_thread_copies();
+        console_log!("Confidentiality map set with {} values", self.confidentiality_map.len());
+        self.audit("set_confidentiality_map", format!("{} values mapped", self.confidentiality_map.len()));
+        Ok(())
+    }
+
+    /// The map in effect, keyed by the reduced form values are matched on.
+    #[wasm_bindgen]
+    pub fn get_confidentiality_map(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.confidentiality_map)
+    }
+}
+
+impl EmailThreadProcessor {
+    /// Sets each email's `confidentiality` to the canonical designation of its
+    /// raw value, recording the raw value first if not yet kept.
+    pub(crate) fn normalize_confidentiality(&self, emails: &mut [EmailMessage]) {
+        for email in emails {
+            if email.confidentiality_raw.is_empty() {
+                email.confidentiality_raw = email.confidentiality.clone();
+            }
+            email.confidentiality = self.canonical_confidentiality(&email.confidentiality_raw);
+        }
+    }
+
+    pub(crate) fn canonical_confidentiality(&self, value: &str) -> String {
+        self.confidentiality_map
+            .get(&designation_key(value))
+            .cloned()
+            .unwrap_or_else(|| value.trim().to_string())
+    }
+}
+
+// Lowercase letters and digits only, so "HC - AEO" and "hc/aeo" match
+fn designation_key(value: &str) -> String {
+    value.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase()
+}
@@ -0,0 +1,91 @@
+use crate::{domains, EmailMessage, EmailThreadProcessor, ThreadingMode};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Which copies of the same message `group_by_threads` keeps in a thread.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupPolicy {
+    // Every copy, e.g. one per custodian
+    #[default]
+    None,
+    // The first email with each Message-ID
+    MessageId,
+    // The first email with each hash
+    Hash,
+}
+
+/// Every setting that changes how threads are built, so a processing run can
+/// be documented and repeated.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThreadingConfig {
+    pub algorithm: ThreadingMode,
+    // Group emails with no thread id by normalized subject
+    pub subject_fallback: bool,
+    pub dedup_policy: DedupPolicy,
+    pub internal_domains: Vec<String>,
+    // Extra chrono formats tried after RFC 3339 when parsing dates
+    pub date_formats: Vec<String>,
+    // Golden-run mode: threads in id order and ties broken by email id, so
+    // identical input and config always give the same output checksum
+    pub deterministic: bool,
+    // Name threads by a hash of their root's Message-ID (or normalized
+    // subject), so ids survive reprocessing an updated corpus
+    pub stable_thread_ids: bool,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Replaces all threading settings with a `ThreadingConfig` object;
+    /// omitted fields take their defaults. Takes effect on the next load or
+    /// `group_by_threads`.
+    #[wasm_bindgen]
+    pub fn set_threading_config(&mut self, config: JsValue) -> Result<(), JsValue> {
+        let config: ThreadingConfig = serde_wasm_bindgen::from_value(config)?;
+        self.threading_mode = config.algorithm;
+        self.subject_fallback = config.subject_fallback;
+        self.dedup_policy = config.dedup_policy;
+        self.internal_domains = domains::normalize_domains(&config.internal_domains);
+        self.date_formats = config.date_formats;
+        self.deterministic = config.deterministic;
+        self.stable_thread_ids = config.stable_thread_ids;
+
+        let details = serde_json::to_string(&self.threading_config()).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        console_log!("Threading config set: {}", details);
+        self.audit("set_threading_config", details);
+        Ok(())
+    }
+
+    /// The settings in effect, as a `ThreadingConfig`.
+    #[wasm_bindgen]
+    pub fn get_config(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.threading_config())
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn threading_config(&self) -> ThreadingConfig {
+        ThreadingConfig {
+            algorithm: self.threading_mode,
+            subject_fallback: self.subject_fallback,
+            dedup_policy: self.dedup_policy,
+            internal_domains: self.internal_domains.clone(),
+            date_formats: self.date_formats.clone(),
+            deterministic: self.deterministic,
+            stable_thread_ids: self.stable_thread_ids,
+        }
+    }
+}
+
+impl DedupPolicy {
+    /// The key duplicates share under this policy; None keeps the email.
+    pub(crate) fn key<'a>(&self, email: &'a EmailMessage) -> Option<&'a str> {
+        let key = match self {
+            DedupPolicy::None => return None,
+            DedupPolicy::MessageId => email.message_id.as_str(),
+            DedupPolicy::Hash => email.hash.as_str(),
+        };
+        Some(key.trim()).filter(|k| !k.is_empty())
+    }
+}
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+
+use crate::msg::{filetime_to_datetime, to_hex};
+use crate::rfc5322::{decode_base64, encode_base64};
+
+// Outlook Conversation Index layout: a 22-byte header (the top six bytes of a
+// FILETIME, the first being the "reserved" 0x01, then a 16-byte conversation
+// GUID) followed by one 5-byte block per reply level carrying a time delta.
+const HEADER_LEN: usize = 22;
+const BLOCK_LEN: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ConversationIndex {
+    pub bytes: Vec<u8>,
+}
+
+impl ConversationIndex {
+    /// Accepts the hex form we store on `EmailMessage` or the base64 form used
+    /// by the Thread-Index header and most load files.
+    pub fn parse(value: &str) -> Option<ConversationIndex> {
+        let value = value.trim();
+        let valid = |b: &Vec<u8>| b.len() >= HEADER_LEN && (b.len() - HEADER_LEN).is_multiple_of(BLOCK_LEN);
+        let bytes = decode_hex(value)
+            .filter(valid)
+            .or_else(|| decode_base64(value).filter(valid))?;
+        Some(ConversationIndex { bytes })
+    }
+
+    pub fn guid(&self) -> String {
+        to_hex(&self.bytes[6..HEADER_LEN]).to_uppercase()
+    }
+
+    pub fn depth(&self) -> usize {
+        (self.bytes.len() - HEADER_LEN) / BLOCK_LEN
+    }
+
+    pub fn parent(&self) -> Option<ConversationIndex> {
+        if self.depth() == 0 {
+            return None;
+        }
+        Some(ConversationIndex {
+            bytes: self.bytes[..self.bytes.len() - BLOCK_LEN].to_vec(),
+        })
+    }
+
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.bytes)
+    }
+
+    /// The form the Thread-Index header carries.
+    pub fn to_base64(&self) -> String {
+        encode_base64(&self.bytes)
+    }
+
+    /// Time the message at this level was created, per the index itself.
+    pub fn timestamp(&self) -> Option<DateTime<Utc>> {
+        let mut filetime = self.bytes[0..6]
+            .iter()
+            .fold(0u64, |acc, &b| (acc << 8) | b as u64)
+            << 16;
+
+        for block in self.bytes[HEADER_LEN..].chunks_exact(BLOCK_LEN) {
+            let word = u32::from_be_bytes([block[0], block[1], block[2], block[3]]);
+            let delta = (word & 0x7FFF_FFFF) as u64;
+            filetime += if word & 0x8000_0000 == 0 { delta << 18 } else { delta << 23 };
+        }
+
+        filetime_to_datetime(filetime)
+    }
+}
+
+/// Normalizes a conversation index from any supported encoding to hex.
+pub(crate) fn normalize(value: &str) -> Option<String> {
+    ConversationIndex::parse(value).map(|ci| ci.to_hex())
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) || !value.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).ok())
+        .collect()
+}
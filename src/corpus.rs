@@ -0,0 +1,160 @@
+use crate::paging::PageRequest;
+use crate::{custodians, filetypes, DateRange, EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadSummary {
+    pub thread_id: String,
+    pub subject: String,
+    pub email_count: usize,
+    pub participant_count: usize,
+    pub custodians: Vec<String>,
+    pub labels: Vec<String>,
+    pub date_range: DateRange,
+    // The first and last emails sent, e.g. to show who replied most recently
+    pub earliest_activity: Option<ThreadActivity>,
+    pub latest_activity: Option<ThreadActivity>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadActivity {
+    pub email_id: String,
+    pub from: String,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub date_sent: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorpusStats {
+    pub email_count: usize,
+    pub thread_count: usize,
+    // Threads holding a single email; counted even when hidden from listings
+    pub singleton_thread_count: usize,
+    pub unthreaded_count: usize,
+    pub custodian_count: usize,
+    pub date_range: Option<DateRange>,
+    // Emails held back by the type filter; not in any count above but email_count
+    pub excluded_count: usize,
+    // Likewise for emails outside the global filter
+    pub filtered_count: usize,
+    // And for emails withdrawn by sequester_emails
+    pub sequestered_count: usize,
+    // Over the emails both filters keep
+    pub types: filetypes::TypeBreakdown,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    // Emails the type filter and global filter keep, in load order
+    pub(crate) fn included_emails(&self) -> impl Iterator<Item = &EmailMessage> {
+        self.emails.iter().filter(|e| self.in_scope(e))
+    }
+
+    /// Emails that no thread key could be derived for under the current
+    /// threading mode. These never appear in `get_thread_ids`. Emails the type
+    /// filter excludes are not listed.
+    #[wasm_bindgen]
+    pub fn get_unthreaded_emails(&self) -> Result<JsValue, JsValue> {
+        let unthreaded: Vec<&EmailMessage> = self.included_emails().filter(|e| self.thread_key(e).is_none()).collect();
+        self.to_js(&unthreaded)
+    }
+
+    /// Whether threads with a single email are listed by `get_thread_ids` and
+    /// `get_thread_summaries` (default true).
+    #[wasm_bindgen]
+    pub fn set_include_singleton_threads(&mut self, include: bool) {
+        self.include_singletons = include;
+    }
+
+    #[wasm_bindgen]
+    pub fn get_include_singleton_threads(&self) -> bool {
+        self.include_singletons
+    }
+
+    /// A page of thread summaries (all of them when `page` is null); only
+    /// the threads on the page are summarized. `sort` is "last_activity",
+    /// "start_date", "size", "depth", "participant_count" or "subject",
+    /// ascending, or descending with a leading `-`; by default threads keep
+    /// grouping order.
+    #[wasm_bindgen]
+    pub fn get_thread_summaries(&self, page: JsValue, sort: Option<String>) -> Result<JsValue, JsValue> {
+        let threads = self.sorted_threads(sort.as_deref())?;
+        let summaries = PageRequest::parse(page)?
+            .apply(threads, |&(thread_id, _)| thread_id.as_str())?
+            .map(|(thread_id, emails)| self.summarize(thread_id, emails));
+        self.to_js(&summaries)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_corpus_stats(&self) -> Result<JsValue, JsValue> {
+        let custodians: HashSet<&str> = self.included_emails().flat_map(custodians::custodians_of).collect();
+        let start = self.included_emails().map(|e| e.date_sent).min();
+        let end = self.included_emails().map(|e| e.date_sent).max();
+        let mut types = filetypes::TypeBreakdown::default();
+        for email in self.included_emails() {
+            types.add(email);
+        }
+
+        let stats = CorpusStats {
+            email_count: self.emails.len(),
+            thread_count: self.threads.len(),
+            singleton_thread_count: self.threads.values().filter(|emails| emails.len() == 1).count(),
+            unthreaded_count: self.included_emails().filter(|e| self.thread_key(e).is_none()).count(),
+            custodian_count: custodians.len(),
+            date_range: start.zip(end).map(|(start, end)| DateRange { start, end }),
+            excluded_count: self.emails.iter().filter(|e| self.type_excluded(e)).count(),
+            filtered_count: self.emails.iter().filter(|e| self.outside_global_filter(e)).count(),
+            sequestered_count: self.emails.iter().filter(|e| self.is_sequestered(&e.id)).count(),
+            types: types.sorted(),
+        };
+        self.to_js(&stats)
+    }
+}
+
+impl EmailThreadProcessor {
+    // Threads as listed to callers, honouring set_include_singleton_threads
+    pub(crate) fn visible_threads(&self) -> impl Iterator<Item = (&String, &Vec<EmailMessage>)> {
+        self.threads
+            .iter()
+            .filter(|(_, emails)| self.include_singletons || emails.len() > 1)
+    }
+
+    pub(crate) fn summarize(&self, thread_id: &str, emails: &[EmailMessage]) -> ThreadSummary {
+        let mut participants = HashSet::new();
+        let mut custodians: Vec<String> = Vec::new();
+        for email in emails {
+            participants.insert(email.from.as_str());
+            participants.extend(email.to.iter().map(|a| a.as_str()));
+            participants.extend(email.cc.iter().map(|a| a.as_str()));
+            for custodian in custodians::custodians_of(email) {
+                if !custodians.iter().any(|c| c == custodian) {
+                    custodians.push(custodian.to_string());
+                }
+            }
+        }
+
+        ThreadSummary {
+            thread_id: thread_id.to_string(),
+            subject: emails.first().map(|e| e.subject.clone()).unwrap_or_default(),
+            email_count: emails.len(),
+            participant_count: participants.len(),
+            custodians,
+            labels: self.get_thread_labels(thread_id),
+            date_range: DateRange::of(emails).unwrap_or_default(),
+            // On a tie, earliest is the first in thread order and latest the last
+            earliest_activity: emails.iter().min_by_key(|e| e.date_sent).map(activity),
+            latest_activity: emails.iter().max_by_key(|e| e.date_sent).map(activity),
+        }
+    }
+}
+
+fn activity(email: &EmailMessage) -> ThreadActivity {
+    ThreadActivity {
+        email_id: email.id.clone(),
+        from: email.from.clone(),
+        date_sent: email.date_sent,
+    }
+}
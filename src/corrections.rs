@@ -0,0 +1,212 @@
+use crate::{custodians, network, opticon, validation, EmailMessage, EmailThreadProcessor};
+use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
+use wasm_bindgen::prelude::*;
+
+/// Fields `update_email` may correct; absent fields are left alone. Dates
+/// are read like the load file's, with the configured date formats, and
+/// address fields take the same "Name <address>" forms.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailPatch {
+    pub date_sent: Option<String>,
+    pub custodian: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<Vec<String>>,
+    pub cc: Option<Vec<String>>,
+    pub bcc: Option<Vec<String>>,
+    pub subject: Option<String>,
+    pub message_id: Option<String>,
+    // An empty string clears it
+    pub in_reply_to: Option<String>,
+    pub references: Option<Vec<String>>,
+    pub thread_id: Option<String>,
+    pub confidentiality: Option<String>,
+    pub beg_bates: Option<String>,
+    pub end_bates: Option<String>,
+    pub file_type: Option<String>,
+    pub folder: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailUpdate {
+    pub email_id: String,
+    // Only fields whose value actually changed
+    pub changes: Vec<FieldChange>,
+    // Whether threads were regrouped, rather than updated in place
+    pub rethreaded: bool,
+    pub thread_id: Option<String>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Corrects metadata on one email from a JSON `EmailPatch`, e.g.
+    /// `{"date_sent": "2021-03-04T10:00:00Z", "custodian": "Smith, J"}`.
+    /// The patch is validated as a whole before anything changes. Threads
+    /// are regrouped only when the edit moves the email to another thread,
+    /// in or out of scope, or changes its date, Message-ID or hash (which
+    /// order, dedup and name threads); other edits update the thread copies
+    /// in place. Each change
+    /// is recorded in the audit log with its old and new value.
+    #[wasm_bindgen]
+    pub fn update_email(&mut self, id: &str, patch_json: &str) -> Result<JsValue, JsValue> {
+        let patch: EmailPatch =
+            serde_json::from_str(patch_json).map_err(|e| JsValue::from_str(&format!("Invalid email patch: {}", e)))?;
+        let idx = self.email_index(id).ok_or_else(|| JsValue::from_str(&format!("Email not found: {}", id)))?;
+        let before = self.emails[idx].clone();
+        let mut after = before.clone();
+        let changes = self.apply_patch(&mut after, &patch).map_err(|e| JsValue::from_str(&e))?;
+        if changes.is_empty() {
+            return self.to_js(&EmailUpdate {
+                email_id: id.to_string(),
+                changes,
+                rethreaded: false,
+                thread_id: self.thread_key(&before),
+            });
+        }
+
+        let rethreaded = self.thread_key(&before) != self.thread_key(&after)
+            || self.in_scope(&before) != self.in_scope(&after)
+            || before.date_sent != after.date_sent
+            || before.message_id != after.message_id
+            || before.hash != after.hash;
+        self.emails[idx] = after;
+        self.search_index = OnceCell::new();
+        self.duplicate_keys = OnceCell::new();
+        if rethreaded {
+            self.group_by_threads();
+        } else {
+            self.refresh_thread_copies();
+        }
+
+        let details: Vec<String> =
+            changes.iter().map(|c| format!("{}: \"{}\" -> \"{}\"", c.field, c.old, c.new)).collect();
+        self.audit("update_email", format!("{}: {}", id, details.join("; ")));
+        let update = EmailUpdate {
+            email_id: id.to_string(),
+            changes,
+            rethreaded,
+            thread_id: self.thread_key(&self.emails[idx]),
+        };
+        self.to_js(&update)
+    }
+}
+
+impl EmailThreadProcessor {
+    // Applies every field of the patch to `email`, failing on the first
+    // invalid value
+    fn apply_patch(&self, email: &mut EmailMessage, patch: &EmailPatch) -> Result<Vec<FieldChange>, String> {
+        let mut changes = Vec::new();
+        let mut change = |field: &str, old: String, new: String| {
+            if old != new {
+                changes.push(FieldChange {
+                    field: field.to_string(),
+                    old,
+                    new,
+                });
+            }
+        };
+
+        if let Some(value) = &patch.date_sent {
+            let date = self.parse_date(value.trim(), "date_sent")?;
+            change("date_sent", email.date_sent.to_rfc3339(), date.to_rfc3339());
+            email.date_sent = date;
+        }
+        if let Some(value) = &patch.custodian {
+            let custodian = value.trim().to_string();
+            if custodian.is_empty() {
+                return Err("custodian is empty".to_string());
+            }
+            change("custodian", email.custodian.clone(), custodian.clone());
+            // The new primary replaces the old one, once, ahead of the rest
+            email.all_custodians =
+                custodians::merge_custodians(&custodian, email.all_custodians.iter().skip(1).map(String::as_str));
+            email.custodian = custodian;
+        }
+        if let Some(value) = &patch.from {
+            check_addresses("from", std::slice::from_ref(value))?;
+            change("from", email.from.clone(), value.trim().to_string());
+            email.from = value.trim().to_string();
+        }
+        for (field, value, target) in
+            [("to", &patch.to, &mut email.to), ("cc", &patch.cc, &mut email.cc), ("bcc", &patch.bcc, &mut email.bcc)]
+        {
+            if let Some(value) = value {
+                check_addresses(field, value)?;
+                let value: Vec<String> = value.iter().map(|v| v.trim().to_string()).collect();
+                change(field, target.join("; "), value.join("; "));
+                *target = value;
+            }
+        }
+        if let Some(value) = &patch.subject {
+            change("subject", email.subject.clone(), value.clone());
+            email.subject = value.clone();
+        }
+        if let Some(value) = &patch.message_id {
+            change("message_id", email.message_id.clone(), value.trim().to_string());
+            email.message_id = value.trim().to_string();
+        }
+        if let Some(value) = &patch.in_reply_to {
+            let value = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+            change("in_reply_to", email.in_reply_to.clone().unwrap_or_default(), value.clone().unwrap_or_default());
+            email.in_reply_to = value;
+        }
+        if let Some(value) = &patch.references {
+            let value: Vec<String> = value.iter().map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect();
+            change("references", email.references.join(" "), value.join(" "));
+            email.references = value;
+        }
+        if let Some(value) = &patch.thread_id {
+            change("thread_id", email.thread_id.clone(), value.trim().to_string());
+            email.thread_id = value.trim().to_string();
+        }
+        if let Some(value) = &patch.confidentiality {
+            let canonical = self.canonical_confidentiality(value);
+            change("confidentiality", email.confidentiality.clone(), canonical.clone());
+            email.confidentiality_raw = value.trim().to_string();
+            email.confidentiality = canonical;
+        }
+        if let Some(value) = &patch.beg_bates {
+            change("beg_bates", email.beg_bates.clone(), value.trim().to_string());
+            email.beg_bates = value.trim().to_string();
+        }
+        if let Some(value) = &patch.end_bates {
+            change("end_bates", email.end_bates.clone(), value.trim().to_string());
+            email.end_bates = value.trim().to_string();
+        }
+        if let (Some((beg_prefix, lo)), Some((end_prefix, hi))) =
+            (opticon::split_bates(email.beg_bates.trim()), opticon::split_bates(email.end_bates.trim()))
+        {
+            if beg_prefix != end_prefix || hi < lo {
+                return Err(format!("Invalid Bates range: {} - {}", email.beg_bates, email.end_bates));
+            }
+        }
+        if let Some(value) = &patch.file_type {
+            change("file_type", email.file_type.clone(), value.trim().to_string());
+            email.file_type = value.trim().to_string();
+        }
+        if let Some(value) = &patch.folder {
+            change("folder", email.folder.clone(), value.clone());
+            email.folder = value.clone();
+        }
+        Ok(changes)
+    }
+}
+
+// Every entry must hold at least one address, and only well-formed ones
+fn check_addresses(field: &str, values: &[String]) -> Result<(), String> {
+    for value in values {
+        let addresses = network::identities(value);
+        if addresses.is_empty() || !addresses.iter().all(|a| validation::looks_like_address(a)) {
+            return Err(format!("Invalid address in {}: {}", field, value));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use crate::{filetypes, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustodianStats {
+    pub custodian: String,
+    // Documents this custodian held, whether as primary or duplicate custodian
+    pub document_count: usize,
+    pub primary_count: usize,
+    pub duplicate_count: usize,
+    // Documents no other custodian held
+    pub unique_count: usize,
+    pub thread_count: usize,
+    pub types: filetypes::TypeBreakdown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupEntry {
+    pub id: String,
+    pub beg_bates: String,
+    pub hash: String,
+    pub custodian: String,
+    pub all_custodians: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashGroup {
+    pub hash: String,
+    pub ids: Vec<String>,
+    pub custodians: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupReport {
+    pub total_documents: usize,
+    // Documents produced once on behalf of several custodians
+    pub deduplicated_documents: usize,
+    // Copies suppressed by deduplication, i.e. custodians beyond the first
+    pub suppressed_copies: usize,
+    pub documents: Vec<DedupEntry>,
+    // Hashes still shared by more than one produced document
+    pub duplicate_hashes: Vec<HashGroup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustodianOverlap {
+    pub custodians: Vec<String>,
+    // documents[i][j]: deduplicated documents custodians i and j both held;
+    // the diagonal is each custodian's own document count
+    pub documents: Vec<Vec<usize>>,
+    // threads[i][j]: threads both custodians hold documents in
+    pub threads: Vec<Vec<usize>>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Per-custodian document counts, including documents held only as a
+    /// duplicate custodian.
+    #[wasm_bindgen]
+    pub fn get_custodian_stats(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.custodian_stats())
+    }
+
+    /// Pairwise document and thread overlap between custodians, as matrices
+    /// in the order of `custodians` (most documents first) for a heat map.
+    #[wasm_bindgen]
+    pub fn get_custodian_overlap(&self) -> Result<JsValue, JsValue> {
+        let custodians: Vec<String> = self.custodian_stats().into_iter().map(|s| s.custodian).collect();
+        let index: HashMap<&str, usize> = custodians.iter().enumerate().map(|(i, c)| (c.as_str(), i)).collect();
+        let n = custodians.len();
+        let mut documents = vec![vec![0; n]; n];
+        let mut threads: Vec<HashSet<String>> = vec![HashSet::new(); n];
+
+        for email in self.included_emails() {
+            let held: Vec<usize> = custodians_of(email).iter().filter_map(|c| index.get(c).copied()).collect();
+            for &a in &held {
+                for &b in &held {
+                    documents[a][b] += 1;
+                }
+                if let Some(thread_id) = self.thread_key(email) {
+                    threads[a].insert(thread_id);
+                }
+            }
+        }
+
+        let overlap = CustodianOverlap {
+            documents,
+            threads: threads
+                .iter()
+                .map(|a| threads.iter().map(|b| a.intersection(b).count()).collect())
+                .collect(),
+            custodians,
+        };
+        self.to_js(&overlap)
+    }
+
+    /// Deduplication summary: which custodians held each deduplicated
+    /// document, plus any hashes that still appear on several documents.
+    #[wasm_bindgen]
+    pub fn get_dedup_report(&self) -> Result<JsValue, JsValue> {
+        let documents: Vec<DedupEntry> = self
+            .emails
+            .iter()
+            .filter(|e| e.all_custodians.len() > 1 && !self.filtered_out(e))
+            .map(|e| DedupEntry {
+                id: e.id.clone(),
+                beg_bates: e.beg_bates.clone(),
+                hash: e.hash.clone(),
+                custodian: e.custodian.clone(),
+                all_custodians: e.all_custodians.clone(),
+            })
+            .collect();
+
+        let mut by_hash: IndexMap<&str, HashGroup> = IndexMap::new();
+        for email in self.emails.iter().filter(|e| !e.hash.is_empty() && !self.filtered_out(e)) {
+            let group = by_hash.entry(&email.hash).or_insert_with(|| HashGroup {
+                hash: email.hash.clone(),
+                ids: Vec::new(),
+                custodians: Vec::new(),
+            });
+            group.ids.push(email.id.clone());
+            for custodian in custodians_of(email) {
+                if !group.custodians.iter().any(|c| c == custodian) {
+                    group.custodians.push(custodian.to_string());
+                }
+            }
+        }
+
+        let report = DedupReport {
+            total_documents: self.emails.len(),
+            deduplicated_documents: documents.len(),
+            suppressed_copies: documents.iter().map(|d| d.all_custodians.len() - 1).sum(),
+            documents,
+            duplicate_hashes: by_hash.into_values().filter(|g| g.ids.len() > 1).collect(),
+        };
+
+        self.to_js(&report)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_custodians_for_email(&self, email_id: &str) -> Result<Vec<String>, JsValue> {
+        self.emails
+            .iter()
+            .find(|e| e.id == email_id)
+            .map(|e| custodians_of(e).into_iter().map(|c| c.to_string()).collect())
+            .ok_or_else(|| JsValue::from_str(&format!("Email not found: {}", email_id)))
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn custodian_stats(&self) -> Vec<CustodianStats> {
+        let mut stats: IndexMap<String, CustodianStats> = IndexMap::new();
+        let mut threads: IndexMap<String, HashSet<String>> = IndexMap::new();
+
+        for email in self.included_emails() {
+            let custodians = custodians_of(email);
+            let thread_key = self.thread_key(email);
+            for (i, custodian) in custodians.iter().enumerate() {
+                let entry = stats.entry(custodian.to_string()).or_insert_with(|| CustodianStats {
+                    custodian: custodian.to_string(),
+                    document_count: 0,
+                    primary_count: 0,
+                    duplicate_count: 0,
+                    unique_count: 0,
+                    thread_count: 0,
+                    types: filetypes::TypeBreakdown::default(),
+                });
+                entry.types.add(email);
+                entry.document_count += 1;
+                if i == 0 {
+                    entry.primary_count += 1;
+                } else {
+                    entry.duplicate_count += 1;
+                }
+                if custodians.len() == 1 {
+                    entry.unique_count += 1;
+                }
+                if let Some(thread_id) = &thread_key {
+                    threads.entry(custodian.to_string()).or_default().insert(thread_id.clone());
+                }
+            }
+        }
+
+        let mut stats: Vec<CustodianStats> = stats
+            .into_values()
+            .map(|mut s| {
+                s.thread_count = threads.get(&s.custodian).map_or(0, |t| t.len());
+                s.types = s.types.sorted();
+                s
+            })
+            .collect();
+        stats.sort_by(|a, b| b.document_count.cmp(&a.document_count).then_with(|| a.custodian.cmp(&b.custodian)));
+
+        stats
+    }
+}
+
+/// Every custodian that held the document, primary first. Falls back to the
+/// primary custodian for sources that never fill `all_custodians`.
+pub(crate) fn custodians_of(email: &crate::EmailMessage) -> Vec<&str> {
+    if email.all_custodians.is_empty() {
+        if email.custodian.is_empty() {
+            Vec::new()
+        } else {
+            vec![email.custodian.as_str()]
+        }
+    } else {
+        email.all_custodians.iter().map(|c| c.as_str()).collect()
+    }
+}
+
+/// Builds the custodian list from the primary custodian and a
+/// DuplicateCustodian value. Names are split on semicolons only, since
+/// "Last, First" is a common custodian format.
+pub(crate) fn merge_custodians<'a>(primary: &str, duplicates: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut custodians: Vec<String> = Vec::new();
+    let names = std::iter::once(primary).chain(duplicates.into_iter().flat_map(|d| d.split(';')));
+    for name in names.map(str::trim).filter(|n| !n.is_empty()) {
+        if !custodians.iter().any(|c| c.eq_ignore_ascii_case(name)) {
+            custodians.push(name.to_string());
+        }
+    }
+    custodians
+}
@@ -0,0 +1,172 @@
+use crate::dialect::{CONCORDANCE_DELIMITER, CONCORDANCE_NEWLINE, CONCORDANCE_QUOTE};
+use crate::overlay::OverlayRow;
+use crate::{classifications, filetypes, redactions, EmailMessage, EmailThreadProcessor};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+// Load file columns, named as the CSV/DAT loader reads them, followed by the
+// computed threading columns of the overlay export
+const DEFAULT_FIELDS: &[&str] = &[
+    "BegBates",
+    "EndBates",
+    "BegAttach",
+    "EndAttach",
+    "Custodian",
+    "DuplicateCustodian",
+    "From",
+    "To",
+    "CC",
+    "BCC",
+    "Subject",
+    "DateSent",
+    "FileName",
+    "FileType",
+    "FileExtension",
+    "ESIType",
+    "DateCreated",
+    "DateLastModified",
+    "Title",
+    "author",
+    "Confidentiality",
+    "Hash",
+    "nativelink",
+    "FullText",
+    "ConversationIndex",
+    "column_history",
+    "ThreadId",
+    "ThreadSortOrder",
+    "ThreadDepth",
+    "ParentBates",
+    "RootBates",
+    "IsInclusive",
+    "ThreadLabels",
+];
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Concordance DAT (þ-quoted, DC4-delimited, ® for line breaks) of the
+    /// loaded corpus with the computed threading columns. `fields` picks and
+    /// orders the columns; any load file column kept from ingestion may be
+    /// named too. With the default fields, dates are RFC 3339 and
+    /// `column_history` carries the message headers, so the file loads back
+    /// with `load_emails_from_bytes`.
+    #[wasm_bindgen]
+    pub fn export_dat(&self, fields: Option<Vec<String>>) -> Result<String, JsValue> {
+        self.reset_cancellation();
+        let fields = fields.unwrap_or_else(|| DEFAULT_FIELDS.iter().map(|f| f.to_string()).collect());
+        if fields.is_empty() {
+            return Err(JsValue::from_str("No fields to export"));
+        }
+        let unknown: Vec<&String> = fields
+            .iter()
+            .filter(|f| !is_known(f) && !self.emails.iter().any(|e| e.extra.contains_key(f.as_str())))
+            .collect();
+        if !unknown.is_empty() {
+            let names: Vec<&str> = unknown.iter().map(|f| f.as_str()).collect();
+            return Err(JsValue::from_str(&format!("Unknown fields: {}", names.join(", "))));
+        }
+
+        let overlay: HashMap<String, OverlayRow> = self.overlay_rows()?.into_iter().map(|r| (r.email_id.clone(), r)).collect();
+        let mut dat = String::new();
+        push_row(&mut dat, fields.iter().map(|f| f.to_string()));
+        let mut count = 0;
+        for email in self.emails.iter().filter(|e| !self.filtered_out(e)) {
+            let row = overlay.get(&email.id);
+            push_row(&mut dat, fields.iter().map(|f| field_value(email, row, f)));
+            count += 1;
+        }
+
+        console_log!("Exported {} emails to DAT with {} fields", count, fields.len());
+        Ok(dat)
+    }
+}
+
+fn is_known(field: &str) -> bool {
+    DEFAULT_FIELDS.contains(&field) || matches!(field, "MessageID" | "InReplyTo" | "Tags" | "ConfidentialityRaw" | "Classifications" | "Production")
+}
+
+fn field_value(email: &EmailMessage, row: Option<&OverlayRow>, field: &str) -> String {
+    let overlay = |value: fn(&OverlayRow) -> String| row.map(value).unwrap_or_default();
+    match field {
+        "BegBates" => email.beg_bates.clone(),
+        "EndBates" => email.end_bates.clone(),
+        "BegAttach" => email.beg_attach.clone(),
+        "EndAttach" => email.end_attach.clone(),
+        "Custodian" => email.custodian.clone(),
+        "DuplicateCustodian" => email.all_custodians.iter().skip(1).cloned().collect::<Vec<_>>().join("; "),
+        "From" => email.from.clone(),
+        "To" => email.to.join(", "),
+        "CC" => email.cc.join(", "),
+        "BCC" => email.bcc.join(", "),
+        "Subject" => email.subject.clone(),
+        "DateSent" => email.date_sent.to_rfc3339(),
+        "FileName" => email.file_name.clone(),
+        "FileType" => email.file_type.clone(),
+        "FileExtension" => filetypes::extension_of(email),
+        "ESIType" => email.esi_type.clone(),
+        "DateCreated" => email.date_created.to_rfc3339(),
+        "DateLastModified" => email.date_last_modified.to_rfc3339(),
+        "Title" => email.title.clone(),
+        "author" => email.author.clone(),
+        "Confidentiality" => email.confidentiality.clone(),
+        "ConfidentialityRaw" => email.confidentiality_raw.clone(),
+        "Hash" => email.hash.clone(),
+        "nativelink" => email.native_link.clone(),
+        "ConversationIndex" => email.conversation_index.clone().unwrap_or_default(),
+        "column_history" => column_history(email),
+        "FullText" => redactions::export_text(email).into_owned(),
+        "MessageID" => email.message_id.clone(),
+        "InReplyTo" => email.in_reply_to.clone().unwrap_or_default(),
+        "Tags" => email.tags.join("; "),
+        "Classifications" => classifications::format_classifications(email),
+        "Production" => email.production.clone(),
+        "ThreadId" => overlay(|r| r.thread_id.clone()),
+        "ThreadSortOrder" => overlay(|r| r.thread_sort_order.map(|o| o.to_string()).unwrap_or_default()),
+        "ThreadDepth" => overlay(|r| r.thread_depth.map(|d| d.to_string()).unwrap_or_default()),
+        "ParentBates" => overlay(|r| r.parent_bates.clone()),
+        "RootBates" => overlay(|r| r.root_bates.clone()),
+        "IsInclusive" => overlay(|r| if r.is_inclusive { "Y" } else { "N" }.to_string()),
+        "ThreadLabels" => overlay(|r| r.thread_labels.join("; ")),
+        other => email.extra.get(other).cloned().unwrap_or_default(),
+    }
+}
+
+// The pipe-separated header summary the loader parses threading info from
+fn column_history(email: &EmailMessage) -> String {
+    let mut parts = Vec::new();
+    if !email.message_id.is_empty() {
+        parts.push(format!("MSG-ID:{}", email.message_id));
+    }
+    if let Some(parent) = &email.in_reply_to {
+        parts.push(format!("IN-REPLY-TO:{}", parent));
+    }
+    if !email.references.is_empty() {
+        parts.push(format!("REFS:{}", email.references.join(" ")));
+    }
+    if !email.thread_id.is_empty() {
+        parts.push(format!("THREAD:{}", email.thread_id));
+    }
+    if let Some(ci) = &email.conversation_index {
+        parts.push(format!("CONV-INDEX:{}", ci));
+    }
+    if email.is_forward {
+        parts.push("FWD:true".to_string());
+    }
+    if email.is_external {
+        parts.push("EXTERNAL:true".to_string());
+    }
+    parts.join("|")
+}
+
+fn push_row(dat: &mut String, values: impl Iterator<Item = String>) {
+    for (i, value) in values.enumerate() {
+        if i > 0 {
+            dat.push(CONCORDANCE_DELIMITER);
+        }
+        dat.push(CONCORDANCE_QUOTE);
+        let value = value.replace("\r\n", "\n").replace(['\r', '\n'], &CONCORDANCE_NEWLINE.to_string());
+        dat.push_str(&value.replace(CONCORDANCE_QUOTE, ""));
+        dat.push(CONCORDANCE_QUOTE);
+    }
+    dat.push_str("\r\n");
+}
@@ -0,0 +1,63 @@
+use crate::{audit, config::ThreadingConfig, EmailThreadProcessor};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+/// What the output checksum covers: the config and, per thread in order, its
+/// emails in order with their resolved parents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputChecksum {
+    // SHA-256, lowercase hex
+    pub checksum: String,
+    pub config: ThreadingConfig,
+    pub thread_count: usize,
+    pub email_count: usize,
+}
+
+#[derive(Serialize)]
+struct ThreadDigest<'a> {
+    thread_id: &'a str,
+    email_ids: Vec<&'a str>,
+    // Email id -> parent email id
+    parents: BTreeMap<String, String>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Checksum of the current threading results, for two analysts to certify
+    /// they produced identical threads from the same input. Audit timestamps
+    /// and other run metadata are not covered. Only stable across runs with
+    /// `deterministic` set in the `ThreadingConfig`.
+    #[wasm_bindgen]
+    pub fn get_output_checksum(&self) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let checksum = self.output_checksum()?;
+        console_log!("Output checksum {} over {} threads", checksum.checksum, checksum.thread_count);
+        self.to_js(&checksum)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn output_checksum(&self) -> Result<OutputChecksum, JsValue> {
+        let config = self.threading_config();
+        let mut payload = serde_json::to_vec(&config).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        for (i, (thread_id, emails)) in self.threads.iter().enumerate() {
+            self.check_cancelled()?;
+            let digest = ThreadDigest {
+                thread_id,
+                email_ids: emails.iter().map(|e| e.id.as_str()).collect(),
+                parents: self.resolve_parents(emails).into_iter().collect(),
+            };
+            payload.push(b'\n');
+            serde_json::to_writer(&mut payload, &digest).map_err(|e| JsValue::from_str(&e.to_string()))?;
+            self.emit_progress("checksum", i + 1, Some(self.threads.len()));
+        }
+
+        Ok(OutputChecksum {
+            checksum: audit::sha256_hex(&payload),
+            config,
+            thread_count: self.threads.len(),
+            email_count: self.threads.values().map(Vec::len).sum(),
+        })
+    }
+}
@@ -0,0 +1,249 @@
+use crate::EmailThreadProcessor;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+const SNIFF_BYTES: usize = 64 * 1024;
+
+// Concordance DAT files use DC4 (shown as ¶) between fields, þ around them and
+// ® for line breaks inside a field
+pub(crate) const CONCORDANCE_DELIMITER: char = '\u{14}';
+pub(crate) const CONCORDANCE_QUOTE: char = '\u{fe}';
+pub(crate) const CONCORDANCE_NEWLINE: char = '\u{ae}';
+const DELIMITER_CANDIDATES: &[char] = &[',', '\t', '|', CONCORDANCE_DELIMITER, CONCORDANCE_QUOTE, ';'];
+
+// The csv reader works on single bytes, so non-ASCII delimiters and quotes are
+// swapped for unused control characters before parsing
+const DELIMITER_STANDIN: char = '\u{1f}';
+const QUOTE_STANDIN: char = '\u{1e}';
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadFileDialect {
+    pub delimiter: char,
+    pub quote: char,
+    pub encoding: String,
+    pub has_bom: bool,
+}
+
+impl Default for LoadFileDialect {
+    fn default() -> Self {
+        LoadFileDialect {
+            delimiter: ',',
+            quote: '"',
+            encoding: "utf-8".to_string(),
+            has_bom: false,
+        }
+    }
+}
+
+/// Caller overrides for `load_emails_from_bytes`; unset fields are sniffed.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DialectOverride {
+    pub delimiter: Option<char>,
+    pub quote: Option<char>,
+    pub encoding: Option<String>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    #[wasm_bindgen]
+    pub fn detect_load_file_dialect(&self, data: &[u8]) -> Result<JsValue, JsValue> {
+        let dialect = detect_dialect(data, &DialectOverride::default());
+        self.to_js(&dialect)
+    }
+
+    /// Loads a delimited load file from raw bytes, sniffing encoding, BOM,
+    /// delimiter and quote unless `dialect` overrides them. The dialect used is
+    /// reported in `get_load_report()`.
+    #[wasm_bindgen]
+    pub fn load_emails_from_bytes(&mut self, data: &[u8], dialect: JsValue) -> Result<usize, JsValue> {
+        console_log!("Loading emails from load file bytes, length: {}", data.len());
+        self.reset_cancellation();
+
+        if data.is_empty() {
+            return Err(JsValue::from_str("Load file data is empty"));
+        }
+
+        let overrides: DialectOverride = if dialect.is_undefined() || dialect.is_null() {
+            DialectOverride::default()
+        } else {
+            serde_wasm_bindgen::from_value(dialect)?
+        };
+
+        let dialect = detect_dialect(data, &overrides);
+        console_log!("Load file dialect: {:?}", dialect);
+
+        let text = decode(data, &dialect.encoding).map_err(|e| JsValue::from_str(&e))?;
+        let count = self.load_delimited(&text, dialect, "load file")?;
+        self.audit_load("load_emails_from_bytes", format!("{} emails loaded", count), &[("load file", data)]);
+        Ok(count)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn load_delimited(&mut self, text: &str, dialect: LoadFileDialect, source: &str) -> Result<usize, JsValue> {
+        let text = prepare_text(text, &dialect);
+        let mut rdr = csv_reader(&text, &dialect);
+        let headers = rdr
+            .headers()
+            .map_err(|e| JsValue::from_str(&format!("Error reading {} headers: {}", source, e)))?
+            .clone();
+
+        // Log headers for debugging
+        console_log!("{} headers: {:?}", source, headers);
+
+        let rows = rdr.records().map(|r| r.map_err(|e| e.to_string()));
+        self.load_rows(&headers, rows, source, Some(dialect))
+    }
+}
+
+pub(crate) fn detect_dialect(data: &[u8], overrides: &DialectOverride) -> LoadFileDialect {
+    let (detected_encoding, has_bom) = detect_encoding(data);
+    let encoding = overrides.encoding.clone().unwrap_or(detected_encoding);
+
+    let sample = &data[..data.len().min(SNIFF_BYTES)];
+    let sample = decode(sample, &encoding).unwrap_or_default();
+    let (delimiter, quote) = sniff_text(&sample);
+
+    LoadFileDialect {
+        delimiter: overrides.delimiter.unwrap_or(delimiter),
+        quote: overrides.quote.unwrap_or(quote),
+        encoding,
+        has_bom,
+    }
+}
+
+/// Picks delimiter and quote from the header line of already-decoded text.
+pub(crate) fn sniff_text(text: &str) -> (char, char) {
+    let text = text.trim_start_matches('\u{feff}');
+    let header = text.lines().next().unwrap_or_default();
+
+    let quote = if header.starts_with(CONCORDANCE_QUOTE) && header.matches(CONCORDANCE_QUOTE).count() >= 2 {
+        CONCORDANCE_QUOTE
+    } else {
+        '"'
+    };
+
+    let delimiter = DELIMITER_CANDIDATES
+        .iter()
+        .filter(|&&c| c != quote)
+        .map(|&c| (c, header.matches(c).count()))
+        .filter(|&(_, count)| count > 0)
+        .max_by_key(|&(_, count)| count)
+        .map(|(c, _)| c)
+        .unwrap_or(',');
+
+    (delimiter, quote)
+}
+
+fn detect_encoding(data: &[u8]) -> (String, bool) {
+    if data.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return ("utf-8".to_string(), true);
+    }
+    if data.starts_with(&[0xFF, 0xFE]) {
+        return ("utf-16le".to_string(), true);
+    }
+    if data.starts_with(&[0xFE, 0xFF]) {
+        return ("utf-16be".to_string(), true);
+    }
+
+    // BOM-less UTF-16 shows up as a NUL in every other byte of ASCII text
+    let sample = &data[..data.len().min(SNIFF_BYTES)];
+    let odd_nuls = sample.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+    let even_nuls = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let half = sample.len() / 2;
+    if half > 0 && odd_nuls * 3 > half {
+        return ("utf-16le".to_string(), false);
+    }
+    if half > 0 && even_nuls * 3 > half {
+        return ("utf-16be".to_string(), false);
+    }
+
+    match std::str::from_utf8(data) {
+        Ok(_) => ("utf-8".to_string(), false),
+        // A sniff window can end mid-character; only reject on a real error
+        Err(e) if e.error_len().is_none() => ("utf-8".to_string(), false),
+        Err(_) => ("windows-1252".to_string(), false),
+    }
+}
+
+pub(crate) fn decode(data: &[u8], encoding: &str) -> Result<String, String> {
+    let text = match encoding.to_lowercase().as_str() {
+        "utf-8" | "utf8" => String::from_utf8_lossy(data).into_owned(),
+        "utf-16le" | "utf-16" => decode_utf16(data, u16::from_le_bytes),
+        "utf-16be" => decode_utf16(data, u16::from_be_bytes),
+        "windows-1252" | "cp1252" | "latin-1" | "latin1" | "iso-8859-1" => data.iter().map(|&b| cp1252_char(b)).collect(),
+        other => return Err(format!("Unsupported encoding: {}", other)),
+    };
+    Ok(text.trim_start_matches('\u{feff}').to_string())
+}
+
+fn decode_utf16(data: &[u8], to_unit: fn([u8; 2]) -> u16) -> String {
+    let units: Vec<u16> = data.chunks_exact(2).map(|c| to_unit([c[0], c[1]])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn cp1252_char(b: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+        '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+    ];
+    match b {
+        0x80..=0x9F => HIGH[(b - 0x80) as usize],
+        _ => b as char,
+    }
+}
+
+fn ascii_standin(c: char, standin: char) -> u8 {
+    if c.is_ascii() {
+        c as u8
+    } else {
+        standin as u8
+    }
+}
+
+/// Reader over text already passed through `prepare_text` for the same dialect.
+pub(crate) fn csv_reader<'a>(prepared: &'a str, dialect: &LoadFileDialect) -> csv::Reader<&'a [u8]> {
+    csv::ReaderBuilder::new()
+        .delimiter(ascii_standin(dialect.delimiter, DELIMITER_STANDIN))
+        .quote(ascii_standin(dialect.quote, QUOTE_STANDIN))
+        // Ragged rows are fitted to the header by fit_row, not rejected
+        .flexible(true)
+        .from_reader(prepared.as_bytes())
+}
+
+/// Pads a row missing trailing columns, or drops trailing cells that are all
+/// empty, so it lines up with `width` headers. Returns the repair made, or an
+/// error when the extra cells hold data and the row cannot be trusted.
+pub(crate) fn fit_row(row: &mut csv::StringRecord, width: usize) -> Result<Option<&'static str>, String> {
+    let len = row.len();
+    if len < width {
+        for _ in len..width {
+            row.push_field("");
+        }
+        return Ok(Some("short_row"));
+    }
+    if len > width {
+        if row.iter().skip(width).any(|f| !f.trim().is_empty()) {
+            return Err(format!("Row has {} fields but there are {} headers", len, width));
+        }
+        row.truncate(width);
+        return Ok(Some("long_row"));
+    }
+    Ok(None)
+}
+
+pub(crate) fn prepare_text(text: &str, dialect: &LoadFileDialect) -> String {
+    let mut prepared = String::with_capacity(text.len());
+    for c in text.trim_start_matches('\u{feff}').chars() {
+        if c == dialect.delimiter && !c.is_ascii() {
+            prepared.push(DELIMITER_STANDIN);
+        } else if c == dialect.quote && !c.is_ascii() {
+            prepared.push(QUOTE_STANDIN);
+        } else if c == CONCORDANCE_NEWLINE && dialect.quote == CONCORDANCE_QUOTE {
+            prepared.push('\n');
+        } else {
+            prepared.push(c);
+        }
+    }
+    prepared
+}
@@ -0,0 +1,138 @@
+use crate::EmailThreadProcessor;
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use serde::{Deserialize, Serialize, Serializer};
+use std::cell::Cell;
+use wasm_bindgen::prelude::*;
+
+/// How datetimes are written in results handed to the caller. Everything is
+/// kept and persisted in UTC; this only changes the rendering.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayTimezone {
+    // RFC 3339 in UTC, e.g. "2024-03-01T17:00:00Z"
+    #[default]
+    Utc,
+    // RFC 3339 at a fixed offset, in seconds east of UTC
+    Offset(i32),
+    // RFC 3339 in the host's (in a browser, the user's) local zone
+    Local,
+    // Milliseconds since the Unix epoch, as a number
+    EpochMillis,
+}
+
+thread_local! {
+    // Set only while a result is being serialized, see to_js
+    static RENDERING: Cell<DisplayTimezone> = const { Cell::new(DisplayTimezone::Utc) };
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Sets how datetimes appear in returned objects and JSON exports: "UTC"
+    /// (default, also null), a fixed offset such as "+05:30" or "-08:00",
+    /// "local" for the host's zone, or "epoch_millis" for numbers. Internal
+    /// data, snapshots, the audit log and CSV exports stay in UTC, and
+    /// datetimes passed in (filters, date ranges) are still read as RFC 3339.
+    /// Named zones such as "America/New_York" are not supported.
+    #[wasm_bindgen]
+    pub fn set_display_timezone(&mut self, tz: Option<String>) -> Result<(), JsValue> {
+        let display = match tz.as_deref().map(str::trim) {
+            None | Some("") => DisplayTimezone::Utc,
+            Some(tz) => parse_display_timezone(tz).ok_or_else(|| {
+                JsValue::from_str(&format!(
+                    "Unknown display timezone: {} (expected UTC, local, epoch_millis or an offset like +05:30)",
+                    tz
+                ))
+            })?,
+        };
+        self.display_timezone = display;
+        self.audit("set_display_timezone", self.get_display_timezone());
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_display_timezone(&self) -> String {
+        match self.display_timezone {
+            DisplayTimezone::Utc => "UTC".to_string(),
+            DisplayTimezone::Offset(seconds) => {
+                let sign = if seconds < 0 { '-' } else { '+' };
+                let minutes = seconds.unsigned_abs() / 60;
+                format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+            }
+            DisplayTimezone::Local => "local".to_string(),
+            DisplayTimezone::EpochMillis => "epoch_millis".to_string(),
+        }
+    }
+}
+
+impl EmailThreadProcessor {
+    /// Serializes a result for the caller, datetimes in the display timezone.
+    pub(crate) fn to_js<T: Serialize + ?Sized>(&self, value: &T) -> Result<JsValue, JsValue> {
+        rendering(self.display_timezone, || serde_wasm_bindgen::to_value(value))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Likewise for a pretty-printed JSON export.
+    pub(crate) fn to_json<T: Serialize + ?Sized>(&self, value: &T) -> Result<String, JsValue> {
+        rendering(self.display_timezone, || serde_json::to_string_pretty(value))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+}
+
+fn rendering<R>(display: DisplayTimezone, f: impl FnOnce() -> R) -> R {
+    let previous = RENDERING.with(|r| r.replace(display));
+    let result = f();
+    RENDERING.with(|r| r.set(previous));
+    result
+}
+
+fn parse_display_timezone(tz: &str) -> Option<DisplayTimezone> {
+    match tz.to_lowercase().as_str() {
+        "utc" | "z" | "gmt" => return Some(DisplayTimezone::Utc),
+        "local" => return Some(DisplayTimezone::Local),
+        "epoch_millis" | "epoch" | "millis" => return Some(DisplayTimezone::EpochMillis),
+        _ => {}
+    }
+    let (sign, rest) = match tz.strip_prefix("UTC").or_else(|| tz.strip_prefix("GMT")).unwrap_or(tz) {
+        rest if rest.starts_with('+') => (1, &rest[1..]),
+        rest if rest.starts_with('-') => (-1, &rest[1..]),
+        _ => return None,
+    };
+    // Digits only: `parse` would take a second sign ("+-5") and slicing
+    // non-ASCII input could split a character
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((h, m)) if digits(h) && digits(m) => (h.parse::<i32>().ok()?, m.parse::<i32>().ok()?),
+        None if rest.len() == 4 && digits(rest) => (rest[..2].parse().ok()?, rest[2..].parse().ok()?),
+        None if digits(rest) => (rest.parse().ok()?, 0),
+        _ => return None,
+    };
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+    let seconds = sign * (hours * 3600 + minutes * 60);
+    FixedOffset::east_opt(seconds).map(|_| DisplayTimezone::Offset(seconds))
+}
+
+/// `serialize_with` for datetimes in results: rendered per the display
+/// timezone while `to_js` or `to_json` runs, plain UTC otherwise.
+pub(crate) fn serialize<S: Serializer>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    match RENDERING.with(Cell::get) {
+        DisplayTimezone::Utc => date.serialize(serializer),
+        DisplayTimezone::Offset(seconds) => match FixedOffset::east_opt(seconds) {
+            Some(offset) => date.with_timezone(&offset).serialize(serializer),
+            None => date.serialize(serializer),
+        },
+        DisplayTimezone::Local => date.with_timezone(&Local).serialize(serializer),
+        DisplayTimezone::EpochMillis => serializer.serialize_i64(date.timestamp_millis()),
+    }
+}
+
+pub(crate) fn serialize_option<S: Serializer>(date: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error> {
+    struct Shown<'a>(&'a DateTime<Utc>);
+    impl Serialize for Shown<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize(self.0, serializer)
+        }
+    }
+    date.as_ref().map(Shown).serialize(serializer)
+}
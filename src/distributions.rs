@@ -0,0 +1,93 @@
+use crate::{DateRange, EmailThreadProcessor};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// Inclusive lower bound and label of each bucket; the last is open-ended
+const SIZE_BUCKETS: &[(i64, &str)] = &[(1, "1"), (2, "2"), (3, "3-5"), (6, "6-10"), (11, "11-25"), (26, "26-50"), (51, "51-100"), (101, "101+")];
+const DEPTH_BUCKETS: &[(i64, &str)] = &[(0, "0"), (1, "1"), (2, "2"), (3, "3"), (4, "4-5"), (6, "6-10"), (11, "11+")];
+const BRANCH_BUCKETS: &[(i64, &str)] = &[(0, "0"), (1, "1"), (2, "2-3"), (4, "4-10"), (11, "11+")];
+// In seconds
+const DURATION_BUCKETS: &[(i64, &str)] = &[
+    (0, "under 1 hour"),
+    (3_600, "1 hour - 1 day"),
+    (86_400, "1-7 days"),
+    (604_800, "1-4 weeks"),
+    (2_419_200, "1-3 months"),
+    (7_776_000, "3-12 months"),
+    (31_536_000, "1 year+"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistogramBucket {
+    pub label: String,
+    pub min: i64,
+    // Exclusive; None for the last bucket
+    pub max: Option<i64>,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadDistributions {
+    pub thread_count: usize,
+    pub size: Vec<HistogramBucket>,
+    pub max_depth: Vec<HistogramBucket>,
+    pub branch_count: Vec<HistogramBucket>,
+    // Seconds from first to last email
+    pub duration: Vec<HistogramBucket>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Bucketed histograms of thread size, maximum depth, branch count and
+    /// duration over the listed threads, computed as `generate_thread_stats`
+    /// would for each.
+    #[wasm_bindgen]
+    pub fn get_thread_distributions(&self) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let mut sizes = Vec::new();
+        let mut depths = Vec::new();
+        let mut branches = Vec::new();
+        let mut durations = Vec::new();
+
+        let threads: Vec<&String> = self.visible_threads().map(|(id, _)| id).collect();
+        for (i, thread_id) in threads.iter().enumerate() {
+            self.check_cancelled()?;
+            let emails = &self.threads[*thread_id];
+            let outline = self.outline(emails);
+            let date_range = DateRange::of(emails).unwrap_or_default();
+            sizes.push(emails.len() as i64);
+            depths.push(outline.max_depth() as i64);
+            branches.push(outline.branch_count as i64);
+            durations.push((date_range.end - date_range.start).num_seconds().max(0));
+            self.emit_progress("distributions", i + 1, Some(threads.len()));
+        }
+
+        let distributions = ThreadDistributions {
+            thread_count: threads.len(),
+            size: histogram(&sizes, SIZE_BUCKETS),
+            max_depth: histogram(&depths, DEPTH_BUCKETS),
+            branch_count: histogram(&branches, BRANCH_BUCKETS),
+            duration: histogram(&durations, DURATION_BUCKETS),
+        };
+        self.to_js(&distributions)
+    }
+}
+
+fn histogram(values: &[i64], buckets: &[(i64, &str)]) -> Vec<HistogramBucket> {
+    let mut histogram: Vec<HistogramBucket> = buckets
+        .iter()
+        .enumerate()
+        .map(|(i, &(min, label))| HistogramBucket {
+            label: label.to_string(),
+            min,
+            max: buckets.get(i + 1).map(|&(max, _)| max),
+            count: 0,
+        })
+        .collect();
+    for &value in values {
+        if let Some(bucket) = histogram.iter_mut().rev().find(|b| value >= b.min) {
+            bucket.count += 1;
+        }
+    }
+    histogram
+}
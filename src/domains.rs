@@ -0,0 +1,143 @@
+use crate::{network, EmailThreadProcessor};
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainStats {
+    pub domain: String,
+    pub is_internal: bool,
+    // Emails sent from the domain / with at least one recipient there
+    pub sent: usize,
+    pub received: usize,
+    pub address_count: usize,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub first_contact: DateTime<Utc>,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub last_contact: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainReport {
+    pub internal_domains: Vec<String>,
+    // Emails whose sender and recipients are all internal
+    pub internal_email_count: usize,
+    // Emails with any participant outside the internal domains
+    pub external_email_count: usize,
+    pub external_domain_count: usize,
+    // Busiest first
+    pub domains: Vec<DomainStats>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// The organization's own domains. Subdomains count as internal too, so
+    /// "acme.com" covers "mail.acme.com".
+    #[wasm_bindgen]
+    pub fn set_internal_domains(&mut self, domains: Vec<String>) {
+        self.internal_domains = normalize_domains(&domains);
+    }
+
+    #[wasm_bindgen]
+    pub fn get_internal_domains(&self) -> Vec<String> {
+        self.internal_domains.clone()
+    }
+
+    /// Traffic by sender and recipient domain, internal or external per
+    /// `set_internal_domains`, with first and last contact dates. With no
+    /// internal domains configured every domain is reported as external.
+    #[wasm_bindgen]
+    pub fn get_domain_report(&self) -> Result<JsValue, JsValue> {
+        let report = self.domain_report();
+        self.to_js(&report)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn is_internal_domain(&self, domain: &str) -> bool {
+        self.internal_domains
+            .iter()
+            .any(|d| domain == d || domain.strip_suffix(d.as_str()).is_some_and(|rest| rest.ends_with('.')))
+    }
+
+    fn domain_report(&self) -> DomainReport {
+        let mut domains: IndexMap<String, (DomainStats, BTreeSet<String>)> = IndexMap::new();
+        let mut internal_email_count = 0;
+        let mut external_email_count = 0;
+
+        for email in self.included_emails() {
+            let senders = network::identities(&email.from);
+            let recipients: Vec<String> = email
+                .to
+                .iter()
+                .chain(&email.cc)
+                .chain(&email.bcc)
+                .flat_map(|r| network::identities(r))
+                .collect();
+
+            let mut received_here = BTreeSet::new();
+            let mut any_external = false;
+            for (address, is_sender) in senders.iter().map(|s| (s, true)).chain(recipients.iter().map(|r| (r, false))) {
+                let Some((_, domain)) = address.rsplit_once('@') else {
+                    continue;
+                };
+                let is_internal = self.is_internal_domain(domain);
+                any_external |= !is_internal;
+                let (stats, addresses) = domains.entry(domain.to_string()).or_insert_with(|| {
+                    (
+                        DomainStats {
+                            domain: domain.to_string(),
+                            is_internal,
+                            sent: 0,
+                            received: 0,
+                            address_count: 0,
+                            first_contact: email.date_sent,
+                            last_contact: email.date_sent,
+                        },
+                        BTreeSet::new(),
+                    )
+                });
+                addresses.insert(address.clone());
+                stats.first_contact = stats.first_contact.min(email.date_sent);
+                stats.last_contact = stats.last_contact.max(email.date_sent);
+                if is_sender {
+                    stats.sent += 1;
+                } else if received_here.insert(domain.to_string()) {
+                    stats.received += 1;
+                }
+            }
+            if any_external {
+                external_email_count += 1;
+            } else if !senders.is_empty() || !recipients.is_empty() {
+                internal_email_count += 1;
+            }
+        }
+
+        let mut domains: Vec<DomainStats> = domains
+            .into_values()
+            .map(|(mut stats, addresses)| {
+                stats.address_count = addresses.len();
+                stats
+            })
+            .collect();
+        domains.sort_by(|a, b| (b.sent + b.received).cmp(&(a.sent + a.received)).then_with(|| a.domain.cmp(&b.domain)));
+
+        DomainReport {
+            internal_domains: self.internal_domains.clone(),
+            internal_email_count,
+            external_email_count,
+            external_domain_count: domains.iter().filter(|d| !d.is_internal).count(),
+            domains,
+        }
+    }
+}
+
+pub(crate) fn normalize_domains(domains: &[String]) -> Vec<String> {
+    domains
+        .iter()
+        .map(|d| d.trim().trim_start_matches('@').to_lowercase())
+        .filter(|d| !d.is_empty())
+        .collect()
+}
@@ -0,0 +1,262 @@
+use crate::custodians::merge_custodians;
+use crate::opticon::split_bates;
+use crate::{infer_thread_ids, EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+// EDRM XML 1.x and 2.0 share the Document/Tag/Relationship layout; 2.0 only adds
+// optional elements we don't need, so a single reader handles both.
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    #[wasm_bindgen]
+    pub fn load_emails_from_edrm(&mut self, xml_data: &str) -> Result<usize, JsValue> {
+        console_log!("Loading emails from EDRM XML, length: {}", xml_data.len());
+        self.reset_cancellation();
+
+        if xml_data.is_empty() {
+            return Err(JsValue::from_str("EDRM XML data is empty"));
+        }
+
+        let (emails, errors) = parse_edrm(xml_data).map_err(|e| JsValue::from_str(&e))?;
+        for error in &errors {
+            console_log!("{}", error);
+            self.emit_warning(error, "EDRM");
+        }
+        let documents = emails.len() + errors.len();
+        self.emit_progress("EDRM", documents, Some(documents));
+        self.check_cancelled()?;
+        let count = self.finish_load(emails, "EDRM");
+        self.load_report.rows_read = documents;
+        self.load_report.errors = errors;
+        self.audit_load("load_emails_from_edrm", format!("{} documents loaded", count), &[("EDRM", xml_data.as_bytes())]);
+        let error_count = self.load_report.errors.len();
+        console_log!("Successfully loaded {} documents from EDRM XML ({} errors)", count, error_count);
+
+        if count == 0 {
+            return Err(JsValue::from_str("No valid documents were parsed from EDRM XML"));
+        }
+
+        Ok(count)
+    }
+}
+
+// Documents that fail to parse (e.g. an unreadable date) are skipped and
+// returned as errors; only XML that cannot be read at all fails the load
+pub(crate) fn parse_edrm(xml_data: &str) -> Result<(Vec<EmailMessage>, Vec<String>), String> {
+    let doc = roxmltree::Document::parse(xml_data).map_err(|e| format!("Invalid EDRM XML: {}", e))?;
+
+    let mut emails = Vec::new();
+    let mut errors = Vec::new();
+    for (i, node) in doc.descendants().filter(|n| n.has_tag_name("Document")).enumerate() {
+        match parse_document(node) {
+            Ok(email) => emails.push(email),
+            Err(e) => {
+                let doc_id = node.attribute("DocID").unwrap_or("no DocID");
+                errors.push(format!("Error parsing EDRM document {} ({}): {}", i + 1, doc_id, e))
+            }
+        }
+    }
+
+    let index: HashMap<String, usize> = emails
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.id.clone(), i))
+        .collect();
+
+    for rel in doc.descendants().filter(|n| n.has_tag_name("Relationship")) {
+        let rel_type = rel.attribute("Type").unwrap_or_default();
+        if !rel_type.eq_ignore_ascii_case("Attachment") && !rel_type.eq_ignore_ascii_case("Family") {
+            continue;
+        }
+
+        let (Some(parent), Some(child)) = (rel.attribute("ParentDocID"), rel.attribute("ChildDocID")) else {
+            continue;
+        };
+        let (Some(&parent_idx), Some(&child_idx)) = (index.get(parent), index.get(child)) else {
+            console_log!("Skipping relationship {} -> {}: document not in load file", parent, child);
+            continue;
+        };
+
+        emails[child_idx].parent_id = Some(parent.to_string());
+        emails[parent_idx].attachment_ids.push(child.to_string());
+    }
+
+    // Family ranges run from the parent to its last attachment, nested
+    // attachments included
+    for i in 0..emails.len() {
+        if emails[i].parent_id.is_some() {
+            continue;
+        }
+        let mut family = Vec::new();
+        let mut visited = HashSet::from([i]);
+        let mut pending = vec![i];
+        while let Some(idx) = pending.pop() {
+            for id in &emails[idx].attachment_ids {
+                if let Some(&child) = index.get(id) {
+                    if visited.insert(child) {
+                        family.push(child);
+                        pending.push(child);
+                    }
+                }
+            }
+        }
+
+        let beg = emails[i].beg_bates.clone();
+        // Bates numbers compare by number, not as strings ("ABC10" > "ABC9")
+        let last = family
+            .iter()
+            .copied()
+            .chain([i])
+            .max_by_key(|&idx| split_bates(&emails[idx].end_bates))
+            .unwrap_or(i);
+        let end = emails[last].end_bates.clone();
+
+        for idx in family {
+            emails[idx].beg_attach = beg.clone();
+            emails[idx].end_attach = end.clone();
+        }
+        emails[i].beg_attach = beg;
+        emails[i].end_attach = end;
+    }
+
+    infer_thread_ids(&mut emails);
+    Ok((emails, errors))
+}
+
+fn parse_document(node: roxmltree::Node) -> Result<EmailMessage, String> {
+    let doc_id = node
+        .attribute("DocID")
+        .ok_or_else(|| "EDRM Document is missing DocID".to_string())?
+        .to_string();
+
+    let mut email = EmailMessage {
+        id: doc_id.clone(),
+        beg_bates: doc_id.clone(),
+        end_bates: doc_id.clone(),
+        file_type: node.attribute("DocType").unwrap_or_default().to_string(),
+        ..Default::default()
+    };
+
+    for tag in node
+        .children()
+        .filter(|n| n.has_tag_name("Tags"))
+        .flat_map(|n| n.children())
+        .filter(|n| n.has_tag_name("Tag"))
+    {
+        let name = tag.attribute("TagName").unwrap_or_default();
+        let value = tag.attribute("TagValue").unwrap_or_default().trim();
+        let data_type = tag.attribute("TagDataType").unwrap_or_default();
+        apply_tag(&mut email, name, value, data_type)?;
+    }
+
+    for file in node.descendants().filter(|n| n.has_tag_name("File")) {
+        let file_type = file.attribute("FileType").unwrap_or_default();
+        if file_type.eq_ignore_ascii_case("Text") {
+            if let Some(content) = file.descendants().find(|n| n.has_tag_name("InlineContent")) {
+                email.full_text = content.text().unwrap_or_default().to_string();
+            }
+        } else if file_type.eq_ignore_ascii_case("Native") {
+            if let Some(external) = file.descendants().find(|n| n.has_tag_name("ExternalFile")) {
+                let path = external.attribute("FilePath").unwrap_or_default();
+                let name = external.attribute("FileName").unwrap_or_default();
+                if email.file_name.is_empty() {
+                    email.file_name = name.to_string();
+                }
+                if email.native_link.is_empty() {
+                    email.native_link = if path.is_empty() {
+                        name.to_string()
+                    } else {
+                        format!("{}/{}", path.trim_end_matches(['/', '\\']), name)
+                    };
+                }
+                if email.hash.is_empty() {
+                    email.hash = external.attribute("Hash").unwrap_or_default().to_string();
+                }
+            }
+        }
+    }
+
+    // Every Custodian location is a holder of this (deduplicated) document
+    let located: Vec<&str> = node
+        .descendants()
+        .filter(|n| n.has_tag_name("Custodian"))
+        .filter_map(|n| n.text())
+        .collect();
+    if email.custodian.is_empty() {
+        email.custodian = located.first().map(|c| c.trim().to_string()).unwrap_or_default();
+    }
+    let duplicates = std::mem::take(&mut email.all_custodians);
+    email.all_custodians = merge_custodians(
+        &email.custodian,
+        located.into_iter().chain(duplicates.iter().map(|d| d.as_str())),
+    );
+
+    if email.date_created == DateTime::<Utc>::default() {
+        email.date_created = email.date_sent;
+    }
+    if email.date_last_modified == DateTime::<Utc>::default() {
+        email.date_last_modified = email.date_created;
+    }
+
+    Ok(email)
+}
+
+fn apply_tag(email: &mut EmailMessage, name: &str, value: &str, data_type: &str) -> Result<(), String> {
+    let split_addresses = |v: &str| -> Vec<String> {
+        v.split([';', ','])
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    };
+
+    match name {
+        "#From" => email.from = value.to_string(),
+        "#To" => email.to = split_addresses(value),
+        "#CC" => email.cc = split_addresses(value),
+        "#BCC" => email.bcc = split_addresses(value),
+        "#Subject" => email.subject = value.to_string(),
+        "#DateSent" => email.date_sent = parse_edrm_date(value, name)?,
+        "#DateCreated" => email.date_created = parse_edrm_date(value, name)?,
+        "#DateModified" | "#DateLastModified" => email.date_last_modified = parse_edrm_date(value, name)?,
+        "#MessageID" => email.message_id = value.to_string(),
+        "#InReplyToID" | "#InReplyTo" if !value.is_empty() => email.in_reply_to = Some(value.to_string()),
+        "#References" => email.references = value.split_whitespace().map(|s| s.to_string()).collect(),
+        "#ThreadID" | "#ConversationID" => email.thread_id = value.to_string(),
+        "#ConversationIndex" => email.conversation_index = crate::conversation_index::normalize(value),
+        "#Custodian" => email.custodian = value.to_string(),
+        "#DuplicateCustodian" | "#AllCustodians" => email.all_custodians.push(value.to_string()),
+        "#FileName" => email.file_name = value.to_string(),
+        "#Author" => email.author = value.to_string(),
+        "#Title" => email.title = value.to_string(),
+        "#BegBates" | "#BeginBates" => email.beg_bates = value.to_string(),
+        "#EndBates" => email.end_bates = value.to_string(),
+        "#Confidentiality" => email.confidentiality = value.to_string(),
+        "#HashValue" | "#MD5Hash" | "#SHA1Hash" if email.hash.is_empty() => email.hash = value.to_string(),
+        _ if name.starts_with('#') => {}
+        // Anything without the reserved '#' prefix is review coding
+        _ if data_type.eq_ignore_ascii_case("Boolean") => {
+            let checked = value.eq_ignore_ascii_case("true") || value == "1";
+            if checked {
+                email.tags.push(name.to_string());
+            }
+        }
+        _ if !value.is_empty() => email.tags.push(format!("{}: {}", name, value)),
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn parse_edrm_date(value: &str, field: &str) -> Result<DateTime<Utc>, String> {
+    if value.is_empty() {
+        return Ok(DateTime::<Utc>::default());
+    }
+    crate::parse_date_field(value, field)
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+                .or_else(|_| chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"))
+                .map(|d| d.and_utc())
+                .map_err(|e| format!("Invalid date format for {}: {}", field, e))
+        })
+}
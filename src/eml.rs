@@ -0,0 +1,133 @@
+use crate::conversation_index::ConversationIndex;
+use crate::rfc5322::encode_base64;
+use crate::{redactions, EmailMessage, EmailThreadProcessor};
+use std::io::{Cursor, Write};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Rebuilds a thread's emails as RFC 5322 messages, headers from the
+    /// metadata and bodies from the extracted text, oldest first. `format` is
+    /// "mbox" (default; mboxrd, readable by `load_emails_from_mbox`) or "zip"
+    /// for one .eml file per email. Sequestered emails are left out.
+    #[wasm_bindgen]
+    pub fn export_thread_eml(&self, thread_id: &str, format: Option<String>) -> Result<Vec<u8>, JsValue> {
+        let emails: Vec<&EmailMessage> = self
+            .threads
+            .get(thread_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Thread not found: {}", thread_id)))?
+            .iter()
+            .filter(|e| !self.is_sequestered(&e.id))
+            .collect();
+        let format = format.unwrap_or_else(|| "mbox".to_string());
+        console_log!("Exporting {} emails of thread {} as {}", emails.len(), thread_id, format);
+
+        match format.as_str() {
+            "mbox" => Ok(to_mbox(&emails).into_bytes()),
+            "zip" => to_zip(&emails).map_err(|e| JsValue::from_str(&format!("Error writing ZIP: {}", e))),
+            other => Err(JsValue::from_str(&format!("Unknown export format: {}", other))),
+        }
+    }
+}
+
+/// One message with CRLF line endings, as a .eml file holds it.
+pub(crate) fn render_message(email: &EmailMessage) -> String {
+    let mut headers: Vec<(&str, String)> = vec![
+        ("From", email.from.clone()),
+        ("To", email.to.join(", ")),
+        ("Cc", email.cc.join(", ")),
+        ("Bcc", email.bcc.join(", ")),
+        ("Subject", encode_word(&email.subject)),
+        ("Date", email.date_sent.to_rfc2822()),
+        ("Message-ID", email.message_id.clone()),
+        ("In-Reply-To", email.in_reply_to.clone().unwrap_or_default()),
+        ("References", email.references.join(" ")),
+    ];
+    if let Some(ci) = email.conversation_index.as_deref().and_then(ConversationIndex::parse) {
+        headers.push(("Thread-Index", ci.to_base64()));
+    }
+    if let Some(thrid) = &email.gmail_thread_id {
+        headers.push(("X-GM-THRID", thrid.clone()));
+    }
+    headers.push(("X-Bates-Begin", email.beg_bates.clone()));
+    headers.push(("X-Custodian", email.custodian.clone()));
+    headers.push(("MIME-Version", "1.0".to_string()));
+    headers.push(("Content-Type", "text/plain; charset=utf-8".to_string()));
+    headers.push(("Content-Transfer-Encoding", "8bit".to_string()));
+
+    let mut message = String::new();
+    for (name, value) in headers.iter().filter(|(_, v)| !v.trim().is_empty()) {
+        message.push_str(&fold_header(name, value));
+        message.push_str("\r\n");
+    }
+    message.push_str("\r\n");
+    for line in redactions::export_text(email).lines() {
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message
+}
+
+// mboxrd: a "From " separator per message and body lines starting with any
+// number of '>' before "From " get one more
+fn to_mbox(emails: &[&EmailMessage]) -> String {
+    let mut mbox = String::new();
+    for email in emails {
+        let sender = crate::rfc5322::parse_addresses(&email.from).into_iter().next();
+        mbox.push_str(&format!(
+            "From {} {}\n",
+            sender.as_deref().unwrap_or("MAILER-DAEMON"),
+            email.date_sent.format("%a %b %e %H:%M:%S %Y")
+        ));
+        for line in render_message(email).lines() {
+            if line.trim_start_matches('>').starts_with("From ") {
+                mbox.push('>');
+            }
+            mbox.push_str(line);
+            mbox.push('\n');
+        }
+        mbox.push('\n');
+    }
+    mbox
+}
+
+fn to_zip(emails: &[&EmailMessage]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (i, email) in emails.iter().enumerate() {
+        let name = if email.beg_bates.is_empty() { &email.id } else { &email.beg_bates };
+        let safe: String = name
+            .chars()
+            .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+            .collect();
+        writer.start_file(format!("{:03}_{}.eml", i + 1, safe), options)?;
+        writer.write_all(render_message(email).as_bytes())?;
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+// RFC 2047 encoded-word for non-ASCII header text
+fn encode_word(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", encode_base64(value.as_bytes()))
+    }
+}
+
+// Address lists and References are wrapped after separators once a line
+// passes 78 characters
+fn fold_header(name: &str, value: &str) -> String {
+    let mut folded = format!("{}:", name);
+    let mut line_len = folded.len();
+    for (i, word) in value.split(' ').enumerate() {
+        if i > 0 && line_len + word.len() + 1 > 78 {
+            folded.push_str("\r\n");
+            line_len = 0;
+        }
+        folded.push(' ');
+        folded.push_str(word);
+        line_len += word.len() + 1;
+    }
+    folded
+}
@@ -0,0 +1,71 @@
+use crate::EmailThreadProcessor;
+use js_sys::Function;
+use wasm_bindgen::prelude::*;
+
+// Progress callbacks fire every this many items, plus once at the end
+const PROGRESS_INTERVAL: usize = 500;
+
+#[derive(Default)]
+pub(crate) struct EventHooks {
+    progress: Option<Function>,
+    thread_built: Option<Function>,
+    warning: Option<Function>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// `callback(stage, done, total)` during loads, threading and bulk
+    /// exports. `total` is undefined when the source size is not known up
+    /// front (delimited and XLSX load files). Pass null to unsubscribe.
+    #[wasm_bindgen]
+    pub fn on_progress(&mut self, callback: Option<Function>) {
+        self.hooks.progress = callback;
+    }
+
+    /// `callback(thread_id, email_count)` for each thread `group_by_threads` builds.
+    #[wasm_bindgen]
+    pub fn on_thread_built(&mut self, callback: Option<Function>) {
+        self.hooks.thread_built = callback;
+    }
+
+    /// `callback(message, source)` for non-fatal problems such as skipped records.
+    #[wasm_bindgen]
+    pub fn on_warning(&mut self, callback: Option<Function>) {
+        self.hooks.warning = callback;
+    }
+}
+
+impl EmailThreadProcessor {
+    // Callback errors are logged rather than propagated; a broken progress bar
+    // should not abort a load
+    pub(crate) fn emit_progress(&self, stage: &str, done: usize, total: Option<usize>) {
+        let Some(callback) = &self.hooks.progress else {
+            return;
+        };
+        if !done.is_multiple_of(PROGRESS_INTERVAL) && Some(done) != total {
+            return;
+        }
+        let total = total.map_or(JsValue::UNDEFINED, |t| JsValue::from_f64(t as f64));
+        if let Err(e) = callback.call3(&JsValue::NULL, &JsValue::from_str(stage), &JsValue::from_f64(done as f64), &total) {
+            console_log!("on_progress callback failed: {:?}", e);
+        }
+    }
+
+    pub(crate) fn emit_thread_built(&self, thread_id: &str, email_count: usize) {
+        let Some(callback) = &self.hooks.thread_built else {
+            return;
+        };
+        if let Err(e) = callback.call2(&JsValue::NULL, &JsValue::from_str(thread_id), &JsValue::from_f64(email_count as f64)) {
+            console_log!("on_thread_built callback failed: {:?}", e);
+        }
+    }
+
+    pub(crate) fn emit_warning(&self, message: &str, source: &str) {
+        let Some(callback) = &self.hooks.warning else {
+            return;
+        };
+        if let Err(e) = callback.call2(&JsValue::NULL, &JsValue::from_str(message), &JsValue::from_str(source)) {
+            console_log!("on_warning callback failed: {:?}", e);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{rfc5322, EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+/// One message of the direct back-and-forth between two participants, with
+/// where it sits in its thread.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeMessage {
+    pub email_id: String,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub date: DateTime<Utc>,
+    pub bates: String,
+    pub subject: String,
+    // "a_to_b" or "b_to_a"
+    pub direction: String,
+    pub thread_id: String,
+    pub thread_subject: String,
+    // 1-based chronological position within the thread
+    pub thread_position: Option<usize>,
+    pub thread_size: usize,
+    // Whether the email replies to an earlier message of this exchange
+    pub replies_to_exchange: bool,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Emails one of the two addresses sent to the other (To, Cc or Bcc)
+    /// across all threads, oldest first. Display names are ignored and
+    /// addresses compare case-insensitively.
+    #[wasm_bindgen]
+    pub fn get_exchange(&self, address_a: &str, address_b: &str) -> Result<JsValue, JsValue> {
+        let (a, b) = (address_a.trim(), address_b.trim());
+        if a.is_empty() || b.is_empty() {
+            return Err(JsValue::from_str("Both addresses are required"));
+        }
+
+        let mut matched: Vec<(&EmailMessage, &str)> = self
+            .emails
+            .iter()
+            .filter(|e| !self.filtered_out(e))
+            .filter_map(|email| {
+                if is_address(&email.from, a) && addressed_to(email, b) {
+                    Some((email, "a_to_b"))
+                } else if is_address(&email.from, b) && addressed_to(email, a) {
+                    Some((email, "b_to_a"))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        matched.sort_by(|(x, _), (y, _)| x.date_sent.cmp(&y.date_sent).then_with(|| x.beg_bates.cmp(&y.beg_bates)));
+
+        let message_ids: HashSet<&str> = matched
+            .iter()
+            .map(|(e, _)| e.message_id.as_str())
+            .filter(|id| !id.is_empty())
+            .collect();
+        let exchange: Vec<ExchangeMessage> = matched
+            .iter()
+            .map(|(email, direction)| {
+                let thread_id = self.thread_key(email).unwrap_or_default();
+                let thread = self.threads.get(&thread_id);
+                ExchangeMessage {
+                    email_id: email.id.clone(),
+                    date: email.date_sent,
+                    bates: email.beg_bates.clone(),
+                    subject: email.subject.clone(),
+                    direction: direction.to_string(),
+                    thread_subject: thread
+                        .and_then(|t| t.first())
+                        .map(|root| root.subject.clone())
+                        .unwrap_or_else(|| email.subject.clone()),
+                    thread_position: thread.and_then(|t| t.iter().position(|e| e.id == email.id)).map(|p| p + 1),
+                    thread_size: thread.map(|t| t.len()).unwrap_or(1),
+                    thread_id,
+                    replies_to_exchange: email.in_reply_to.as_deref().is_some_and(|p| message_ids.contains(p)),
+                }
+            })
+            .collect();
+
+        console_log!("Exchange between {} and {} has {} emails", a, b, exchange.len());
+        self.to_js(&exchange)
+    }
+}
+
+// Load files often carry "Name <address>" rather than the bare address
+fn is_address(field: &str, address: &str) -> bool {
+    rfc5322::parse_addresses(field).iter().any(|a| a.eq_ignore_ascii_case(address))
+}
+
+fn addressed_to(email: &EmailMessage, address: &str) -> bool {
+    email.to.iter().chain(&email.cc).chain(&email.bcc).any(|r| is_address(r, address))
+}
@@ -0,0 +1,101 @@
+use crate::{filetypes, network, search, EmailMessage, EmailThreadProcessor, LoadReport};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+/// Records dropped while loading, before they reach threading or stats:
+/// known system files by hash (a NIST NSRL list, say), file types or
+/// extensions, and sender address patterns with `*`/`?` wildcards such as
+/// `"*@noreply.*"`. All comparisons are case-insensitive.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExclusionRules {
+    pub hashes: Vec<String>,
+    pub file_types: Vec<String>,
+    pub sender_patterns: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Sets the rules applied by every loader from the next load on. Counts of
+    /// excluded records appear in `get_load_report()`. Pass null to keep
+    /// everything.
+    #[wasm_bindgen]
+    pub fn set_exclusion_rules(&mut self, rules: JsValue) -> Result<(), JsValue> {
+        self.exclusion_rules = if rules.is_undefined() || rules.is_null() {
+            ExclusionRules::default()
+        } else {
+            serde_wasm_bindgen::from_value(rules)?
+        };
+        self.audit("set_exclusion_rules", serde_json::to_string(&self.exclusion_rules).unwrap_or_default());
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_exclusion_rules(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.exclusion_rules)
+    }
+}
+
+impl EmailThreadProcessor {
+    /// Replaces the corpus with a freshly parsed batch for loaders that keep
+    /// no load report of their own, recording one for `source` with the
+    /// exclusions applied. Returns the number of emails kept.
+    pub(crate) fn finish_load(&mut self, mut emails: Vec<EmailMessage>, source: &str) -> usize {
+        self.load_report = LoadReport {
+            source: source.to_string(),
+            rows_read: emails.len(),
+            ..Default::default()
+        };
+        self.apply_exclusions(&mut emails, source);
+        self.normalize_confidentiality(&mut emails);
+        let count = emails.len();
+        self.load_report.emails_loaded = count;
+        self.store_loaded(emails);
+        count
+    }
+
+    /// Drops excluded records from a freshly parsed batch and adds the counts
+    /// per rule to the current load report.
+    pub(crate) fn apply_exclusions(&mut self, emails: &mut Vec<EmailMessage>, source: &str) {
+        let rules = &self.exclusion_rules;
+        let hashes: HashSet<String> = rules.hashes.iter().map(|h| h.trim().to_lowercase()).collect();
+        let patterns: Vec<String> = rules.sender_patterns.iter().map(|p| p.trim().to_lowercase()).collect();
+        let mut excluded = HashSet::new();
+        for email in emails.iter() {
+            let reason = if !email.hash.is_empty() && hashes.contains(&email.hash.trim().to_lowercase()) {
+                "hash"
+            } else if rules.file_types.iter().any(|t| matches_type(t, email)) {
+                "file_type"
+            } else if network::identities(&email.from)
+                .iter()
+                .any(|sender| patterns.iter().any(|p| search::wildcard_match(p, sender)))
+            {
+                "sender"
+            } else {
+                continue;
+            };
+            excluded.insert(email.id.clone());
+            *self.load_report.excluded_by.entry(reason.to_string()).or_default() += 1;
+        }
+        if excluded.is_empty() {
+            return;
+        }
+
+        emails.retain(|e| !excluded.contains(&e.id));
+        // Attachments of an excluded parent stay, as standalone documents
+        for email in emails.iter_mut() {
+            email.attachment_ids.retain(|id| !excluded.contains(id));
+            if email.parent_id.as_ref().is_some_and(|p| excluded.contains(p)) {
+                email.parent_id = None;
+            }
+        }
+        self.load_report.excluded_count = excluded.len();
+        console_log!("Excluded {} records from {}", excluded.len(), source);
+    }
+}
+
+fn matches_type(rule: &str, email: &EmailMessage) -> bool {
+    let rule = rule.trim().trim_start_matches('.');
+    !rule.is_empty() && (email.file_type.trim().eq_ignore_ascii_case(rule) || filetypes::extension_of(email).eq_ignore_ascii_case(rule))
+}
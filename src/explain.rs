@@ -0,0 +1,189 @@
+use crate::conversation_index::ConversationIndex;
+use crate::reattach::Reattachment;
+use crate::{topics, EmailMessage, EmailThreadProcessor, ThreadingMode};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// A Message-ID an email cites, and where that message is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CitedMessage {
+    pub message_id: String,
+    pub email_id: Option<String>,
+    // "in_thread", "other_thread" or "missing"
+    pub status: String,
+}
+
+/// The evidence behind an email's place in its thread.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadingExplanation {
+    pub email_id: String,
+    pub threading_mode: String,
+    // None when the email is unthreaded or outside the filters
+    pub thread_id: Option<String>,
+    // Where the thread key came from: "locked", "reattached",
+    // "conversation_index", "gmail_thread_id", "thread_id" or
+    // "subject_fallback"; None if no key
+    pub thread_source: Option<String>,
+    // False when the type filter or global filter keeps it out of threads
+    pub in_scope: bool,
+    // When deduplication dropped this copy, the email kept in its place
+    pub duplicate_of: Option<String>,
+    pub parent_id: Option<String>,
+    // "conversation_index", "in_reply_to", "references", "reattached" or
+    // "locked"; None for a root
+    pub parent_source: Option<String>,
+    pub in_reply_to: Option<CitedMessage>,
+    pub references: Vec<CitedMessage>,
+    // The normalized subject the thread was keyed on, under subject fallback
+    pub subject_key: Option<String>,
+    // The heuristic edge reattach_orphans made, the only override there is
+    pub reattachment: Option<Reattachment>,
+    // The above as sentences, for a report or declaration
+    pub reasons: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Why an email sits where it does: which field gave its thread key, how
+    /// its parent was found (conversation index, In-Reply-To, References or
+    /// `reattach_orphans`), where every message it cites is, and whether
+    /// filters or deduplication kept it out.
+    #[wasm_bindgen]
+    pub fn explain_threading(&self, email_id: &str) -> Result<JsValue, JsValue> {
+        let explanation = self.threading_explanation(email_id)?;
+        self.to_js(&explanation)
+    }
+}
+
+impl EmailThreadProcessor {
+    fn threading_explanation(&self, email_id: &str) -> Result<ThreadingExplanation, JsValue> {
+        let email = self
+            .email_by_id(email_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Email not found: {}", email_id)))?;
+        let mut reasons = Vec::new();
+        let keyed = self.thread_key_source(email);
+        let in_scope = self.in_scope(email);
+        let reattachment = self.reattachments.get(email_id).cloned();
+
+        match &keyed {
+            Some((key, "locked")) => reasons.push(format!("Held in thread {}, locked with this email in it", key)),
+            Some((key, "reattached")) => {
+                let r = reattachment.as_ref().map(|r| (r.parent_id.as_str(), r.signals.join(", "))).unwrap_or_default();
+                reasons.push(format!("Reattached as an orphan reply under {} ({}), joining thread {}", r.0, r.1, key));
+            }
+            Some((key, "conversation_index")) => {
+                reasons.push(format!("Grouped by its conversation index into thread {}", key))
+            }
+            Some((key, "gmail_thread_id")) => {
+                reasons.push(format!("Grouped by its Gmail thread id into thread {}", key))
+            }
+            Some((key, "thread_id")) => reasons.push(format!("Grouped by the load file ThreadId value {}", key)),
+            Some((key, _)) => reasons.push(format!("No thread id; grouped by normalized subject into thread {}", key)),
+            None => reasons.push("No thread id, and subject fallback is off or the subject is empty".to_string()),
+        }
+        if self.type_excluded(email) {
+            reasons.push("Kept out of threads by the type filter".to_string());
+        } else if self.filtered_out(email) {
+            reasons.push("Kept out of threads by the global filter".to_string());
+        }
+
+        let thread = keyed.as_ref().filter(|_| in_scope).and_then(|(key, _)| self.threads.get_key_value(key));
+        let held = thread.and_then(|(_, emails)| emails.iter().find(|e| e.id == email.id));
+        let duplicate_of = match (thread, held) {
+            (Some((_, emails)), None) => self
+                .dedup_policy
+                .key(email)
+                .and_then(|key| emails.iter().find(|e| self.dedup_policy.key(e) == Some(key)))
+                .map(|kept| kept.id.clone()),
+            _ => None,
+        };
+        if let Some(kept) = &duplicate_of {
+            reasons.push(format!("Dropped from the thread as a duplicate of {}", kept));
+        } else if thread.is_some() && held.is_none() {
+            reasons.push("Threads were grouped before this email was loaded or changed".to_string());
+        }
+
+        let thread_emails = thread.map(|(_, emails)| emails.as_slice()).unwrap_or_default();
+        let cite = |message_id: &str| {
+            let in_thread = thread_emails.iter().find(|e| e.message_id == message_id);
+            let loaded = in_thread.or_else(|| self.emails.iter().find(|e| e.message_id == message_id));
+            CitedMessage {
+                message_id: message_id.to_string(),
+                email_id: loaded.map(|e| e.id.clone()),
+                status: match (in_thread, loaded) {
+                    (Some(_), _) => "in_thread",
+                    (None, Some(_)) => "other_thread",
+                    (None, None) => "missing",
+                }
+                .to_string(),
+            }
+        };
+        let in_reply_to = email.in_reply_to.as_deref().map(cite);
+        let references: Vec<CitedMessage> = email.references.iter().map(|r| cite(r)).collect();
+
+        let parent_id = held.and_then(|_| self.resolve_parents(thread_emails).remove(&email.id));
+        let parent = parent_id.as_deref().and_then(|id| thread_emails.iter().find(|e| e.id == id));
+        let parent_source = parent.map(|parent| self.parent_source(email, parent));
+        match (parent, parent_source) {
+            (Some(parent), Some(source)) => {
+                reasons.push(format!("Parent {} found by {}", parent.id, source.replace('_', " ")))
+            }
+            _ if held.is_some() => reasons.push("A root of its thread: no cited message is in the thread".to_string()),
+            _ => {}
+        }
+        if let Some(cited) = &in_reply_to {
+            if cited.status != "in_thread" {
+                reasons.push(format!("In-Reply-To {} is {}", cited.message_id, cited.status.replace('_', " ")));
+            }
+        }
+        let missing = references.iter().filter(|r| r.status == "missing").count();
+        if missing > 0 {
+            reasons.push(format!("{} of {} references are not loaded", missing, references.len()));
+        }
+
+        Ok(ThreadingExplanation {
+            email_id: email.id.clone(),
+            threading_mode: self.get_threading_mode(),
+            thread_id: thread.map(|(key, _)| key.clone()),
+            thread_source: keyed.as_ref().map(|(_, source)| source.to_string()),
+            in_scope,
+            duplicate_of,
+            parent_id,
+            parent_source: parent_source.map(str::to_string),
+            in_reply_to,
+            references,
+            subject_key: keyed
+                .as_ref()
+                .filter(|(_, source)| *source == "subject_fallback")
+                .map(|_| topics::normalize_subject(&email.subject)),
+            reattachment,
+            reasons,
+        })
+    }
+
+    // Mirrors the precedence of resolve_parents
+    pub(crate) fn parent_source(&self, email: &EmailMessage, parent: &EmailMessage) -> &'static str {
+        if self.thread_lock_of(&email.id).is_some() {
+            return "locked";
+        }
+        if self.threading_mode == ThreadingMode::ConversationIndex {
+            let index = |e: &EmailMessage| e.conversation_index.as_deref().and_then(ConversationIndex::parse);
+            if let (Some(child), Some(parent)) = (index(email), index(parent)) {
+                let mut ancestor = child.parent();
+                while let Some(candidate) = ancestor {
+                    if candidate.to_hex() == parent.to_hex() {
+                        return "conversation_index";
+                    }
+                    ancestor = candidate.parent();
+                }
+            }
+        }
+        if email.in_reply_to.as_deref() == Some(parent.message_id.as_str()) && !parent.message_id.is_empty() {
+            "in_reply_to"
+        } else if email.references.contains(&parent.message_id) && !parent.message_id.is_empty() {
+            "references"
+        } else {
+            "reattached"
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Document types left out of threading, search and stats, e.g.
+/// `{ esi_types: ["Calendar"] }`. Values compare case-insensitively and
+/// extensions may be given with or without the dot.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TypeFilter {
+    pub file_types: Vec<String>,
+    pub file_extensions: Vec<String>,
+    pub esi_types: Vec<String>,
+}
+
+/// Document counts by FileType, file extension and ESIType, largest first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TypeBreakdown {
+    pub file_types: IndexMap<String, usize>,
+    pub file_extensions: IndexMap<String, usize>,
+    pub esi_types: IndexMap<String, usize>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Sets the document types to exclude and regroups threads if they were
+    /// already built. Pass null to exclude nothing.
+    #[wasm_bindgen]
+    pub fn set_type_filter(&mut self, filter: JsValue) -> Result<(), JsValue> {
+        self.type_filter = if filter.is_undefined() || filter.is_null() {
+            TypeFilter::default()
+        } else {
+            serde_wasm_bindgen::from_value(filter)?
+        };
+        self.audit("set_type_filter", serde_json::to_string(&self.type_filter).unwrap_or_default());
+        if !self.threads.is_empty() {
+            self.group_by_threads();
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_type_filter(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.type_filter)
+    }
+
+    /// Emails held back by the type filter.
+    #[wasm_bindgen]
+    pub fn get_excluded_emails(&self) -> Result<JsValue, JsValue> {
+        let excluded: Vec<&EmailMessage> = self.emails.iter().filter(|e| self.type_excluded(e)).collect();
+        self.to_js(&excluded)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn type_excluded(&self, email: &EmailMessage) -> bool {
+        let listed = |values: &[String], value: &str| {
+            !value.is_empty() && values.iter().any(|v| v.trim().trim_start_matches('.').eq_ignore_ascii_case(value))
+        };
+        let filter = &self.type_filter;
+        listed(&filter.file_types, email.file_type.trim())
+            || listed(&filter.file_extensions, &extension_of(email))
+            || listed(&filter.esi_types, email.esi_type.trim())
+    }
+}
+
+impl TypeBreakdown {
+    pub(crate) fn add(&mut self, email: &EmailMessage) {
+        *self.file_types.entry(label(&email.file_type)).or_default() += 1;
+        *self.file_extensions.entry(label(&extension_of(email))).or_default() += 1;
+        *self.esi_types.entry(label(&email.esi_type)).or_default() += 1;
+    }
+
+    pub(crate) fn sorted(mut self) -> Self {
+        for counts in [&mut self.file_types, &mut self.file_extensions, &mut self.esi_types] {
+            counts.sort_by(|ka, a, kb, b| b.cmp(a).then_with(|| ka.cmp(kb)));
+        }
+        self
+    }
+}
+
+/// The FileExtension column when present, otherwise the file name's extension,
+/// lowercased and without the dot.
+pub(crate) fn extension_of(email: &EmailMessage) -> String {
+    let given = email.file_extension.trim().trim_start_matches('.');
+    if !given.is_empty() {
+        return given.to_lowercase();
+    }
+    match email.file_name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.is_empty() && !ext.contains(['/', '\\', ' ']) => ext.to_lowercase(),
+        _ => String::new(),
+    }
+}
+
+pub(crate) fn label(value: &str) -> String {
+    if value.trim().is_empty() {
+        "(none)".to_string()
+    } else {
+        value.trim().to_string()
+    }
+}
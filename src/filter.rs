@@ -0,0 +1,123 @@
+use crate::{custodians, EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Working subset of the corpus, e.g. `{ start: "2021-03-01T00:00:00Z",
+/// custodians: ["Smith, J"] }`. An email is kept when it matches every set
+/// criterion: sent within `start`/`end` (RFC 3339, inclusive), held by one of
+/// `custodians`, marked with one of `confidentiality`, carrying one of
+/// `tags`, in one of `topics` (ids from `build_topics`, ignored until topics
+/// are built), given one of `classification_labels` by any model and scored at
+/// least `min_classification_score` by any model (see `apply_classifications`)
+/// from one of `productions` (see `set_production`) and, for each column in
+/// `extra` (load file columns outside the standard mapping, e.g.
+/// `{ "Review Batch": ["B-12"] }`), holding one of its values there.
+/// Empty lists match everything; values and extra column names compare
+/// case-insensitively, and confidentiality values go through the
+/// confidentiality map first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GlobalFilter {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub custodians: Vec<String>,
+    pub confidentiality: Vec<String>,
+    pub tags: Vec<String>,
+    pub topics: Vec<usize>,
+    pub classification_labels: Vec<String>,
+    pub min_classification_score: Option<f64>,
+    pub productions: Vec<String>,
+    pub extra: IndexMap<String, Vec<String>>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Scopes threads, summaries, trees, stats, search, reports and exports to
+    /// the emails the filter keeps, regrouping threads if they were already
+    /// built. Loaded data is untouched; `clear_filter` restores the full
+    /// corpus. QC checks (`verify_hashes`, `get_coding_conflicts`) and lookups
+    /// by email id still see every email.
+    #[wasm_bindgen]
+    pub fn set_global_filter(&mut self, filter: JsValue) -> Result<(), JsValue> {
+        self.global_filter = if filter.is_undefined() || filter.is_null() {
+            GlobalFilter::default()
+        } else {
+            serde_wasm_bindgen::from_value(filter)?
+        };
+        self.audit("set_global_filter", serde_json::to_string(&self.global_filter).unwrap_or_default());
+        self.regroup_after_filter_change();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_global_filter(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.global_filter)
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_filter(&mut self) {
+        self.global_filter = GlobalFilter::default();
+        self.audit("clear_filter", String::new());
+        self.regroup_after_filter_change();
+    }
+}
+
+impl EmailThreadProcessor {
+    /// Whether the global filter or a sequestration leaves the email out.
+    pub(crate) fn filtered_out(&self, email: &EmailMessage) -> bool {
+        self.is_sequestered(&email.id) || self.outside_global_filter(email)
+    }
+
+    pub(crate) fn outside_global_filter(&self, email: &EmailMessage) -> bool {
+        let filter = &self.global_filter;
+        let listed = |values: &[String], value: &str| values.iter().any(|v| v.trim().eq_ignore_ascii_case(value.trim()));
+
+        !(filter.start.is_none_or(|start| email.date_sent >= start)
+            && filter.end.is_none_or(|end| email.date_sent <= end)
+            && (filter.custodians.is_empty()
+                || custodians::custodians_of(email).iter().any(|c| listed(&filter.custodians, c)))
+            && (filter.confidentiality.is_empty()
+                || filter
+                    .confidentiality
+                    .iter()
+                    .any(|v| self.canonical_confidentiality(v).eq_ignore_ascii_case(&email.confidentiality)))
+            && (filter.tags.is_empty() || email.tags.iter().any(|t| listed(&filter.tags, t)))
+            && (filter.topics.is_empty()
+                || self.topic_model.is_none()
+                || self.email_topic(&email.id).is_some_and(|t| filter.topics.contains(&t)))
+            && (filter.classification_labels.is_empty()
+                || email
+                    .classifications
+                    .values()
+                    .filter_map(|c| c.label.as_deref())
+                    .any(|l| listed(&filter.classification_labels, l)))
+            && filter
+                .min_classification_score
+                .is_none_or(|min| email.classifications.values().any(|c| c.score.is_some_and(|s| s >= min)))
+            && (filter.productions.is_empty() || listed(&filter.productions, &email.production))
+            && filter.extra.iter().all(|(column, values)| {
+                values.is_empty()
+                    || email
+                        .extra
+                        .iter()
+                        .find(|(name, _)| name.trim().eq_ignore_ascii_case(column.trim()))
+                        .is_some_and(|(_, value)| listed(values, value))
+            }))
+    }
+
+    /// Kept by both the type filter and the global filter.
+    pub(crate) fn in_scope(&self, email: &EmailMessage) -> bool {
+        !self.type_excluded(email) && !self.filtered_out(email)
+    }
+
+    // Unlike the type filter this regroups even when no threads are built,
+    // since a filter that kept nothing leaves none behind to detect
+    fn regroup_after_filter_change(&mut self) {
+        if !self.emails.is_empty() {
+            let threads = self.group_by_threads();
+            console_log!("Global filter keeps {} emails in {} threads", self.included_emails().count(), threads);
+        }
+    }
+}
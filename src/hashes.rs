@@ -0,0 +1,148 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use wasm_bindgen::prelude::*;
+
+// Hex digest lengths of MD5, SHA-1 and SHA-256
+const DIGEST_LENGTHS: &[usize] = &[32, 40, 64];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashIssue {
+    // "malformed_hash", "metadata_mismatch" or "text_hash_mismatch"
+    pub kind: String,
+    pub hashes: Vec<String>,
+    pub email_ids: Vec<String>,
+    // Metadata fields that differ within a metadata_mismatch group
+    pub differing_fields: Vec<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashReport {
+    pub emails_checked: usize,
+    pub missing_count: usize,
+    pub malformed_count: usize,
+    pub metadata_mismatch_count: usize,
+    pub text_hash_mismatch_count: usize,
+    pub issues: Vec<HashIssue>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// QC of the Hash field: values that are not an MD5, SHA-1 or SHA-256 hex
+    /// digest, documents sharing a hash whose metadata differs (possible
+    /// vendor error) and documents with identical text under different hashes.
+    #[wasm_bindgen]
+    pub fn verify_hashes(&self) -> Result<JsValue, JsValue> {
+        let report = self.hash_report();
+        console_log!(
+            "Hash check: {} malformed, {} metadata mismatches, {} text mismatches",
+            report.malformed_count,
+            report.metadata_mismatch_count,
+            report.text_hash_mismatch_count
+        );
+        self.to_js(&report)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn hash_report(&self) -> HashReport {
+        let mut report = HashReport {
+            emails_checked: self.emails.len(),
+            missing_count: 0,
+            malformed_count: 0,
+            metadata_mismatch_count: 0,
+            text_hash_mismatch_count: 0,
+            issues: Vec::new(),
+        };
+        let mut by_hash: IndexMap<String, Vec<&EmailMessage>> = IndexMap::new();
+        let mut by_text: IndexMap<String, Vec<&EmailMessage>> = IndexMap::new();
+
+        for email in &self.emails {
+            let hash = email.hash.trim();
+            if hash.is_empty() {
+                report.missing_count += 1;
+                continue;
+            }
+            if !is_digest(hash) {
+                report.malformed_count += 1;
+                report.issues.push(HashIssue {
+                    kind: "malformed_hash".to_string(),
+                    hashes: vec![hash.to_string()],
+                    email_ids: vec![email.id.clone()],
+                    differing_fields: Vec::new(),
+                    message: format!("Hash is not an MD5, SHA-1 or SHA-256 hex digest ({} characters)", hash.len()),
+                });
+            }
+            by_hash.entry(hash.to_lowercase()).or_default().push(email);
+            let text = email.full_text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !text.is_empty() {
+                by_text.entry(text).or_default().push(email);
+            }
+        }
+
+        for (hash, group) in by_hash.iter().filter(|(_, g)| g.len() > 1) {
+            let differing_fields = differing_fields(group);
+            if differing_fields.is_empty() {
+                continue;
+            }
+            report.metadata_mismatch_count += 1;
+            report.issues.push(HashIssue {
+                kind: "metadata_mismatch".to_string(),
+                hashes: vec![hash.clone()],
+                email_ids: group.iter().map(|e| e.id.clone()).collect(),
+                message: format!("{} documents share a hash but differ in {}", group.len(), differing_fields.join(", ")),
+                differing_fields,
+            });
+        }
+
+        for group in by_text.values() {
+            let hashes: BTreeSet<String> = group.iter().map(|e| e.hash.trim().to_lowercase()).collect();
+            if hashes.len() < 2 {
+                continue;
+            }
+            report.text_hash_mismatch_count += 1;
+            report.issues.push(HashIssue {
+                kind: "text_hash_mismatch".to_string(),
+                email_ids: group.iter().map(|e| e.id.clone()).collect(),
+                message: format!("{} documents have identical text under {} different hashes", group.len(), hashes.len()),
+                hashes: hashes.into_iter().collect(),
+                differing_fields: Vec::new(),
+            });
+        }
+
+        report
+    }
+}
+
+fn is_digest(hash: &str) -> bool {
+    DIGEST_LENGTHS.contains(&hash.len()) && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// Fields that identify the message itself; custodian, Bates and paths
+// legitimately differ between copies of a duplicate
+fn differing_fields(group: &[&EmailMessage]) -> Vec<String> {
+    ["from", "to", "cc", "subject", "date_sent", "message_id", "file_type"]
+        .iter()
+        .filter(|field| group.iter().map(|e| field_value(e, field)).collect::<BTreeSet<_>>().len() > 1)
+        .map(|field| field.to_string())
+        .collect()
+}
+
+fn field_value(email: &EmailMessage, field: &str) -> String {
+    match field {
+        "from" => email.from.trim().to_lowercase(),
+        "to" => recipients(&email.to),
+        "cc" => recipients(&email.cc),
+        "subject" => email.subject.trim().to_string(),
+        "date_sent" => email.date_sent.to_rfc3339(),
+        "message_id" => email.message_id.clone(),
+        _ => email.file_type.trim().to_lowercase(),
+    }
+}
+
+fn recipients(addresses: &[String]) -> String {
+    let sorted: BTreeSet<String> = addresses.iter().map(|a| a.trim().to_lowercase()).collect();
+    sorted.into_iter().collect::<Vec<_>>().join(";")
+}
@@ -0,0 +1,126 @@
+use crate::search::{self, HitOffset, Query};
+use crate::{EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Set name -> offsets of that set's matches within one email.
+pub type Highlights = IndexMap<String, Vec<HitOffset>>;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HighlightTermsInput {
+    // A bare list is stored as the "default" set
+    List(Vec<String>),
+    Sets(IndexMap<String, Vec<String>>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighlightedEmail {
+    pub email: EmailMessage,
+    pub highlights: Highlights,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailBody {
+    pub email_id: String,
+    pub subject: String,
+    pub full_text: String,
+    pub highlights: Highlights,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Configures term sets whose matches are returned with every email from
+    /// the tree, flat and body APIs. Takes a list of terms or an object of
+    /// named lists (e.g. `{"hot": [...], "privilege": [...]}`); each term uses
+    /// the `search` query syntax.
+    #[wasm_bindgen]
+    pub fn set_highlight_terms(&mut self, terms: JsValue) -> Result<(), JsValue> {
+        let input: HighlightTermsInput = serde_wasm_bindgen::from_value(terms)?;
+        let sets = match input {
+            HighlightTermsInput::List(terms) => IndexMap::from([("default".to_string(), terms)]),
+            HighlightTermsInput::Sets(sets) => sets,
+        };
+
+        let mut parsed = IndexMap::new();
+        for (name, terms) in sets {
+            let queries = terms
+                .iter()
+                .map(|term| search::parse_query(term).map_err(|e| JsValue::from_str(&format!("Highlight set {}: {}", name, e))))
+                .collect::<Result<Vec<_>, _>>()?;
+            parsed.insert(name, queries);
+        }
+        self.highlight_terms = parsed;
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_highlight_terms(&mut self) {
+        self.highlight_terms.clear();
+    }
+
+    /// Emails of a thread in thread order, without building the tree.
+    #[wasm_bindgen]
+    pub fn get_thread_emails(&self, thread_id: &str) -> Result<JsValue, JsValue> {
+        let emails = self
+            .threads
+            .get(thread_id)
+            .ok_or_else(|| JsValue::from_str("Thread not found"))?;
+        let emails: Vec<HighlightedEmail> = emails
+            .iter()
+            .map(|e| HighlightedEmail {
+                email: e.clone(),
+                highlights: self.highlights_for(&e.id),
+            })
+            .collect();
+        self.to_js(&emails)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_email_body(&self, email_id: &str) -> Result<JsValue, JsValue> {
+        let email = self
+            .emails
+            .iter()
+            .find(|e| e.id == email_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Email not found: {}", email_id)))?;
+        if self.is_sequestered(email_id) {
+            return Err(JsValue::from_str(&format!("Email is sequestered: {}", email_id)));
+        }
+        self.to_js(&self.email_body(email))
+    }
+}
+
+impl EmailThreadProcessor {
+    // None for sequestered emails: the index still holds their text
+    pub(crate) fn highlights_for(&self, email_id: &str) -> Highlights {
+        let mut highlights = Highlights::new();
+        if self.highlight_terms.is_empty() || self.is_sequestered(email_id) {
+            return highlights;
+        }
+        let index = self.search_index();
+        let Some(doc) = index.doc_of(email_id) else {
+            return highlights;
+        };
+
+        for (name, queries) in &self.highlight_terms {
+            let mut spans: Vec<(u32, u32)> = queries.iter().flat_map(|q: &Query| index.spans(q, doc)).collect();
+            if spans.is_empty() {
+                continue;
+            }
+            spans.sort_unstable();
+            spans.dedup();
+            highlights.insert(name.clone(), search::hit_offsets(&self.emails[doc], &spans));
+        }
+        highlights
+    }
+
+    fn email_body(&self, email: &EmailMessage) -> EmailBody {
+        EmailBody {
+            email_id: email.id.clone(),
+            subject: email.subject.clone(),
+            full_text: email.full_text.clone(),
+            highlights: self.highlights_for(&email.id),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::search::{self, parse_query};
+use crate::EmailThreadProcessor;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+const DEFAULT_WINDOW: usize = 8;
+// Longer "sentences" are usually unpunctuated runs, such as tables or
+// signatures, so the word window is shown instead
+const MAX_SENTENCE_LEN: usize = 400;
+
+/// One search hit in its surroundings, for a keyword-in-context preview.
+/// Snippets have their whitespace collapsed to single spaces.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HitContext {
+    // "subject" or "full_text"
+    pub field: String,
+    // UTF-16 code units into the field, as in `get_search_hits`
+    pub start: usize,
+    pub end: usize,
+    pub hit: String,
+    // Up to `window` words either side of the hit, within the field
+    pub before: String,
+    pub after: String,
+    // The sentence containing the hit, or before + hit + after when the
+    // sentence is too long to preview
+    pub sentence: String,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// The context of each place `term` (any `search` query) matches in an
+    /// email: the words either side (`window`, default 8) and the enclosing
+    /// sentence, so result lists can preview hits without fetching bodies.
+    /// Empty when the email does not match or is outside the current filters.
+    #[wasm_bindgen]
+    pub fn get_hit_context(&self, email_id: &str, term: &str, window: Option<usize>) -> Result<JsValue, JsValue> {
+        let query = parse_query(term).map_err(|e| JsValue::from_str(&e))?;
+        let index = self.search_index();
+        let doc = index
+            .doc_of(email_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Email not found: {}", email_id)))?;
+        let window = window.unwrap_or(DEFAULT_WINDOW);
+
+        let contexts = if index.evaluate(&query).contains(&doc) && self.in_scope(&self.emails[doc]) {
+            let email = &self.emails[doc];
+            let text = search::indexed_text(email);
+            let tokens = search::tokenize(&text);
+            let body_start = email.subject.len() + 1;
+
+            index
+                .spans(&query, doc)
+                .into_iter()
+                .filter_map(|(first, last)| {
+                    let (first, last) = (first as usize, last as usize);
+                    let start = tokens.get(first)?.1 .0;
+                    let end = tokens.get(last)?.1 .1;
+                    let (field, lo, hi) = if start < body_start {
+                        ("subject", 0, email.subject.len())
+                    } else {
+                        ("full_text", body_start, text.len())
+                    };
+                    let in_field = |i: &usize| tokens[*i].1 .0 >= lo && tokens[*i].1 .1 <= hi;
+                    let from = (first.saturating_sub(window)..first).find(in_field).map_or(start, |i| tokens[i].1 .0);
+                    let to = (last + 1..(last + 1 + window).min(tokens.len()))
+                        .rev()
+                        .find(in_field)
+                        .map_or(end, |i| tokens[i].1 .1);
+
+                    let before = squash(&text[from..start]);
+                    let hit = squash(&text[start..end]);
+                    let after = squash(&text[end..to]);
+                    let (s, e) = sentence_bounds(&text, start, end, lo, hi);
+                    let sentence = if e - s <= MAX_SENTENCE_LEN {
+                        squash(&text[s..e])
+                    } else {
+                        [before.as_str(), hit.as_str(), after.as_str()]
+                            .iter()
+                            .filter(|part| !part.is_empty())
+                            .copied()
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    };
+                    Some(HitContext {
+                        field: field.to_string(),
+                        start: search::utf16_len(&text[lo..start]),
+                        end: search::utf16_len(&text[lo..end]),
+                        hit,
+                        before,
+                        after,
+                        sentence,
+                    })
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.to_js(&contexts)
+    }
+}
+
+// Byte range of the sentence around [start, end), kept within [lo, hi). A
+// sentence ends at ., ! or ? followed by whitespace, or at a blank line.
+fn sentence_bounds(text: &str, start: usize, end: usize, lo: usize, hi: usize) -> (usize, usize) {
+    let bytes = text.as_bytes();
+    let ends_sentence = |i: usize| {
+        matches!(bytes[i], b'.' | b'!' | b'?') && (i + 1 >= hi || bytes[i + 1].is_ascii_whitespace())
+            || bytes[i] == b'\n' && i + 1 < hi && bytes[i + 1] == b'\n'
+    };
+    // Only ASCII bytes are tested, so every cut lands on a char boundary
+    let s = (lo..start).rev().find(|&i| ends_sentence(i)).map_or(lo, |i| i + 1);
+    let e = (end..hi).find(|&i| ends_sentence(i)).map_or(hi, |i| if bytes[i] == b'\n' { i } else { i + 1 });
+    (s, e)
+}
+
+fn squash(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
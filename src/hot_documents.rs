@@ -0,0 +1,259 @@
+use crate::search;
+use crate::{network, EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use wasm_bindgen::prelude::*;
+
+/// Points each signal adds to an email's score.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignalWeights {
+    // Per distinct search term matched
+    pub search_terms: f64,
+    // Once, when any privilege term matches
+    pub privilege_terms: f64,
+    // Once, when an internal sender wrote to someone outside
+    pub external_disclosure: f64,
+    // Per key player among the sender and recipients
+    pub key_players: f64,
+    pub after_hours: f64,
+}
+
+impl Default for SignalWeights {
+    fn default() -> Self {
+        SignalWeights {
+            search_terms: 2.0,
+            privilege_terms: 1.5,
+            external_disclosure: 1.0,
+            key_players: 1.0,
+            after_hours: 0.5,
+        }
+    }
+}
+
+/// What makes a document hot. Terms use the `search` query syntax; with no
+/// `key_players` the top `key_player_count` of `get_key_players` are used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringConfig {
+    pub search_terms: Vec<String>,
+    pub privilege_terms: Vec<String>,
+    pub key_players: Vec<String>,
+    pub key_player_count: usize,
+    // Business hours in the custodians' local time, [start, end)
+    pub business_start_hour: u32,
+    pub business_end_hour: u32,
+    pub utc_offset_hours: i64,
+    pub weights: SignalWeights,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            search_terms: Vec::new(),
+            privilege_terms: ["privileged", "attorney client", "legal advice", "work product", "counsel"]
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+            key_players: Vec::new(),
+            key_player_count: 5,
+            business_start_hour: 8,
+            business_end_hour: 18,
+            utc_offset_hours: 0,
+            weights: SignalWeights::default(),
+        }
+    }
+}
+
+/// One signal's contribution to a score.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreSignal {
+    // "search_terms", "privilege_terms", "external_disclosure",
+    // "key_players" or "after_hours"
+    pub signal: String,
+    // The terms or people matched, when there are any
+    pub matched: Vec<String>,
+    pub points: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotDocument {
+    pub rank: usize,
+    pub email_id: String,
+    pub beg_bates: String,
+    pub thread_id: Option<String>,
+    pub subject: String,
+    pub from: String,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub date_sent: DateTime<Utc>,
+    pub score: f64,
+    pub signals: Vec<ScoreSignal>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Replaces the scoring configuration with a `ScoringConfig` object;
+    /// omitted fields take their defaults.
+    #[wasm_bindgen]
+    pub fn set_scoring_config(&mut self, config: JsValue) -> Result<(), JsValue> {
+        let config: ScoringConfig = serde_wasm_bindgen::from_value(config)?;
+        if config.business_start_hour > 24 || config.business_end_hour > 24 {
+            return Err(JsValue::from_str("Business hours must be between 0 and 24"));
+        }
+        if !(-14..=14).contains(&config.utc_offset_hours) {
+            return Err(JsValue::from_str("UTC offset must be between -14 and 14 hours"));
+        }
+        for term in config.search_terms.iter().chain(&config.privilege_terms) {
+            search::parse_query(term).map_err(|e| JsValue::from_str(&format!("Scoring term {}: {}", term, e)))?;
+        }
+        self.scoring_config = config;
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_scoring_config(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.scoring_config)
+    }
+
+    /// The `n` highest-scoring emails for early case assessment, each with
+    /// the signals behind its score. Emails scoring zero are left out.
+    #[wasm_bindgen]
+    pub fn get_hot_documents(&self, n: Option<usize>) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let mut documents = self.hot_documents()?;
+        documents.truncate(n.unwrap_or(documents.len()));
+        console_log!("Ranked {} hot documents", documents.len());
+        self.to_js(&documents)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn hot_documents(&self) -> Result<Vec<HotDocument>, JsValue> {
+        let config = &self.scoring_config;
+        let search_hits = self.term_hits(&config.search_terms)?;
+        let privilege_hits = self.term_hits(&config.privilege_terms)?;
+        let key_players: HashSet<String> = if config.key_players.is_empty() {
+            self.identity_ranks()?
+                .into_iter()
+                .take(config.key_player_count)
+                .map(|r| r.identity)
+                .collect()
+        } else {
+            config.key_players.iter().filter_map(|p| self.lookup_identity(p)).collect()
+        };
+
+        let total = self.emails.len();
+        let mut documents = Vec::new();
+        for (doc, email) in self.emails.iter().enumerate() {
+            self.check_cancelled()?;
+            if !self.in_scope(email) {
+                continue;
+            }
+            let mut signals = Vec::new();
+            let matched = |hits: &[(String, BTreeSet<usize>)]| -> Vec<String> {
+                hits.iter().filter(|(_, docs)| docs.contains(&doc)).map(|(term, _)| term.clone()).collect()
+            };
+
+            let terms = matched(&search_hits);
+            if !terms.is_empty() {
+                let points = config.weights.search_terms * terms.len() as f64;
+                signals.push(signal("search_terms", terms, points));
+            }
+            let terms = matched(&privilege_hits);
+            if !terms.is_empty() {
+                signals.push(signal("privilege_terms", terms, config.weights.privilege_terms));
+            }
+            if self.discloses_externally(email) {
+                signals.push(signal("external_disclosure", Vec::new(), config.weights.external_disclosure));
+            }
+            let players: Vec<String> = std::iter::once(&email.from)
+                .chain(&email.to)
+                .chain(&email.cc)
+                .chain(&email.bcc)
+                .flat_map(|a| self.identity_map.identities(a))
+                .filter(|i| key_players.contains(i))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            if !players.is_empty() {
+                let points = config.weights.key_players * players.len() as f64;
+                signals.push(signal("key_players", players, points));
+            }
+            if self.after_hours(email) {
+                signals.push(signal("after_hours", Vec::new(), config.weights.after_hours));
+            }
+
+            let score: f64 = signals.iter().map(|s| s.points).sum();
+            if score > 0.0 {
+                documents.push(HotDocument {
+                    rank: 0,
+                    email_id: email.id.clone(),
+                    beg_bates: email.beg_bates.clone(),
+                    thread_id: self.thread_key(email),
+                    subject: email.subject.clone(),
+                    from: email.from.clone(),
+                    date_sent: email.date_sent,
+                    score,
+                    signals,
+                });
+            }
+            self.emit_progress("scoring", doc + 1, Some(total));
+        }
+
+        documents.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.date_sent.cmp(&b.date_sent))
+                .then_with(|| a.email_id.cmp(&b.email_id))
+        });
+        for (i, document) in documents.iter_mut().enumerate() {
+            document.rank = i + 1;
+        }
+        Ok(documents)
+    }
+
+    // Each term with the indices into `self.emails` it matches
+    fn term_hits(&self, terms: &[String]) -> Result<Vec<(String, BTreeSet<usize>)>, JsValue> {
+        terms
+            .iter()
+            .map(|term| {
+                let query = search::parse_query(term).map_err(|e| JsValue::from_str(&e))?;
+                Ok((term.clone(), self.search_index().evaluate(&query)))
+            })
+            .collect()
+    }
+
+    // An internal sender writing outside the internal domains; without
+    // configured domains, the load file's external flag
+    fn discloses_externally(&self, email: &EmailMessage) -> bool {
+        if self.internal_domains.is_empty() {
+            return email.is_external;
+        }
+        let internal = |address: &String| address.rsplit_once('@').is_some_and(|(_, d)| self.is_internal_domain(d));
+        network::identities(&email.from).first().is_some_and(internal)
+            && email
+                .to
+                .iter()
+                .chain(&email.cc)
+                .chain(&email.bcc)
+                .flat_map(|r| network::identities(r))
+                .any(|r| !internal(&r))
+    }
+
+    fn after_hours(&self, email: &EmailMessage) -> bool {
+        let config = &self.scoring_config;
+        let local = email.date_sent + Duration::hours(config.utc_offset_hours);
+        matches!(local.weekday(), Weekday::Sat | Weekday::Sun)
+            || local.hour() < config.business_start_hour
+            || local.hour() >= config.business_end_hour
+    }
+}
+
+fn signal(name: &str, matched: Vec<String>, points: f64) -> ScoreSignal {
+    ScoreSignal {
+        signal: name.to_string(),
+        matched,
+        points,
+    }
+}
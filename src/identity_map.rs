@@ -0,0 +1,193 @@
+use crate::{network, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+const IDENTITY_MAP_HEADERS: &[&str] = &["Person", "Address"];
+
+/// Which addresses belong to one person, maintained outside the tool and
+/// imported with `import_identity_map`. Mapped addresses are reported as the
+/// person's name in participant stats, the communication graph and search
+/// facets.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "IndexMap<String, Vec<String>>", into = "IndexMap<String, Vec<String>>")]
+pub(crate) struct IdentityMap {
+    // Person -> lowercased bare addresses, in import order
+    persons: IndexMap<String, Vec<String>>,
+    // Address -> person
+    by_address: HashMap<String, String>,
+}
+
+impl IdentityMap {
+    /// Builds the map from person -> addresses, failing when an address is
+    /// given to two people.
+    pub(crate) fn new(persons: IndexMap<String, Vec<String>>) -> Result<IdentityMap, String> {
+        let mut map = IdentityMap::default();
+        for (person, addresses) in persons {
+            let person = person.trim().to_string();
+            if person.is_empty() {
+                continue;
+            }
+            let entry = map.persons.entry(person.clone()).or_default();
+            for address in addresses.iter().flat_map(|a| network::identities(a)) {
+                match map.by_address.get(&address) {
+                    Some(other) if *other != person => {
+                        return Err(format!("Address {} is mapped to both {} and {}", address, other, person));
+                    }
+                    Some(_) => {}
+                    None => {
+                        map.by_address.insert(address.clone(), person.clone());
+                        entry.push(address);
+                    }
+                }
+            }
+        }
+        Ok(map)
+    }
+
+    pub(crate) fn person_of(&self, address: &str) -> Option<&str> {
+        self.by_address.get(address).map(String::as_str)
+    }
+
+    /// The person an identity (a lowercased bare address) belongs to, or the
+    /// identity itself when unmapped.
+    pub(crate) fn resolve(&self, identity: String) -> String {
+        match self.by_address.get(&identity) {
+            Some(person) => person.clone(),
+            None => identity,
+        }
+    }
+
+    /// `network::identities` with mapped addresses replaced by their person,
+    /// each listed once.
+    pub(crate) fn identities(&self, field: &str) -> Vec<String> {
+        let mut identities: Vec<String> = Vec::new();
+        for identity in network::identities(field).into_iter().map(|i| self.resolve(i)) {
+            if !identities.contains(&identity) {
+                identities.push(identity);
+            }
+        }
+        identities
+    }
+
+    pub(crate) fn person_count(&self) -> usize {
+        self.persons.len()
+    }
+
+    /// A person's name as mapped, matched case-insensitively.
+    pub(crate) fn find_person(&self, name: &str) -> Option<&str> {
+        let name = name.trim();
+        self.persons.keys().find(|p| p.eq_ignore_ascii_case(name)).map(String::as_str)
+    }
+}
+
+impl From<IndexMap<String, Vec<String>>> for IdentityMap {
+    // Snapshots hold maps that were validated on import
+    fn from(persons: IndexMap<String, Vec<String>>) -> Self {
+        IdentityMap::new(persons).unwrap_or_default()
+    }
+}
+
+impl From<IdentityMap> for IndexMap<String, Vec<String>> {
+    fn from(map: IdentityMap) -> Self {
+        map.persons
+    }
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Replaces the person-to-addresses mapping. Takes JSON, either
+    /// `{"Jane Smith": ["jane@acme.com", "jsmith@gmail.com"]}` or an array of
+    /// `{person, addresses}` objects, or CSV with Person and Address columns
+    /// (one row per address, or several addresses separated by semicolons).
+    /// Returns the number of addresses mapped.
+    #[wasm_bindgen]
+    pub fn import_identity_map(&mut self, data: &str) -> Result<usize, JsValue> {
+        let data = data.trim_start_matches('\u{feff}').trim();
+        if data.is_empty() {
+            return Err(JsValue::from_str("Identity map is empty"));
+        }
+
+        let persons = if data.starts_with('{') || data.starts_with('[') {
+            parse_json(data)
+        } else {
+            parse_csv(data)
+        }
+        .map_err(|e| JsValue::from_str(&format!("Error reading identity map: {}", e)))?;
+        self.identity_map = IdentityMap::new(persons).map_err(|e| JsValue::from_str(&e))?;
+
+        let count = self.identity_map.by_address.len();
+        console_log!("Identity map imported: {} addresses for {} people", count, self.identity_map.persons.len());
+        self.audit_load(
+            "import_identity_map",
+            format!("{} addresses for {} people", count, self.identity_map.persons.len()),
+            &[("identity map", data.as_bytes())],
+        );
+        Ok(count)
+    }
+
+    /// The mapping as `import_identity_map` reads it back. `format` is "json"
+    /// (default) or "csv".
+    #[wasm_bindgen]
+    pub fn export_identity_map(&self, format: Option<String>) -> Result<String, JsValue> {
+        match format.as_deref().unwrap_or("json") {
+            "json" => serde_json::to_string_pretty(&self.identity_map).map_err(|e| JsValue::from_str(&e.to_string())),
+            "csv" => to_csv(&self.identity_map),
+            other => Err(JsValue::from_str(&format!("Unknown export format: {}", other))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PersonRecord {
+    person: String,
+    #[serde(default)]
+    addresses: Vec<String>,
+}
+
+fn parse_json(data: &str) -> Result<IndexMap<String, Vec<String>>, String> {
+    if data.starts_with('[') {
+        let records: Vec<PersonRecord> = serde_json::from_str(data).map_err(|e| e.to_string())?;
+        let mut persons: IndexMap<String, Vec<String>> = IndexMap::new();
+        for record in records {
+            persons.entry(record.person).or_default().extend(record.addresses);
+        }
+        Ok(persons)
+    } else {
+        serde_json::from_str(data).map_err(|e| e.to_string())
+    }
+}
+
+fn parse_csv(data: &str) -> Result<IndexMap<String, Vec<String>>, String> {
+    let mut rdr = csv::ReaderBuilder::new().flexible(true).from_reader(data.as_bytes());
+    let headers = rdr.headers().map_err(|e| e.to_string())?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+    let (Some(person_col), Some(address_col)) = (column("Person"), column("Address").or_else(|| column("Addresses"))) else {
+        return Err("CSV needs Person and Address columns".to_string());
+    };
+
+    let mut persons: IndexMap<String, Vec<String>> = IndexMap::new();
+    for (i, record) in rdr.records().enumerate() {
+        let record = record.map_err(|e| format!("row {}: {}", i + 1, e))?;
+        let person = record.get(person_col).unwrap_or_default();
+        let addresses = record.get(address_col).unwrap_or_default();
+        persons.entry(person.to_string()).or_default().push(addresses.to_string());
+    }
+    Ok(persons)
+}
+
+fn to_csv(map: &IdentityMap) -> Result<String, JsValue> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let to_js = |e: csv::Error| JsValue::from_str(&format!("Error writing identity map: {}", e));
+
+    writer.write_record(IDENTITY_MAP_HEADERS).map_err(to_js)?;
+    for (person, addresses) in &map.persons {
+        for address in addresses {
+            writer.write_record([person.as_str(), address.as_str()]).map_err(to_js)?;
+        }
+    }
+
+    let data = writer.into_inner().map_err(|e| JsValue::from_str(&e.to_string()))?;
+    String::from_utf8(data).map_err(|e| JsValue::from_str(&e.to_string()))
+}
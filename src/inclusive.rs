@@ -0,0 +1,100 @@
+use crate::EmailThreadProcessor;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+// Lines that open the quoted part of a reply or forward; everything from the
+// first of these on is treated as earlier messages, not new content.
+const QUOTE_HEADERS: &[&str] = &[
+    "-----original message-----",
+    "----- original message -----",
+    "-----forwarded message-----",
+    "---------- forwarded message ----------",
+    "begin forwarded message:",
+];
+
+/// The text an email adds itself: the body up to the first quote header,
+/// "On ... wrote:" line or `>`-quoted line.
+pub(crate) fn new_content(text: &str) -> &str {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim().to_lowercase();
+        let starts_quote = trimmed.starts_with('>')
+            || QUOTE_HEADERS.iter().any(|h| trimmed.starts_with(h))
+            || (trimmed.starts_with("on ") && trimmed.ends_with("wrote:"))
+            || (trimmed.starts_with("from:") && offset > 0);
+        if starts_quote {
+            return &text[..offset];
+        }
+        offset += line.len();
+    }
+    text
+}
+
+/// Lowercased words with quote markers and punctuation dropped, so a quoted
+/// copy re-wrapped by a mail client still compares equal.
+pub(crate) fn normalized_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Whether `later` quotes all of the new content of `earlier`.
+pub(crate) fn is_contained(earlier: &str, later: &str) -> bool {
+    let earlier = normalized_words(new_content(earlier));
+    let later = normalized_words(later);
+    if earlier.is_empty() {
+        return true;
+    }
+    later.windows(earlier.len()).any(|w| w == earlier.as_slice())
+}
+
+/// Share of the new content of `earlier` that `later` repeats, from 0 to 1:
+/// the fraction of its three-word runs (single words for shorter texts) found
+/// anywhere in `later`. 1.0 whenever `is_contained` holds.
+pub(crate) fn containment_score(earlier: &str, later: &str) -> f64 {
+    if is_contained(earlier, later) {
+        return 1.0;
+    }
+    let earlier = normalized_words(new_content(earlier));
+    let later = normalized_words(later);
+    let size = earlier.len().min(3);
+    let found: HashSet<&[String]> = later.windows(size).collect();
+    let runs: Vec<&[String]> = earlier.windows(size).collect();
+    runs.iter().filter(|run| found.contains(*run)).count() as f64 / runs.len() as f64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Containment {
+    pub email_a: String,
+    pub email_b: String,
+    // B repeats all of A's new content, whitespace and quote markers aside
+    pub contained: bool,
+    pub score: f64,
+    pub new_content_words: usize,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Whether email B's text includes all of the new content of email A (the
+    /// test behind inclusive-email flags), with a 0-1 score of how much of it
+    /// B repeats. Both are looked up by email id across the whole load.
+    #[wasm_bindgen]
+    pub fn is_content_contained(&self, email_a: &str, email_b: &str) -> Result<JsValue, JsValue> {
+        let text_of = |id: &str| {
+            self.email_index(id)
+                .map(|i| self.emails[i].full_text.as_str())
+                .ok_or_else(|| JsValue::from_str(&format!("Email not found: {}", id)))
+        };
+        let (a, b) = (text_of(email_a)?, text_of(email_b)?);
+        let containment = Containment {
+            email_a: email_a.to_string(),
+            email_b: email_b.to_string(),
+            contained: is_contained(a, b),
+            score: containment_score(a, b),
+            new_content_words: normalized_words(new_content(a)).len(),
+        };
+        self.to_js(&containment)
+    }
+}
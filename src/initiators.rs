@@ -0,0 +1,155 @@
+use crate::{mass_mail, EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use wasm_bindgen::prelude::*;
+
+/// Who started a thread.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadInitiator {
+    pub thread_id: String,
+    pub subject: String,
+    // The sender of the earliest root email, as a mapped person or address
+    pub initiator: String,
+    pub email_id: String,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub started_at: DateTime<Utc>,
+}
+
+/// How one identity takes part in conversations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommunicationRole {
+    pub identity: String,
+    pub threads_started: usize,
+    // Threads they sent or received in without starting them
+    pub threads_joined: usize,
+    pub messages_sent: usize,
+    // Their emails answering someone else's, and others' answering theirs
+    pub replies_given: usize,
+    pub replies_received: usize,
+    // Mean distinct recipients of the emails they sent
+    pub average_audience: f64,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// The initiator of every visible thread: who sent its earliest root
+    /// email, and when.
+    #[wasm_bindgen]
+    pub fn get_thread_initiators(&self) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let initiators = self.thread_initiators()?;
+        self.to_js(&initiators)
+    }
+
+    /// Threads started and joined, replies given and received and average
+    /// audience per identity, to tell those who drive conversations from
+    /// those copied on them. Limited to `identity` (an address or mapped
+    /// person) when given; otherwise everyone, most threads first.
+    #[wasm_bindgen]
+    pub fn get_communication_roles(&self, identity: Option<String>) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let wanted = match &identity {
+            Some(identity) => {
+                Some(self.lookup_identity(identity).ok_or_else(|| JsValue::from_str("Invalid identity"))?)
+            }
+            None => None,
+        };
+        let initiators: IndexMap<String, String> =
+            self.thread_initiators()?.into_iter().map(|t| (t.thread_id, t.initiator)).collect();
+
+        let mut roles: IndexMap<String, CommunicationRole> = IndexMap::new();
+        let mut audiences: IndexMap<String, usize> = IndexMap::new();
+        for (thread_id, emails) in self.visible_threads() {
+            self.check_cancelled()?;
+            let parents = self.resolve_parents(emails);
+            let mut participants = BTreeSet::new();
+            for email in emails {
+                let sender = self.sender_identity(email);
+                participants.extend(sender.clone());
+                for field in email.to.iter().chain(&email.cc).chain(&email.bcc) {
+                    participants.extend(self.identity_map.identities(field));
+                }
+                let Some(sender) = sender else {
+                    continue;
+                };
+
+                let role = role_of(&mut roles, &sender);
+                role.messages_sent += 1;
+                *audiences.entry(sender.clone()).or_default() += mass_mail::recipient_count(email);
+                let parent_sender = parents
+                    .get(&email.id)
+                    .and_then(|p| emails.iter().find(|e| &e.id == p))
+                    .and_then(|parent| self.sender_identity(parent))
+                    .filter(|parent_sender| *parent_sender != sender);
+                if let Some(parent_sender) = parent_sender {
+                    role.replies_given += 1;
+                    role_of(&mut roles, &parent_sender).replies_received += 1;
+                }
+            }
+
+            let initiator = initiators.get(thread_id);
+            if let Some(initiator) = initiator {
+                role_of(&mut roles, initiator).threads_started += 1;
+            }
+            for participant in participants.iter().filter(|p| Some(*p) != initiator) {
+                role_of(&mut roles, participant).threads_joined += 1;
+            }
+        }
+
+        let mut roles: Vec<CommunicationRole> = roles
+            .into_values()
+            .filter(|role| wanted.as_ref().is_none_or(|w| *w == role.identity))
+            .map(|mut role| {
+                if role.messages_sent > 0 {
+                    role.average_audience = audiences[&role.identity] as f64 / role.messages_sent as f64;
+                }
+                role
+            })
+            .collect();
+        roles.sort_by(|a, b| {
+            (b.threads_started + b.threads_joined)
+                .cmp(&(a.threads_started + a.threads_joined))
+                .then(b.threads_started.cmp(&a.threads_started))
+                .then_with(|| a.identity.cmp(&b.identity))
+        });
+        self.to_js(&roles)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn thread_initiators(&self) -> Result<Vec<ThreadInitiator>, JsValue> {
+        let mut initiators = Vec::new();
+        for (thread_id, emails) in self.visible_threads() {
+            self.check_cancelled()?;
+            let parents = self.resolve_parents(emails);
+            let first = emails
+                .iter()
+                .filter(|e| !parents.contains_key(&e.id))
+                .min_by(|a, b| a.date_sent.cmp(&b.date_sent).then_with(|| a.id.cmp(&b.id)));
+            let Some((email, initiator)) = first.and_then(|e| Some((e, self.sender_identity(e)?))) else {
+                continue;
+            };
+            initiators.push(ThreadInitiator {
+                thread_id: thread_id.clone(),
+                subject: email.subject.clone(),
+                initiator,
+                email_id: email.id.clone(),
+                started_at: email.date_sent,
+            });
+        }
+        Ok(initiators)
+    }
+
+    fn sender_identity(&self, email: &EmailMessage) -> Option<String> {
+        self.identity_map.identities(&email.from).into_iter().next()
+    }
+}
+
+fn role_of<'a>(roles: &'a mut IndexMap<String, CommunicationRole>, identity: &str) -> &'a mut CommunicationRole {
+    roles.entry(identity.to_string()).or_insert_with(|| CommunicationRole {
+        identity: identity.to_string(),
+        ..Default::default()
+    })
+}
@@ -0,0 +1,147 @@
+use crate::{EmailThreadProcessor, ThreadNode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadViolation {
+    pub thread_id: String,
+    pub email_id: String,
+    pub kind: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadVerification {
+    pub thread_id: String,
+    pub email_count: usize,
+    pub reachable_count: usize,
+    pub is_valid: bool,
+    pub violations: Vec<ThreadViolation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub threads_checked: usize,
+    pub invalid_threads: usize,
+    pub violation_counts: HashMap<String, usize>,
+    // Only threads with at least one violation
+    pub threads: Vec<ThreadVerification>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Checks the tree built for `thread_id`: every email reachable from the
+    /// roots exactly once, child depth one below its parent and no parent
+    /// cycles.
+    #[wasm_bindgen]
+    pub fn verify_thread(&self, thread_id: &str) -> Result<JsValue, JsValue> {
+        let verification = self.verify(thread_id)?;
+        self.to_js(&verification)
+    }
+
+    #[wasm_bindgen]
+    pub fn verify_all(&self) -> Result<JsValue, JsValue> {
+        console_log!("Verifying {} threads", self.threads.len());
+
+        let mut report = IntegrityReport {
+            threads_checked: 0,
+            invalid_threads: 0,
+            violation_counts: HashMap::new(),
+            threads: Vec::new(),
+        };
+        for thread_id in self.threads.keys() {
+            let verification = self.verify(thread_id)?;
+            report.threads_checked += 1;
+            if verification.is_valid {
+                continue;
+            }
+            report.invalid_threads += 1;
+            for violation in &verification.violations {
+                *report.violation_counts.entry(violation.kind.clone()).or_default() += 1;
+            }
+            report.threads.push(verification);
+        }
+
+        console_log!("{} of {} threads failed verification", report.invalid_threads, report.threads_checked);
+        self.to_js(&report)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn verify(&self, thread_id: &str) -> Result<ThreadVerification, JsValue> {
+        let emails = self
+            .threads
+            .get(thread_id)
+            .ok_or_else(|| JsValue::from_str("Thread not found"))?;
+        let tree = self.thread_tree(thread_id)?;
+
+        let mut violations = Vec::new();
+        let mut violation = |email_id: &str, kind: &str, message: String| {
+            violations.push(ThreadViolation {
+                thread_id: thread_id.to_string(),
+                email_id: email_id.to_string(),
+                kind: kind.to_string(),
+                message,
+            });
+        };
+
+        let mut seen_ids = HashSet::new();
+        for email in emails {
+            if !seen_ids.insert(email.id.as_str()) {
+                violation(&email.id, "duplicate_email_id", "Email id appears more than once in the thread".to_string());
+            }
+        }
+
+        let mut visits: HashMap<String, usize> = HashMap::new();
+        let mut stack: Vec<(&ThreadNode, Option<usize>)> = tree.roots.iter().map(|n| (n, None)).collect();
+        while let Some((node, parent_depth)) = stack.pop() {
+            *visits.entry(node.email.id.clone()).or_default() += 1;
+
+            let expected = parent_depth.map_or(0, |d| d + 1);
+            if node.depth != expected {
+                violation(
+                    &node.email.id,
+                    "depth_mismatch",
+                    format!("Depth is {} but its position in the tree implies {}", node.depth, expected),
+                );
+            }
+            stack.extend(node.children.iter().map(|c| (c, Some(node.depth))));
+        }
+
+        for (id, &count) in &visits {
+            if count > 1 {
+                violation(id, "multiple_paths", format!("Reachable from the roots {} times", count));
+            }
+        }
+
+        let parents = self.resolve_parents(emails);
+        for email in emails.iter().filter(|e| !visits.contains_key(&e.id)) {
+            if in_cycle(&parents, &email.id) {
+                violation(&email.id, "cycle", "Parent chain loops back on itself".to_string());
+            } else {
+                violation(&email.id, "unreachable", "Not reachable from any root".to_string());
+            }
+        }
+
+        Ok(ThreadVerification {
+            thread_id: thread_id.to_string(),
+            email_count: emails.len(),
+            reachable_count: visits.len(),
+            is_valid: violations.is_empty(),
+            violations,
+        })
+    }
+}
+
+pub(crate) fn in_cycle(parents: &HashMap<String, String>, email_id: &str) -> bool {
+    let mut seen = HashSet::new();
+    let mut current = email_id;
+    while let Some(parent) = parents.get(current) {
+        if !seen.insert(current) {
+            return true;
+        }
+        current = parent;
+    }
+    false
+}
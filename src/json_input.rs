@@ -0,0 +1,233 @@
+use crate::custodians::merge_custodians;
+use crate::{infer_thread_ids, parse_date_field, EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Loads emails from a JSON array or newline-delimited JSON (one object per
+    /// line). See `JsonEmailRecord` for the accepted fields.
+    #[wasm_bindgen]
+    pub fn load_emails_from_json(&mut self, json_data: &str) -> Result<usize, JsValue> {
+        console_log!("Loading emails from JSON data, length: {}", json_data.len());
+        self.reset_cancellation();
+
+        let trimmed = json_data.trim_start_matches('\u{feff}').trim();
+        if trimmed.is_empty() {
+            return Err(JsValue::from_str("JSON data is empty"));
+        }
+
+        let values: Vec<serde_json::Value> = if trimmed.starts_with('[') {
+            serde_json::from_str(trimmed).map_err(|e| JsValue::from_str(&format!("Invalid JSON array: {}", e)))?
+        } else {
+            let mut values = Vec::new();
+            for (line_no, line) in trimmed.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let value = serde_json::from_str(line)
+                    .map_err(|e| JsValue::from_str(&format!("Invalid JSON on line {}: {}", line_no + 1, e)))?;
+                values.push(value);
+            }
+            values
+        };
+
+        let mut emails = Vec::new();
+        let mut error_count = 0;
+
+        let total = values.len();
+        for (i, value) in values.into_iter().enumerate() {
+            self.check_cancelled()?;
+            let parsed = serde_json::from_value::<JsonEmailRecord>(value)
+                .map_err(|e| e.to_string())
+                .and_then(JsonEmailRecord::into_email);
+
+            match parsed {
+                Ok(email) => emails.push(email),
+                Err(e) => {
+                    error_count += 1;
+                    console_log!("Error parsing JSON email record {}: {}", i + 1, e);
+                    self.emit_warning(&format!("Error parsing JSON email record {}: {}", i + 1, e), "JSON");
+                    if error_count > 5 {
+                        return Err(JsValue::from_str(&format!("Too many JSON parsing errors ({}), stopping", error_count)));
+                    }
+                }
+            }
+            self.emit_progress("JSON", i + 1, Some(total));
+        }
+
+        infer_thread_ids(&mut emails);
+
+        let count = self.finish_load(emails, "JSON");
+        self.audit_load("load_emails_from_json", format!("{} emails loaded", count), &[("JSON", json_data.as_bytes())]);
+        console_log!("Successfully loaded {} emails from JSON ({} errors)", count, error_count);
+
+        if count == 0 {
+            return Err(JsValue::from_str("No valid emails were parsed from JSON"));
+        }
+
+        Ok(count)
+    }
+}
+
+/// Input schema for `load_emails_from_json`. Field names follow `EmailMessage`;
+/// only `id`, `from` and `date_sent` are required. Dates are RFC 3339 strings,
+/// and recipient fields take either an array or a comma/semicolon separated string.
+#[derive(Deserialize)]
+struct JsonEmailRecord {
+    id: String,
+    from: String,
+    date_sent: String,
+    #[serde(default)]
+    message_id: String,
+    #[serde(default)]
+    in_reply_to: Option<String>,
+    #[serde(default)]
+    references: Vec<String>,
+    #[serde(default)]
+    thread_id: String,
+    #[serde(default)]
+    to: AddressList,
+    #[serde(default)]
+    cc: AddressList,
+    #[serde(default)]
+    bcc: AddressList,
+    #[serde(default)]
+    subject: String,
+    #[serde(default, alias = "body")]
+    full_text: String,
+    #[serde(default)]
+    custodian: String,
+    #[serde(default, alias = "duplicate_custodians")]
+    all_custodians: Vec<String>,
+    #[serde(default)]
+    file_name: String,
+    #[serde(default)]
+    confidentiality: String,
+    #[serde(default)]
+    is_forward: bool,
+    #[serde(default)]
+    is_external: bool,
+    #[serde(default)]
+    beg_bates: Option<String>,
+    #[serde(default)]
+    end_bates: Option<String>,
+    #[serde(default)]
+    beg_attach: String,
+    #[serde(default)]
+    end_attach: String,
+    #[serde(default)]
+    parent_id: Option<String>,
+    #[serde(default)]
+    attachment_ids: Vec<String>,
+    #[serde(default)]
+    file_type: String,
+    #[serde(default)]
+    file_extension: String,
+    #[serde(default)]
+    esi_type: String,
+    #[serde(default)]
+    hash: String,
+    #[serde(default)]
+    native_link: String,
+    #[serde(default)]
+    author: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    date_created: Option<String>,
+    #[serde(default)]
+    date_last_modified: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    conversation_index: Option<String>,
+    #[serde(default)]
+    gmail_thread_id: Option<String>,
+    #[serde(default)]
+    folder: String,
+    #[serde(default)]
+    extra: IndexMap<String, String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(untagged)]
+enum AddressList {
+    #[default]
+    Empty,
+    List(Vec<String>),
+    Text(String),
+}
+
+impl AddressList {
+    fn into_vec(self) -> Vec<String> {
+        let raw = match self {
+            AddressList::Empty => Vec::new(),
+            AddressList::List(list) => list,
+            AddressList::Text(text) => text.split([',', ';']).map(|s| s.to_string()).collect(),
+        };
+        raw.into_iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+}
+
+impl JsonEmailRecord {
+    fn into_email(self) -> Result<EmailMessage, String> {
+        let date_sent = parse_date_field(&self.date_sent, "date_sent")?;
+        let date_created = match &self.date_created {
+            Some(value) => parse_date_field(value, "date_created")?,
+            None => date_sent,
+        };
+        let date_last_modified = match &self.date_last_modified {
+            Some(value) => parse_date_field(value, "date_last_modified")?,
+            None => date_created,
+        };
+
+        Ok(EmailMessage {
+            beg_bates: self.beg_bates.unwrap_or_else(|| self.id.clone()),
+            end_bates: self.end_bates.unwrap_or_else(|| self.id.clone()),
+            id: self.id,
+            message_id: self.message_id,
+            in_reply_to: self.in_reply_to.filter(|r| !r.is_empty()),
+            references: self.references,
+            thread_id: self.thread_id,
+            from: self.from,
+            to: self.to.into_vec(),
+            cc: self.cc.into_vec(),
+            bcc: self.bcc.into_vec(),
+            subject: self.subject,
+            date_sent,
+            all_custodians: merge_custodians(&self.custodian, self.all_custodians.iter().map(|c| c.as_str())),
+            custodian: self.custodian,
+            file_name: self.file_name,
+            full_text: self.full_text,
+            confidentiality: self.confidentiality,
+            is_forward: self.is_forward,
+            is_external: self.is_external,
+            file_type: self.file_type,
+            file_extension: self.file_extension,
+            esi_type: self.esi_type,
+            hash: self.hash,
+            native_link: self.native_link,
+            author: self.author,
+            title: self.title,
+            date_created,
+            date_last_modified,
+            beg_attach: self.beg_attach,
+            end_attach: self.end_attach,
+            parent_id: self.parent_id,
+            attachment_ids: self.attachment_ids,
+            tags: self.tags,
+            conversation_index: self
+                .conversation_index
+                .and_then(|ci| crate::conversation_index::normalize(&ci)),
+            gmail_thread_id: self.gmail_thread_id,
+            folder: self.folder,
+            extra: self.extra,
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,71 @@
+use crate::EmailThreadProcessor;
+use wasm_bindgen::prelude::*;
+
+// Thread-level labels are kept apart from per-email `tags`: they describe the
+// conversation (triage state), not any one document, and are keyed by thread id.
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    #[wasm_bindgen]
+    pub fn label_thread(&mut self, thread_id: &str, label: &str) -> Result<(), JsValue> {
+        if !self.threads.contains_key(thread_id) {
+            return Err(JsValue::from_str("Thread not found"));
+        }
+        let label = label.trim();
+        if label.is_empty() {
+            return Err(JsValue::from_str("Label is empty"));
+        }
+
+        let labels = self.thread_labels.entry(thread_id.to_string()).or_default();
+        if !labels.iter().any(|l| l.eq_ignore_ascii_case(label)) {
+            labels.push(label.to_string());
+            self.audit("label_thread", format!("\"{}\" applied to thread {}", label, thread_id));
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn unlabel_thread(&mut self, thread_id: &str, label: &str) -> bool {
+        let Some(labels) = self.thread_labels.get_mut(thread_id) else {
+            return false;
+        };
+        let before = labels.len();
+        labels.retain(|l| !l.eq_ignore_ascii_case(label));
+        let removed = labels.len() < before;
+        if labels.is_empty() {
+            self.thread_labels.shift_remove(thread_id);
+        }
+        if removed {
+            self.audit("unlabel_thread", format!("\"{}\" removed from thread {}", label, thread_id));
+        }
+        removed
+    }
+
+    #[wasm_bindgen]
+    pub fn get_thread_labels(&self, thread_id: &str) -> Vec<String> {
+        self.thread_labels.get(thread_id).cloned().unwrap_or_default()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_threads_by_label(&self, label: &str) -> Vec<String> {
+        self.thread_labels
+            .iter()
+            .filter(|(thread_id, labels)| {
+                self.threads.contains_key(thread_id.as_str()) && labels.iter().any(|l| l.eq_ignore_ascii_case(label))
+            })
+            .map(|(thread_id, _)| thread_id.clone())
+            .collect()
+    }
+
+    /// Every thread label in use, sorted case-insensitively.
+    #[wasm_bindgen]
+    pub fn get_all_thread_labels(&self) -> Vec<String> {
+        let mut labels: Vec<String> = Vec::new();
+        for label in self.thread_labels.values().flatten() {
+            if !labels.iter().any(|l| l.eq_ignore_ascii_case(label)) {
+                labels.push(label.clone());
+            }
+        }
+        labels.sort_by_key(|l| l.to_lowercase());
+        labels
+    }
+}
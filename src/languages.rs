@@ -0,0 +1,237 @@
+use crate::{search, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
+use wasm_bindgen::prelude::*;
+
+// Share of letters in a script that makes a document CJK
+const CJK_SHARE: f64 = 0.2;
+// Tokens looked at to guess a Latin-script language
+const SAMPLE_TOKENS: usize = 500;
+// Shortest part a compound is split into
+const MIN_COMPOUND_PART: usize = 4;
+
+// Function words that mark each Latin-script language
+const MARKERS: &[(Language, &[&str])] = &[
+    (Language::English, &["the", "and", "of", "to", "is", "that", "for", "with", "this", "you", "are", "have"]),
+    (
+        Language::German,
+        &["der", "die", "das", "und", "ist", "nicht", "mit", "sie", "ich", "den", "ein", "eine", "für", "wir"],
+    ),
+    (
+        Language::French,
+        &["le", "les", "et", "est", "des", "une", "pour", "dans", "nous", "vous", "pas", "avec", "sur", "qui"],
+    ),
+    (
+        Language::Spanish,
+        &["el", "los", "las", "y", "es", "por", "para", "con", "una", "del", "se", "lo", "como", "pero"],
+    ),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    // Searched like English
+    #[default]
+    Unknown,
+    English,
+    German,
+    French,
+    Spanish,
+    Chinese,
+    Japanese,
+    Korean,
+}
+
+/// How the search index treats languages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanguageOptions {
+    // Match every plain search term by stem in each document's language, as
+    // if written with a trailing `~`
+    pub stem_all_terms: bool,
+    // Index the parts of German compounds found elsewhere in the corpus
+    pub split_compounds: bool,
+}
+
+impl Default for LanguageOptions {
+    fn default() -> Self {
+        LanguageOptions {
+            stem_all_terms: false,
+            split_compounds: true,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Sets how search handles languages from a `LanguageOptions` object:
+    /// `stem_all_terms` (default false) to match every plain term by its
+    /// stem, and `split_compounds` (default true) so "vertrag" finds
+    /// "Kaufvertrag". Each email's language is detected when the index is
+    /// built; Chinese, Japanese and Korean text is indexed as overlapping
+    /// character pairs, and `word~` stems in the English, German, French or
+    /// Spanish of each document. The index is rebuilt on the next search.
+    #[wasm_bindgen]
+    pub fn set_language_options(&mut self, options: JsValue) -> Result<(), JsValue> {
+        let options: LanguageOptions = if options.is_undefined() || options.is_null() {
+            LanguageOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options)?
+        };
+        self.audit("set_language_options", serde_json::to_string(&options).unwrap_or_default());
+        self.language_options = options;
+        self.search_index = OnceCell::new();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_language_options(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.language_options)
+    }
+
+    /// How many in-scope emails were detected in each language, most first.
+    #[wasm_bindgen]
+    pub fn get_language_stats(&self) -> Result<JsValue, JsValue> {
+        let index = self.search_index();
+        let mut counts: IndexMap<Language, usize> = IndexMap::new();
+        for (doc, email) in self.emails.iter().enumerate() {
+            if self.in_scope(email) {
+                *counts.entry(index.language_of(doc)).or_default() += 1;
+            }
+        }
+        counts.sort_by(|_, a, _, b| b.cmp(a));
+        self.to_js(&counts)
+    }
+}
+
+/// Best guess at the language of `text`: by script for CJK, otherwise by
+/// which language's function words are most frequent.
+pub(crate) fn detect(text: &str) -> Language {
+    let (mut letters, mut han, mut kana, mut hangul) = (0usize, 0usize, 0usize, 0usize);
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        match c {
+            '\u{3040}'..='\u{30ff}' => kana += 1,
+            '\u{ac00}'..='\u{d7af}' | '\u{1100}'..='\u{11ff}' => hangul += 1,
+            c if is_han(c) => han += 1,
+            _ => {}
+        }
+    }
+    let share = |n: usize| letters > 0 && n as f64 / letters as f64 >= CJK_SHARE;
+    if share(kana) || (kana > 0 && share(han + kana)) {
+        return Language::Japanese;
+    }
+    if share(hangul) {
+        return Language::Korean;
+    }
+    if share(han) {
+        return Language::Chinese;
+    }
+
+    let tokens = search::tokenize(text);
+    let mut scores = [0usize; 4];
+    for (term, _) in tokens.iter().take(SAMPLE_TOKENS) {
+        for (i, (_, markers)) in MARKERS.iter().enumerate() {
+            if markers.contains(&term.as_str()) {
+                scores[i] += 1;
+            }
+        }
+    }
+    let best = (0..MARKERS.len()).max_by_key(|&i| (scores[i], std::cmp::Reverse(i))).unwrap_or(0);
+    if scores[best] == 0 {
+        Language::Unknown
+    } else {
+        MARKERS[best].0
+    }
+}
+
+pub(crate) fn is_han(c: char) -> bool {
+    matches!(c, '\u{4e00}'..='\u{9fff}' | '\u{3400}'..='\u{4dbf}' | '\u{f900}'..='\u{faff}')
+}
+
+/// Characters written without spaces between words, indexed as pairs.
+pub(crate) fn is_cjk(c: char) -> bool {
+    is_han(c) || matches!(c, '\u{3040}'..='\u{30ff}' | '\u{ac00}'..='\u{d7af}' | '\u{1100}'..='\u{11ff}')
+}
+
+/// `term` reduced to its stem in `language`; CJK pairs are left alone.
+pub(crate) fn stem(language: Language, term: &str) -> String {
+    match language {
+        Language::English | Language::Unknown => search::stem(term),
+        Language::German => strip_suffixes(&fold(term), GERMAN_SUFFIXES),
+        Language::French => strip_suffixes(&fold(term), FRENCH_SUFFIXES),
+        Language::Spanish => strip_suffixes(&fold(term), SPANISH_SUFFIXES),
+        Language::Chinese | Language::Japanese | Language::Korean => term.to_string(),
+    }
+}
+
+const GERMAN_SUFFIXES: &[&str] = &["ungen", "ern", "em", "en", "er", "es", "e", "s", "n"];
+const FRENCH_SUFFIXES: &[&str] = &[
+    "issements", "issement", "ements", "ement", "ations", "ation", "euses", "euse", "ites", "ite", "ives", "ive", "ees",
+    "ent", "ee", "es", "er", "ez", "e", "s",
+];
+const SPANISH_SUFFIXES: &[&str] = &[
+    "amientos", "amiento", "aciones", "acion", "mente", "idades", "idad", "ables", "able", "istas", "ista", "iendo",
+    "ando", "ados", "adas", "idos", "idas", "ado", "ada", "ido", "ida", "ar", "er", "ir", "es", "os", "as", "a", "o",
+    "e", "s",
+];
+
+// Longest matching suffix off, keeping at least three characters
+fn strip_suffixes(term: &str, suffixes: &[&str]) -> String {
+    for suffix in suffixes {
+        if let Some(base) = term.strip_suffix(suffix) {
+            if base.chars().count() >= 3 {
+                return base.to_string();
+            }
+        }
+    }
+    term.to_string()
+}
+
+// Accents and umlauts dropped so inflected and plain spellings meet
+fn fold(term: &str) -> String {
+    let mut folded = String::with_capacity(term.len());
+    for c in term.chars() {
+        match c {
+            'ä' | 'à' | 'á' | 'â' => folded.push('a'),
+            'ö' | 'ó' | 'ò' | 'ô' => folded.push('o'),
+            'ü' | 'ú' | 'ù' | 'û' => folded.push('u'),
+            'é' | 'è' | 'ê' | 'ë' => folded.push('e'),
+            'í' | 'ì' | 'î' | 'ï' => folded.push('i'),
+            'ñ' => folded.push('n'),
+            'ç' => folded.push('c'),
+            'ß' => folded.push_str("ss"),
+            c => folded.push(c),
+        }
+    }
+    folded
+}
+
+/// The two parts of a German compound that both occur as words on their
+/// own, e.g. "kaufvertrag" -> ["kauf", "vertrag"], allowing a linking "s"
+/// or "n" ("arbeitsvertrag" -> "arbeit", "vertrag"). Longest head first.
+pub(crate) fn split_compound(term: &str, is_word: impl Fn(&str) -> bool) -> Option<[String; 2]> {
+    let chars: Vec<char> = term.chars().collect();
+    if chars.len() < 2 * MIN_COMPOUND_PART {
+        return None;
+    }
+    for split in (MIN_COMPOUND_PART..=chars.len() - MIN_COMPOUND_PART).rev() {
+        let head: String = chars[..split].iter().collect();
+        let tail: String = chars[split..].iter().collect();
+        if !is_word(&tail) {
+            continue;
+        }
+        if is_word(&head) {
+            return Some([head, tail]);
+        }
+        for link in ["s", "es", "n", "en"] {
+            if let Some(base) = head.strip_suffix(link) {
+                if base.chars().count() >= MIN_COMPOUND_PART && is_word(base) {
+                    return Some([base.to_string(), tail]);
+                }
+            }
+        }
+    }
+    None
+}
@@ -1,9 +1,14 @@
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::borrow::Borrow;
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
 use chrono::{DateTime, Utc};
 use indexmap::IndexMap;
 
+use conversation_index::ConversationIndex;
+use dialect::LoadFileDialect;
+
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global allocator.
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
@@ -18,10 +23,103 @@ extern "C" {
 }
 
 macro_rules! console_log {
-    ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
+    ($($t:tt)*) => ($crate::log(&format_args!($($t)*).to_string()))
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+mod address_book;
+mod attachments;
+mod audit;
+mod batches;
+mod cancel;
+mod chronology;
+mod classifications;
+mod coding;
+mod comparison;
+mod completeness;
+mod confidentiality;
+mod config;
+mod conversation_index;
+mod corpus;
+mod corrections;
+mod custodians;
+mod dat;
+mod determinism;
+mod dialect;
+mod display;
+mod distributions;
+mod domains;
+mod edrm;
+mod eml;
+mod events;
+mod exchange;
+mod exclusion;
+mod explain;
+mod filetypes;
+mod filter;
+mod hashes;
+mod highlight;
+mod hit_context;
+mod hot_documents;
+mod identity_map;
+mod inclusive;
+mod initiators;
+mod integrity;
+mod json_input;
+mod labels;
+mod languages;
+mod maildir;
+mod mass_mail;
+mod methodology;
+mod metrics;
+mod mbox;
+mod msg;
+mod narrative;
+mod network;
+mod opticon;
+mod overlay;
+mod paging;
+mod participant_search;
+mod participation;
+#[cfg(feature = "pdf-export")]
+mod pdf;
+mod periods;
+mod person_timeline;
+mod phases;
+mod productions;
+mod qc_report;
+mod reattach;
+mod redactions;
+mod reference_graph;
+mod reply_matrix;
+mod response_times;
+#[cfg(feature = "xlsx-export")]
+mod report_xlsx;
+mod rfc5322;
+mod rollups;
+mod schema;
+mod search;
+mod sequestration;
+mod shape;
+mod snapshot;
+mod sorting;
+mod stable_ids;
+mod stop_words;
+mod subtree;
+mod suspicious_dates;
+#[cfg(feature = "test-corpus")]
+mod synthetic;
+mod term_report;
+mod thread_families;
+mod thread_locks;
+mod topic_clusters;
+mod topics;
+mod tree_options;
+mod unload;
+mod validation;
+mod warnings;
+mod xlsx;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EmailMessage {
     pub id: String,
     pub message_id: String,
@@ -33,22 +131,60 @@ pub struct EmailMessage {
     pub cc: Vec<String>,
     pub bcc: Vec<String>,
     pub subject: String,
+    #[serde(serialize_with = "crate::display::serialize")]
     pub date_sent: DateTime<Utc>,
     pub custodian: String,
     pub file_name: String,
     pub full_text: String,
+    // Canonical designation per set_confidentiality_map
     pub confidentiality: String,
+    // As the load file gave it
+    #[serde(default)]
+    pub confidentiality_raw: String,
     pub is_forward: bool,
     pub is_external: bool,
     pub beg_bates: String,
     pub end_bates: String,
     pub file_type: String,
+    // Default so snapshots written before these columns were kept still load
+    #[serde(default)]
+    pub file_extension: String,
+    #[serde(default)]
+    pub esi_type: String,
     pub hash: String,
     pub native_link: String,
     pub author: String,
     pub title: String,
+    #[serde(serialize_with = "crate::display::serialize")]
     pub date_created: DateTime<Utc>,
+    #[serde(serialize_with = "crate::display::serialize")]
     pub date_last_modified: DateTime<Utc>,
+    pub beg_attach: String,
+    pub end_attach: String,
+    pub parent_id: Option<String>,
+    pub attachment_ids: Vec<String>,
+    pub tags: Vec<String>,
+    pub page_count: usize,
+    pub image_paths: Vec<String>,
+    pub conversation_index: Option<String>,
+    pub gmail_thread_id: Option<String>,
+    pub folder: String,
+    // Primary custodian first, then any DuplicateCustodian entries
+    pub all_custodians: Vec<String>,
+    // Load file columns outside the standard mapping, in file order
+    pub extra: IndexMap<String, String>,
+    // Production volume it arrived in, see set_production
+    #[serde(default)]
+    pub production: String,
+    // External model results by model name, from apply_classifications
+    #[serde(default)]
+    pub classifications: IndexMap<String, classifications::Classification>,
+    // Redactions from import_redactions, and the text exports carry instead
+    // of full_text
+    #[serde(default)]
+    pub redactions: Vec<redactions::Redaction>,
+    #[serde(default)]
+    pub redacted_text: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +192,38 @@ pub struct ThreadNode {
     pub email: EmailMessage,
     pub children: Vec<ThreadNode>,
     pub depth: usize,
+    // Matches for the configured highlight term sets
+    #[serde(default)]
+    pub highlights: highlight::Highlights,
+    // Subject differs from the thread root's beyond Re:/Fwd: prefixes
+    #[serde(default)]
+    pub subject_changed: bool,
+    // Confidentiality and redactions of this node and its descendants
+    #[serde(default)]
+    pub rollup: rollups::BranchRollup,
+    #[serde(default)]
+    pub metrics: metrics::ReadingMetrics,
+    // Hung under its parent by reattach_orphans rather than by headers
+    #[serde(default)]
+    pub heuristic_parent: bool,
+    // A placeholder for an email withdrawn by sequester_emails
+    #[serde(default)]
+    pub sequestered: bool,
+    // DateSent looks wrong, see get_suspicious_dates
+    #[serde(default)]
+    pub suspicious_date: bool,
+    // Distinct To, Cc and Bcc addresses, and whether that makes it mass mail
+    #[serde(default)]
+    pub recipient_count: usize,
+    #[serde(default)]
+    pub mass_mail: bool,
+    // The placeholder TreeOptions::virtual_root puts above a thread's roots
+    #[serde(default)]
+    pub is_virtual: bool,
+    // Replies under this node left out by max_depth or max_nodes, counting
+    // their own replies
+    #[serde(default)]
+    pub more_replies: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,14 +233,31 @@ pub struct ThreadTree {
     pub total_emails: usize,
     pub participants: Vec<String>,
     pub date_range: DateRange,
+    // Some emails were left out by max_depth or max_nodes
+    #[serde(default)]
+    pub truncated: bool,
+    // Emails in root branches max_nodes left out entirely
+    #[serde(default)]
+    pub more_roots: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DateRange {
+    #[serde(serialize_with = "crate::display::serialize")]
     pub start: DateTime<Utc>,
+    #[serde(serialize_with = "crate::display::serialize")]
     pub end: DateTime<Utc>,
 }
 
+impl DateRange {
+    /// Earliest to latest date sent, whatever order the emails are in.
+    pub(crate) fn of(emails: &[EmailMessage]) -> Option<DateRange> {
+        let start = emails.iter().map(|e| e.date_sent).min()?;
+        let end = emails.iter().map(|e| e.date_sent).max()?;
+        Some(DateRange { start, end })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThreadStats {
     pub thread_id: String,
@@ -85,12 +270,144 @@ pub struct ThreadStats {
     pub reply_count: usize,
     pub external_count: usize,
     pub date_range: DateRange,
+    // When each participant joined and left the conversation, by joining order
+    pub participant_timeline: Vec<participation::ParticipantSpan>,
+    pub completeness: completeness::Completeness,
+    // Totals over the thread's emails
+    pub metrics: metrics::ReadingMetrics,
+    // External model results over the thread's emails, by model
+    pub classifications: IndexMap<String, classifications::ClassificationSummary>,
+    // Emails produced with redactions, from import_redactions or load file
+    // columns and tags
+    pub redacted_count: usize,
+    pub has_redactions: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoadReport {
+    pub source: String,
+    pub rows_read: usize,
+    pub emails_loaded: usize,
+    pub errors: Vec<String>,
+    pub dialect: Option<LoadFileDialect>,
+    // Records dropped by the exclusion rules, in total and per rule
+    // ("hash", "file_type", "sender")
+    #[serde(default)]
+    pub excluded_count: usize,
+    #[serde(default)]
+    pub excluded_by: IndexMap<String, usize>,
+    // Input problems worked around rather than rejected, in total and per
+    // kind ("bom", "multiline_field", "short_row", "long_row"), with the first
+    // MAX_REPAIR_NOTES described
+    #[serde(default)]
+    pub repaired_count: usize,
+    #[serde(default)]
+    pub repaired_by: IndexMap<String, usize>,
+    #[serde(default)]
+    pub repairs: Vec<String>,
+}
+
+const MAX_REPAIR_NOTES: usize = 100;
+
+impl LoadReport {
+    pub(crate) fn note_repair(&mut self, kind: &str, note: String) {
+        self.repaired_count += 1;
+        *self.repaired_by.entry(kind.to_string()).or_default() += 1;
+        if self.repairs.len() < MAX_REPAIR_NOTES {
+            self.repairs.push(note);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadingMode {
+    // Thread ids supplied by the load file (column_history THREAD:, EDRM tags)
+    #[default]
+    ThreadId,
+    // Outlook Conversation Index GUID and reply blocks, falling back to ThreadId
+    ConversationIndex,
+    // Gmail X-GM-THRID from Takeout exports, falling back to ThreadId
+    GmailThreadId,
 }
 
 #[wasm_bindgen]
 pub struct EmailThreadProcessor {
     emails: Vec<EmailMessage>,
     threads: IndexMap<String, Vec<EmailMessage>>,
+    threading_mode: ThreadingMode,
+    // See set_threading_config
+    subject_fallback: bool,
+    dedup_policy: config::DedupPolicy,
+    deterministic: bool,
+    stable_thread_ids: bool,
+    // Grouping key -> content-derived thread id, see stable_thread_ids
+    thread_aliases: HashMap<String, String>,
+    load_report: LoadReport,
+    // Canonical column name -> header used by this load file
+    column_mapping: IndexMap<String, String>,
+    date_formats: Vec<String>,
+    include_singletons: bool,
+    search_index: OnceCell<search::SearchIndex>,
+    // Email id -> index into `emails`, built on first lookup
+    id_index: OnceCell<HashMap<String, usize>>,
+    // Each email's duplicate set, see coding::duplicate_keys
+    duplicate_keys: OnceCell<Vec<Option<String>>>,
+    // Name -> query, in the order saved
+    saved_searches: IndexMap<String, String>,
+    // Set name -> parsed terms, see set_highlight_terms
+    highlight_terms: IndexMap<String, Vec<search::Query>>,
+    hooks: events::EventHooks,
+    cancellation: cancel::CancellationToken,
+    // Thread id -> triage labels, see label_thread
+    thread_labels: IndexMap<String, Vec<String>>,
+    // Mutually exclusive tag pairs, see set_conflicting_tags
+    conflicting_tags: Vec<(String, String)>,
+    // Document types kept out of threading, search and stats
+    type_filter: filetypes::TypeFilter,
+    // Applied by every loader, see set_exclusion_rules
+    exclusion_rules: exclusion::ExclusionRules,
+    // Lowercased, see set_internal_domains
+    internal_domains: Vec<String>,
+    // Working subset every API is scoped to, see set_global_filter
+    global_filter: filter::GlobalFilter,
+    // Append-only, see get_audit_log
+    audit_log: Vec<audit::AuditEntry>,
+    // Reduced raw value -> canonical designation, see set_confidentiality_map
+    confidentiality_map: IndexMap<String, String>,
+    // Person -> addresses, see import_identity_map
+    identity_map: identity_map::IdentityMap,
+    // See set_scoring_config
+    scoring_config: hot_documents::ScoringConfig,
+    // See build_topics
+    topic_model: Option<topic_clusters::TopicModel>,
+    // Email id -> heuristic parent edge, see reattach_orphans
+    reattachments: IndexMap<String, reattach::Reattachment>,
+    // Label and load mode for the next loads, see set_production
+    production: Option<String>,
+    append_loads: bool,
+    // Email id -> withdrawal, see sequester_emails
+    sequestered: IndexMap<String, sequestration::Sequestration>,
+    // See set_display_timezone
+    display_timezone: display::DisplayTimezone,
+    // See set_business_calendar
+    business_calendar: response_times::BusinessCalendar,
+    // See set_stop_words
+    stop_words: stop_words::StopWordConfig,
+    // See set_language_options
+    language_options: languages::LanguageOptions,
+    // See set_mass_mail_options
+    mass_mail: mass_mail::MassMailOptions,
+    // Thread id -> frozen structure, see lock_thread
+    locked_threads: IndexMap<String, thread_locks::ThreadLock>,
+    // Email id -> locked thread id, rebuilt whenever locked_threads changes
+    locked_emails: HashMap<String, String>,
+}
+
+impl Default for EmailThreadProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[wasm_bindgen]
@@ -101,85 +418,80 @@ impl EmailThreadProcessor {
         EmailThreadProcessor {
             emails: Vec::new(),
             threads: IndexMap::new(),
+            threading_mode: ThreadingMode::default(),
+            subject_fallback: false,
+            dedup_policy: config::DedupPolicy::default(),
+            deterministic: false,
+            stable_thread_ids: false,
+            thread_aliases: HashMap::new(),
+            load_report: LoadReport::default(),
+            column_mapping: IndexMap::new(),
+            date_formats: Vec::new(),
+            include_singletons: true,
+            search_index: OnceCell::new(),
+            id_index: OnceCell::new(),
+            duplicate_keys: OnceCell::new(),
+            saved_searches: IndexMap::new(),
+            highlight_terms: IndexMap::new(),
+            hooks: events::EventHooks::default(),
+            cancellation: cancel::CancellationToken::default(),
+            thread_labels: IndexMap::new(),
+            conflicting_tags: coding::default_conflicting_tags(),
+            type_filter: filetypes::TypeFilter::default(),
+            exclusion_rules: exclusion::ExclusionRules::default(),
+            internal_domains: Vec::new(),
+            global_filter: filter::GlobalFilter::default(),
+            audit_log: Vec::new(),
+            confidentiality_map: confidentiality::default_confidentiality_map(),
+            identity_map: identity_map::IdentityMap::default(),
+            scoring_config: hot_documents::ScoringConfig::default(),
+            topic_model: None,
+            reattachments: IndexMap::new(),
+            production: None,
+            append_loads: false,
+            sequestered: IndexMap::new(),
+            display_timezone: display::DisplayTimezone::default(),
+            business_calendar: response_times::BusinessCalendar::default(),
+            stop_words: stop_words::StopWordConfig::default(),
+            language_options: languages::LanguageOptions::default(),
+            mass_mail: mass_mail::MassMailOptions::default(),
+            locked_threads: IndexMap::new(),
+            locked_emails: HashMap::new(),
         }
     }
 
     #[wasm_bindgen]
     pub fn load_emails_from_csv(&mut self, csv_data: &str) -> Result<usize, JsValue> {
         console_log!("Loading emails from CSV data, length: {}", csv_data.len());
+        self.reset_cancellation();
 
         if csv_data.is_empty() {
             return Err(JsValue::from_str("CSV data is empty"));
         }
 
-        let mut emails = Vec::new();
-        let mut rdr = csv::Reader::from_reader(csv_data.as_bytes());
-        let mut row_count = 0;
-        let mut error_count = 0;
-
-        // Log headers for debugging
-        if let Ok(headers) = rdr.headers() {
-            console_log!("CSV headers: {:?}", headers);
-        }
-
-        for result in rdr.deserialize() {
-            row_count += 1;
-            match result {
-                Ok(record) => {
-                    match self.parse_csv_record(record) {
-                        Ok(email) => {
-                            console_log!("Successfully parsed email {}: {}", emails.len() + 1, email.subject);
-                            emails.push(email);
-                        },
-                        Err(e) => {
-                            error_count += 1;
-                            console_log!("Error parsing email record {}: {}", row_count, e);
-                            if error_count > 5 {
-                                return Err(JsValue::from_str(&format!("Too many parsing errors ({}), stopping", error_count)));
-                            }
-                            continue;
-                        }
-                    }
-                }
-                Err(e) => {
-                    error_count += 1;
-                    console_log!("Error reading CSV record {}: {}", row_count, e);
-                    if error_count > 5 {
-                        return Err(JsValue::from_str(&format!("Too many CSV reading errors ({}), stopping", error_count)));
-                    }
-                    continue;
-                }
-            }
-        }
-
-        let count = emails.len();
-        self.emails = emails;
-        console_log!("Successfully loaded {} emails out of {} rows ({} errors)", count, row_count, error_count);
-
-        if count == 0 {
-            return Err(JsValue::from_str("No valid emails were parsed from CSV"));
-        }
-
+        let (delimiter, quote) = dialect::sniff_text(csv_data);
+        let dialect = LoadFileDialect {
+            delimiter,
+            quote,
+            has_bom: csv_data.starts_with('\u{feff}'),
+            ..Default::default()
+        };
+        let count = self.load_delimited(csv_data, dialect, "CSV")?;
+        self.audit_load("load_emails_from_csv", format!("{} emails loaded", count), &[("CSV", csv_data.as_bytes())]);
         Ok(count)
     }
 
+    #[wasm_bindgen]
+    pub fn get_load_report(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.load_report)
+    }
+
     fn parse_csv_record(&self, record: CsvRecord) -> Result<EmailMessage, String> {
         let thread_info = self.parse_column_history(&record.column_history);
 
-        let date_sent = chrono::DateTime::parse_from_rfc3339(&record.date_sent)
-            .or_else(|_| chrono::DateTime::parse_from_str(&record.date_sent, "%Y-%m-%dT%H:%M:%SZ"))
-            .map_err(|e| format!("Invalid date format for DateSent: {}", e))?
-            .with_timezone(&Utc);
-
-        let date_created = chrono::DateTime::parse_from_rfc3339(&record.date_created)
-            .or_else(|_| chrono::DateTime::parse_from_str(&record.date_created, "%Y-%m-%dT%H:%M:%SZ"))
-            .map_err(|e| format!("Invalid date format for DateCreated: {}", e))?
-            .with_timezone(&Utc);
-
-        let date_last_modified = chrono::DateTime::parse_from_rfc3339(&record.date_last_modified)
-            .or_else(|_| chrono::DateTime::parse_from_str(&record.date_last_modified, "%Y-%m-%dT%H:%M:%SZ"))
-            .map_err(|e| format!("Invalid date format for DateLastModified: {}", e))?
-            .with_timezone(&Utc);
+        let date_sent = self.parse_date(&record.date_sent, "DateSent")?;
+        let date_created = self.parse_date(&record.date_created, "DateCreated")?;
+        let date_last_modified = self.parse_date(&record.date_last_modified, "DateLastModified")?;
 
         Ok(EmailMessage {
             id: record.beg_bates.clone(),
@@ -193,6 +505,7 @@ impl EmailThreadProcessor {
             bcc: record.bcc.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect(),
             subject: record.subject,
             date_sent,
+            all_custodians: custodians::merge_custodians(&record.custodian, [record.duplicate_custodian.as_str()]),
             custodian: record.custodian,
             file_name: record.file_name,
             full_text: record.full_text,
@@ -202,12 +515,20 @@ impl EmailThreadProcessor {
             beg_bates: record.beg_bates,
             end_bates: record.end_bates,
             file_type: record.file_type,
+            file_extension: record.file_extension,
+            esi_type: record.esi_type,
             hash: record.hash,
             native_link: record.native_link,
             author: record.author,
             title: record.title,
             date_created,
             date_last_modified,
+            beg_attach: record.beg_attach,
+            end_attach: record.end_attach,
+            conversation_index: thread_info
+                .conversation_index
+                .or_else(|| conversation_index::normalize(&record.conversation_index)),
+            ..Default::default()
         })
     }
 
@@ -230,6 +551,8 @@ impl EmailThreadProcessor {
                 }
             } else if part.starts_with("THREAD:") {
                 info.thread_id = Some(part.replace("THREAD:", ""));
+            } else if part.starts_with("CONV-INDEX:") {
+                info.conversation_index = conversation_index::normalize(&part.replace("CONV-INDEX:", ""));
             } else if part == "FWD:true" {
                 info.is_forward = true;
             } else if part == "EXTERNAL:true" {
@@ -243,30 +566,101 @@ impl EmailThreadProcessor {
     #[wasm_bindgen]
     pub fn group_by_threads(&mut self) -> usize {
         console_log!("Grouping emails by threads");
+        self.reset_cancellation();
         self.threads.clear();
-
-        for email in &self.emails {
-            if !email.thread_id.is_empty() {
+        self.thread_aliases.clear();
+
+        for (i, email) in self.emails.iter().enumerate() {
+            // Sequestered emails the filters would keep stay as placeholders
+            let kept = self.in_scope(email)
+                || self.is_sequestered(&email.id) && !self.type_excluded(email) && !self.outside_global_filter(email);
+            if let Some(key) = self.thread_key(email).filter(|_| kept) {
+                let copy = self.thread_copy(email);
                 self.threads
-                    .entry(email.thread_id.clone())
-                    .or_insert_with(Vec::new)
-                    .push(email.clone());
+                    .entry(key)
+                    .or_default()
+                    .push(copy);
             }
+            self.emit_progress("threading", i + 1, Some(self.emails.len()));
+        }
+
+        // Sort emails within each thread by date; the conversation index carries
+        // the Exchange-side timestamp, which survives bad client clocks
+        let by_index = self.threading_mode == ThreadingMode::ConversationIndex;
+        let dedup_policy = self.dedup_policy;
+        let deterministic = self.deterministic;
+        let sent = |e: &EmailMessage| {
+            e.conversation_index
+                .as_deref()
+                .filter(|_| by_index)
+                .and_then(ConversationIndex::parse)
+                .and_then(|ci| ci.timestamp())
+                .unwrap_or(e.date_sent)
+        };
+        for (thread_id, emails) in self.threads.iter_mut() {
+            // Stable sort, so the first loaded copy is the one kept; in
+            // deterministic mode ties go to the lowest id instead
+            emails.sort_by(|a, b| {
+                sent(a)
+                    .cmp(&sent(b))
+                    .then_with(|| if deterministic { a.id.cmp(&b.id) } else { std::cmp::Ordering::Equal })
+            });
+            // A locked thread keeps every copy it was locked with
+            if self.locked_threads.contains_key(thread_id) {
+                continue;
+            }
+            let mut seen = HashSet::new();
+            emails.retain(|e| dedup_policy.key(e).is_none_or(|key| seen.insert(key.to_string())));
+        }
+
+        if self.stable_thread_ids {
+            self.thread_aliases = self.stable_thread_ids();
+            let threads = std::mem::take(&mut self.threads);
+            self.threads =
+                threads.into_iter().map(|(key, emails)| (self.thread_aliases[&key].clone(), emails)).collect();
+        }
+        if deterministic {
+            self.threads.sort_unstable_keys();
         }
 
-        // Sort emails within each thread by date
-        for emails in self.threads.values_mut() {
-            emails.sort_by(|a, b| a.date_sent.cmp(&b.date_sent));
+        for (thread_id, emails) in &self.threads {
+            self.emit_thread_built(thread_id, emails.len());
+        }
+
+        // Regrouping always runs to completion, as stopping part way would
+        // leave threads half built; a cancel raised meanwhile is dropped
+        if self.cancellation.is_cancelled() {
+            console_log!("Grouping cannot be cancelled, cancellation ignored");
+            self.reset_cancellation();
         }
 
         console_log!("Found {} threads", self.threads.len());
+        let mode = self.get_threading_mode();
+        self.audit("group_by_threads", format!("{} threads built in {} mode", self.threads.len(), mode));
         self.threads.len()
     }
 
+    /// A thread's reply tree, roots ordered with the earliest plausibly dated
+    /// first. `options` is a `TreeOptions` object: `virtual_root` ties the
+    /// roots of a multi-root thread together under one placeholder node, and
+    /// `child_order` ("chronological" by default, "bates" or "subtree_size")
+    /// orders siblings at every level, roots included, and `max_depth` and
+    /// `max_nodes` cap the size of what is returned for very large threads,
+    /// leaving a `more_replies` count wherever replies were cut.
     #[wasm_bindgen]
-    pub fn build_thread_tree(&self, thread_id: &str) -> Result<JsValue, JsValue> {
+    pub fn build_thread_tree(&self, thread_id: &str, options: JsValue) -> Result<JsValue, JsValue> {
         console_log!("Building thread tree for: {}", thread_id);
 
+        let options = tree_options::TreeOptions::from_js(options)?;
+        let thread_tree = self.thread_tree_with(thread_id, &options)?;
+        self.to_js(&thread_tree)
+    }
+
+    fn thread_tree(&self, thread_id: &str) -> Result<ThreadTree, JsValue> {
+        self.thread_tree_with(thread_id, &tree_options::TreeOptions::default())
+    }
+
+    fn thread_tree_with(&self, thread_id: &str, options: &tree_options::TreeOptions) -> Result<ThreadTree, JsValue> {
         let emails = match self.threads.get(thread_id) {
             Some(emails) => emails,
             None => return Err(JsValue::from_str("Thread not found")),
@@ -274,33 +668,51 @@ impl EmailThreadProcessor {
 
         let mut email_map: HashMap<String, EmailMessage> = HashMap::new();
         let mut children_map: HashMap<String, Vec<String>> = HashMap::new();
+        let parents = self.resolve_parents(emails);
 
         // Build email map and children relationships
         for email in emails {
-            email_map.insert(email.message_id.clone(), email.clone());
+            email_map.insert(email.id.clone(), email.clone());
 
-            if let Some(parent_id) = &email.in_reply_to {
+            if let Some(parent_id) = parents.get(&email.id) {
                 children_map
                     .entry(parent_id.clone())
-                    .or_insert_with(Vec::new)
-                    .push(email.message_id.clone());
+                    .or_default()
+                    .push(email.id.clone());
             }
         }
 
-        // Find root emails (those without parents in this thread)
+        // Find root emails (those without parents in this thread), the
+        // earliest with a plausible date first
         let mut roots = Vec::new();
         for email in emails {
-            if email.in_reply_to.is_none() ||
-               !email_map.contains_key(email.in_reply_to.as_ref().unwrap()) {
-                roots.push(self.build_node(&email_map, &children_map, &email.message_id, 0));
+            if !parents.contains_key(&email.id) {
+                roots.push(self.build_node(&email_map, &children_map, &email.id, 0));
             }
         }
+        roots.sort_by_key(|root| (suspicious_dates::is_vendor_default(root.email.date_sent), root.email.date_sent));
+        for root in &mut roots {
+            topics::mark_subject_changes(root);
+            rollups::roll_up(root);
+            self.mark_heuristic_edges(root);
+            suspicious_dates::mark_suspicious_dates(root, None);
+            tree_options::order_children(root, options.child_order);
+        }
+        // Stable, so chronological order keeps plausibly dated roots first
+        if options.child_order != tree_options::ChildOrder::Chronological {
+            tree_options::order_nodes(&mut roots, options.child_order);
+        }
+        let more_roots = tree_options::limit(&mut roots, options);
+        let truncated = more_roots > 0 || tree_options::any_cut(&roots);
+        if options.virtual_root && roots.len() + usize::from(more_roots > 0) > 1 {
+            roots = vec![tree_options::virtual_root(thread_id, roots, more_roots)];
+        }
 
         let participants = self.get_unique_participants(emails);
-        let date_range = DateRange {
-            start: emails.first().map(|e| e.date_sent).unwrap_or_else(Utc::now),
-            end: emails.last().map(|e| e.date_sent).unwrap_or_else(Utc::now),
-        };
+        let date_range = DateRange::of(emails).unwrap_or_else(|| DateRange {
+            start: Utc::now(),
+            end: Utc::now(),
+        });
 
         let thread_tree = ThreadTree {
             thread_id: thread_id.to_string(),
@@ -308,31 +720,168 @@ impl EmailThreadProcessor {
             total_emails: emails.len(),
             participants,
             date_range,
+            truncated,
+            more_roots,
         };
 
-        serde_wasm_bindgen::to_value(&thread_tree).map_err(|e| JsValue::from_str(&e.to_string()))
+        Ok(thread_tree)
+    }
+
+    fn thread_key(&self, email: &EmailMessage) -> Option<String> {
+        self.thread_key_source(email).map(|(key, _)| key)
+    }
+
+    // The thread key with what it came from: "locked", "reattached",
+    // "conversation_index", "gmail_thread_id", "thread_id" or "subject_fallback"
+    fn thread_key_source(&self, email: &EmailMessage) -> Option<(String, &'static str)> {
+        if let Some(lock) = self.thread_lock_of(&email.id) {
+            return Some((lock.thread_id.clone(), "locked"));
+        }
+        let (key, source) = match self.reattached_parent(&email.id).and_then(|id| self.email_by_id(id)) {
+            // Reattached orphans follow their parent wherever it is grouped
+            Some(parent) => (self.thread_key(parent)?, "reattached"),
+            None => self.grouping_key(email)?,
+        };
+        let key = if self.locked_threads.contains_key(&key) {
+            format!("{}{}", key, thread_locks::NEWCOMER_SUFFIX)
+        } else {
+            key
+        };
+        Some((self.thread_aliases.get(&key).cloned().unwrap_or(key), source))
+    }
+
+    // The key emails are grouped by, before any stable thread id replaces it
+    fn grouping_key(&self, email: &EmailMessage) -> Option<(String, &'static str)> {
+        match self.threading_mode {
+            ThreadingMode::ConversationIndex => {
+                if let Some(ci) = email.conversation_index.as_deref().and_then(ConversationIndex::parse) {
+                    return Some((format!("CI-{}", ci.guid()), "conversation_index"));
+                }
+            }
+            ThreadingMode::GmailThreadId => {
+                if let Some(thrid) = email.gmail_thread_id.as_deref().filter(|t| !t.is_empty()) {
+                    return Some((format!("GM-{}", thrid), "gmail_thread_id"));
+                }
+            }
+            ThreadingMode::ThreadId => {}
+        }
+
+        if !email.thread_id.is_empty() {
+            return Some((email.thread_id.clone(), "thread_id"));
+        }
+        let subject = topics::normalize_subject(&email.subject);
+        if self.subject_fallback && !subject.is_empty() {
+            Some((format!("SUBJ-{}", subject), "subject_fallback"))
+        } else {
+            None
+        }
+    }
+
+    // Maps each email id to its parent's id within the thread. In conversation
+    // index mode the index wins, walking up to the nearest ancestor we hold;
+    // otherwise (or when it yields nothing) In-Reply-To is used, then the
+    // nearest References entry in the thread not dated after the email. Emails
+    // left without a parent take the one reattach_orphans gave them, if
+    // present. A locked thread keeps the links it was locked with.
+    fn resolve_parents<E: Borrow<EmailMessage>>(&self, emails: &[E]) -> HashMap<String, String> {
+        let emails: Vec<&EmailMessage> = emails.iter().map(Borrow::borrow).collect();
+        if let Some(parents) = self.locked_parents(&emails) {
+            return parents;
+        }
+        let by_message_id: HashMap<&str, &str> = emails
+            .iter()
+            .filter(|e| !e.message_id.is_empty())
+            .map(|e| (e.message_id.as_str(), e.id.as_str()))
+            .collect();
+        let dated: HashMap<&str, DateTime<Utc>> = emails.iter().map(|e| (e.id.as_str(), e.date_sent)).collect();
+
+        let use_index = self.threading_mode == ThreadingMode::ConversationIndex;
+        let by_index: HashMap<String, &str> = emails
+            .iter()
+            .filter(|_| use_index)
+            .filter_map(|e| {
+                let ci = ConversationIndex::parse(e.conversation_index.as_deref()?)?;
+                Some((ci.to_hex(), e.id.as_str()))
+            })
+            .collect();
+
+        let mut parents = HashMap::new();
+        for &email in &emails {
+            let from_index = email
+                .conversation_index
+                .as_deref()
+                .and_then(ConversationIndex::parse)
+                .and_then(|ci| {
+                    let mut ancestor = ci.parent();
+                    while let Some(candidate) = ancestor {
+                        if let Some(id) = by_index.get(&candidate.to_hex()) {
+                            return Some(*id);
+                        }
+                        ancestor = candidate.parent();
+                    }
+                    None
+                });
+            let from_header = email
+                .in_reply_to
+                .as_deref()
+                .and_then(|p| by_message_id.get(p).copied());
+            let from_references = || {
+                email.references.iter().rev().find_map(|r| {
+                    let parent = by_message_id.get(r.as_str()).copied()?;
+                    (parent != email.id && *dated.get(parent)? <= email.date_sent).then_some(parent)
+                })
+            };
+
+            if let Some(parent) = from_index.or(from_header).or_else(from_references) {
+                if parent != email.id {
+                    parents.insert(email.id.clone(), parent.to_string());
+                }
+            }
+        }
+
+        if !self.reattachments.is_empty() {
+            let ids: HashSet<&str> = emails.iter().map(|e| e.id.as_str()).collect();
+            for email in &emails {
+                if let Some(parent) = self.reattached_parent(&email.id).filter(|p| ids.contains(p)) {
+                    parents.entry(email.id.clone()).or_insert_with(|| parent.to_string());
+                }
+            }
+        }
+
+        parents
     }
 
     fn build_node(
         &self,
         email_map: &HashMap<String, EmailMessage>,
         children_map: &HashMap<String, Vec<String>>,
-        message_id: &str,
+        email_id: &str,
         depth: usize,
     ) -> ThreadNode {
-        let email = email_map.get(message_id).unwrap().clone();
+        let email = email_map.get(email_id).unwrap().clone();
         let mut children = Vec::new();
 
-        if let Some(child_ids) = children_map.get(message_id) {
+        if let Some(child_ids) = children_map.get(email_id) {
             for child_id in child_ids {
                 children.push(self.build_node(email_map, children_map, child_id, depth + 1));
             }
         }
 
         ThreadNode {
+            highlights: self.highlights_for(&email.id),
+            metrics: self.reading_metrics(&email),
+            recipient_count: mass_mail::recipient_count(&email),
+            mass_mail: self.is_mass_mail(&email),
             email,
             children,
             depth,
+            subject_changed: false,
+            rollup: rollups::BranchRollup::default(),
+            heuristic_parent: false,
+            sequestered: self.is_sequestered(email_id),
+            suspicious_date: false,
+            is_virtual: false,
+            more_replies: 0,
         }
     }
 
@@ -349,30 +898,55 @@ impl EmailThreadProcessor {
             }
         }
 
-        participants.into_iter().collect()
+        let mut participants: Vec<String> = participants.into_iter().collect();
+        participants.sort();
+        participants
     }
 
     #[wasm_bindgen]
     pub fn generate_thread_stats(&self, thread_id: &str) -> Result<JsValue, JsValue> {
         console_log!("Generating stats for thread: {}", thread_id);
 
+        let stats = self.thread_stats(thread_id)?;
+        self.to_js(&stats)
+    }
+
+    /// `generate_thread_stats` for every visible thread in one call, in
+    /// thread order, reporting progress and honouring cancellation.
+    #[wasm_bindgen]
+    pub fn generate_all_stats(&self) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let threads: Vec<&String> = self.visible_threads().map(|(id, _)| id).collect();
+        console_log!("Generating stats for {} threads", threads.len());
+
+        let mut all_stats = Vec::with_capacity(threads.len());
+        for (i, thread_id) in threads.iter().enumerate() {
+            self.check_cancelled()?;
+            all_stats.push(self.thread_stats(thread_id)?);
+            self.emit_progress("stats", i + 1, Some(threads.len()));
+        }
+        self.to_js(&all_stats)
+    }
+
+    // Straight from the thread's emails and parent links; the tree itself is
+    // never built
+    fn thread_stats(&self, thread_id: &str) -> Result<ThreadStats, JsValue> {
         let emails = match self.threads.get(thread_id) {
             Some(emails) => emails,
             None => return Err(JsValue::from_str("Thread not found")),
         };
 
-        let tree_result = self.build_thread_tree(thread_id)?;
-        let tree: ThreadTree = serde_wasm_bindgen::from_value(tree_result)?;
-
         let participants = self.get_unique_participants(emails);
-        let max_depth = self.calculate_max_depth(&tree.roots);
-        let branch_count = self.count_branches(&tree.roots);
+        let outline = self.outline(emails);
 
         let mut forward_count = 0;
         let mut reply_count = 0;
         let mut external_count = 0;
+        let mut redacted_count = 0;
+        let mut metrics = metrics::ReadingMetrics::default();
 
         for email in emails {
+            metrics.add(&self.reading_metrics(email));
             if email.is_forward {
                 forward_count += 1;
             }
@@ -382,65 +956,88 @@ impl EmailThreadProcessor {
             if email.is_external {
                 external_count += 1;
             }
+            if rollups::is_redacted(email) {
+                redacted_count += 1;
+            }
         }
 
-        let stats = ThreadStats {
+        Ok(ThreadStats {
             thread_id: thread_id.to_string(),
             total_emails: emails.len(),
             participant_count: participants.len(),
             participants,
-            max_depth,
-            branch_count,
+            max_depth: outline.max_depth(),
+            branch_count: outline.branch_count,
             forward_count,
             reply_count,
             external_count,
-            date_range: tree.date_range,
-        };
-
-        serde_wasm_bindgen::to_value(&stats).map_err(|e| JsValue::from_str(&e.to_string()))
+            date_range: DateRange::of(emails).unwrap_or_default(),
+            participant_timeline: participation::participant_timeline(emails, &self.identity_map),
+            completeness: completeness::completeness(emails),
+            metrics,
+            classifications: classifications::summarize(emails),
+            redacted_count,
+            has_redactions: redacted_count > 0,
+        })
     }
 
-    fn calculate_max_depth(&self, roots: &[ThreadNode]) -> usize {
-        roots.iter()
-            .map(|node| self.node_max_depth(node, 0))
-            .max()
-            .unwrap_or(0)
+    #[wasm_bindgen]
+    pub fn set_threading_mode(&mut self, mode: &str) -> Result<(), JsValue> {
+        self.threading_mode = match mode {
+            "thread_id" => ThreadingMode::ThreadId,
+            "conversation_index" => ThreadingMode::ConversationIndex,
+            "gmail_thread_id" => ThreadingMode::GmailThreadId,
+            _ => return Err(JsValue::from_str(&format!("Unknown threading mode: {}", mode))),
+        };
+        console_log!("Threading mode set to {:?}", self.threading_mode);
+        self.audit("set_threading_mode", mode.to_string());
+        Ok(())
     }
 
-    fn node_max_depth(&self, node: &ThreadNode, current_depth: usize) -> usize {
-        if node.children.is_empty() {
-            current_depth
-        } else {
-            node.children.iter()
-                .map(|child| self.node_max_depth(child, current_depth + 1))
-                .max()
-                .unwrap_or(current_depth)
+    #[wasm_bindgen]
+    pub fn get_threading_mode(&self) -> String {
+        match self.threading_mode {
+            ThreadingMode::ThreadId => "thread_id".to_string(),
+            ThreadingMode::ConversationIndex => "conversation_index".to_string(),
+            ThreadingMode::GmailThreadId => "gmail_thread_id".to_string(),
         }
     }
 
-    fn count_branches(&self, roots: &[ThreadNode]) -> usize {
-        roots.iter()
-            .map(|node| self.node_branch_count(node))
-            .sum()
+    #[wasm_bindgen]
+    pub fn get_labels(&self) -> Vec<String> {
+        let mut labels: Vec<String> = self
+            .included_emails()
+            .flat_map(|e| e.tags.iter().cloned())
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        labels.sort_by_key(|l| l.to_lowercase());
+        labels
     }
 
-    fn node_branch_count(&self, node: &ThreadNode) -> usize {
-        let mut branches = if node.children.len() > 1 {
-            node.children.len()
-        } else {
-            0
-        };
-
-        for child in &node.children {
-            branches += self.node_branch_count(child);
-        }
-
-        branches
+    /// Optionally paged; an id is the cursor for the page after it.
+    #[wasm_bindgen]
+    pub fn get_email_ids_by_label(&self, label: &str, page: JsValue) -> Result<Vec<String>, JsValue> {
+        let ids: Vec<&str> = self
+            .included_emails()
+            .filter(|e| e.tags.iter().any(|t| t.eq_ignore_ascii_case(label)))
+            .map(|e| e.id.as_str())
+            .collect();
+        let page = paging::PageRequest::parse(page)?.apply(ids, |&id| id)?;
+        Ok(page.items.into_iter().map(String::from).collect())
     }
 
+    /// Optionally paged and sorted (see `get_thread_summaries`); a thread id
+    /// is the cursor for the page after it.
     #[wasm_bindgen]
-    pub fn get_thread_ids(&self) -> Vec<String> {
-        self.threads.keys().cloned().collect()
+    pub fn get_thread_ids(&self, page: JsValue, sort: Option<String>) -> Result<Vec<String>, JsValue> {
+        let ids: Vec<&str> = self
+            .sorted_threads(sort.as_deref())?
+            .into_iter()
+            .map(|(id, _)| id.as_str())
+            .collect();
+        let page = paging::PageRequest::parse(page)?.apply(ids, |&id| id)?;
+        Ok(page.items.into_iter().map(String::from).collect())
     }
 
     #[wasm_bindgen]
@@ -454,12 +1051,213 @@ impl EmailThreadProcessor {
     }
 }
 
+impl EmailThreadProcessor {
+    // Shared by every tabular source (CSV, XLSX) so column mapping, date parsing
+    // and error limits behave the same whatever the container format.
+    fn load_rows<I>(
+        &mut self,
+        headers: &csv::StringRecord,
+        rows: I,
+        source: &str,
+        dialect: Option<LoadFileDialect>,
+    ) -> Result<usize, JsValue>
+    where
+        I: Iterator<Item = Result<csv::StringRecord, String>>,
+    {
+        let headers = &self.map_headers(headers);
+        let mut emails = Vec::new();
+        let mut row_count = 0;
+        let mut errors = Vec::new();
+        let has_bom = dialect.as_ref().is_some_and(|d| d.has_bom);
+        let previous_report = std::mem::replace(
+            &mut self.load_report,
+            LoadReport {
+                source: source.to_string(),
+                dialect,
+                ..Default::default()
+            },
+        );
+        if has_bom {
+            self.load_report.note_repair("bom", "Byte order mark stripped".to_string());
+        }
+
+        for result in rows {
+            if let Err(e) = self.check_cancelled() {
+                self.load_report = previous_report;
+                return Err(e);
+            }
+            row_count += 1;
+            let parsed = result
+                .map_err(|e| format!("Error reading {} record {}: {}", source, row_count, e))
+                .and_then(|mut row| {
+                    let fitted = dialect::fit_row(&mut row, headers.len())
+                        .map_err(|e| format!("Error reading {} record {}: {}", source, row_count, e))?;
+                    if let Some(kind) = fitted {
+                        self.load_report.note_repair(kind, format!("Record {}: {} fitted to {} columns", row_count, kind.replace('_', " "), headers.len()));
+                    }
+                    if row.iter().any(|f| f.contains('\n')) {
+                        self.load_report.note_repair("multiline_field", format!("Record {}: multi-line field kept", row_count));
+                    }
+                    let mut email = row
+                        .deserialize::<CsvRecord>(Some(headers))
+                        .map_err(|e| e.to_string())
+                        .and_then(|record| self.parse_csv_record(record))
+                        .map_err(|e| format!("Error parsing email record {}: {}", row_count, e))?;
+                    email.extra = headers
+                        .iter()
+                        .zip(row.iter())
+                        .filter(|(header, _)| !schema::is_mapped(header))
+                        .map(|(header, value)| (header.to_string(), value.to_string()))
+                        .collect();
+                    Ok(email)
+                });
+
+            match parsed {
+                Ok(email) => {
+                    console_log!("Successfully parsed email {}: {}", emails.len() + 1, email.subject);
+                    emails.push(email);
+                }
+                Err(e) => {
+                    console_log!("{}", e);
+                    self.emit_warning(&e, source);
+                    errors.push(e);
+                    if errors.len() > 5 {
+                        let message = format!("Too many parsing errors ({}), stopping", errors.len());
+                        self.load_report.rows_read = row_count;
+                        self.load_report.errors = errors;
+                        return Err(JsValue::from_str(&message));
+                    }
+                }
+            }
+            self.emit_progress(source, row_count, None);
+        }
+
+        self.emit_progress(source, row_count, Some(row_count));
+        if let Err(e) = self.check_cancelled() {
+            self.load_report = previous_report;
+            return Err(e);
+        }
+        self.apply_exclusions(&mut emails, source);
+        self.normalize_confidentiality(&mut emails);
+        let count = emails.len();
+        self.store_loaded(emails);
+        console_log!("Successfully loaded {} emails out of {} rows ({} errors)", count, row_count, errors.len());
+        self.load_report.rows_read = row_count;
+        self.load_report.emails_loaded = count;
+        self.load_report.errors = errors;
+
+        if count == 0 {
+            return Err(JsValue::from_str(&format!("No valid emails were parsed from {}", source)));
+        }
+
+        Ok(count)
+    }
+
+    // Renames load-file headers to the canonical names `CsvRecord` expects,
+    // per set_column_mapping
+    pub(crate) fn map_headers(&self, headers: &csv::StringRecord) -> csv::StringRecord {
+        headers
+            .iter()
+            .map(|header| {
+                self.column_mapping
+                    .iter()
+                    .find(|(_, source)| source.as_str() == header)
+                    .map(|(canonical, _)| canonical.as_str())
+                    .unwrap_or(header)
+            })
+            .collect()
+    }
+
+    // RFC 3339 first, then any formats configured with set_date_formats
+    pub(crate) fn parse_date(&self, value: &str, field: &str) -> Result<DateTime<Utc>, String> {
+        parse_date_field(value, field).or_else(|err| {
+            self.date_formats
+                .iter()
+                .find_map(|format| {
+                    chrono::DateTime::parse_from_str(value, format)
+                        .map(|d| d.with_timezone(&Utc))
+                        .ok()
+                        .or_else(|| chrono::NaiveDateTime::parse_from_str(value, format).ok().map(|d| d.and_utc()))
+                        .or_else(|| {
+                            chrono::NaiveDate::parse_from_str(value, format)
+                                .ok()
+                                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                                .map(|d| d.and_utc())
+                        })
+                })
+                .ok_or(err)
+        })
+    }
+
+    // Every loader goes through here so indexes derived from the previous
+    // corpus are dropped along with it
+    fn replace_emails(&mut self, emails: Vec<EmailMessage>) {
+        self.emails = emails;
+        self.search_index = OnceCell::new();
+        self.id_index = OnceCell::new();
+        self.duplicate_keys = OnceCell::new();
+    }
+
+    // Threads hold their own copies of each email; push edits made to
+    // `self.emails` through to them without re-grouping.
+    fn refresh_thread_copies(&mut self) {
+        let by_id: HashMap<&str, &EmailMessage> = self.emails.iter().map(|e| (e.id.as_str(), e)).collect();
+        let mut threads = std::mem::take(&mut self.threads);
+        for emails in threads.values_mut() {
+            for email in emails.iter_mut() {
+                if let Some(updated) = by_id.get(email.id.as_str()) {
+                    *email = self.thread_copy(updated);
+                }
+            }
+        }
+        self.threads = threads;
+    }
+}
+
+fn parse_date_field(value: &str, field: &str) -> Result<DateTime<Utc>, String> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .or_else(|_| chrono::DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%SZ"))
+        .map(|d| d.with_timezone(&Utc))
+        .map_err(|e| format!("Invalid date format for {}: {}", field, e))
+}
+
+// Fills in missing thread ids by walking in_reply_to/references back to the
+// conversation root, for sources that carry message headers but no thread field.
+fn infer_thread_ids(emails: &mut [EmailMessage]) {
+    let parents: HashMap<String, Option<String>> = emails
+        .iter()
+        .filter(|e| !e.message_id.is_empty())
+        .map(|e| (e.message_id.clone(), e.in_reply_to.clone()))
+        .collect();
+
+    for email in emails.iter_mut() {
+        if !email.thread_id.is_empty() || email.message_id.is_empty() {
+            continue;
+        }
+        if let Some(first_ref) = email.references.first() {
+            email.thread_id = first_ref.clone();
+            continue;
+        }
+
+        let mut root = email.message_id.clone();
+        let mut seen = std::collections::HashSet::new();
+        while let Some(Some(parent)) = parents.get(&root) {
+            if !seen.insert(root.clone()) {
+                break;
+            }
+            root = parent.clone();
+        }
+        email.thread_id = root;
+    }
+}
+
 #[derive(Default)]
 struct ThreadInfo {
     message_id: Option<String>,
     in_reply_to: Option<String>,
     references: Option<Vec<String>>,
     thread_id: Option<String>,
+    conversation_index: Option<String>,
     is_forward: bool,
     is_external: bool,
 }
@@ -499,6 +1297,7 @@ struct CsvRecord {
     #[serde(rename = "ESIType", default)]
     esi_type: String,
     #[serde(rename = "DeDuplicatedPath", default)]
+    #[allow(dead_code)]
     deduplicated_path: String,
     #[serde(rename = "DateCreated")]
     date_created: String,
@@ -517,9 +1316,12 @@ struct CsvRecord {
     #[serde(rename = "FullText")]
     full_text: String,
     #[serde(rename = "EndAttach_Left", default)]
+    #[allow(dead_code)]
     end_attach_left: String,
     #[serde(rename = "column_history")]
     column_history: String,
+    #[serde(rename = "ConversationIndex", default)]
+    conversation_index: String,
 }
 
 #[wasm_bindgen]
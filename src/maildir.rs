@@ -0,0 +1,101 @@
+use crate::{audit, rfc5322};
+use crate::{infer_thread_ids, EmailThreadProcessor};
+use indexmap::IndexMap;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Loads a Maildir export passed as file buffers plus their paths relative to
+    /// the Maildir root (e.g. "cur/1700000000.host:2,S" or ".Sent/new/...").
+    #[wasm_bindgen]
+    pub fn load_emails_from_maildir(&mut self, files: js_sys::Array, paths: Vec<String>) -> Result<usize, JsValue> {
+        console_log!("Loading {} Maildir entries", files.length());
+        self.reset_cancellation();
+
+        if files.length() as usize != paths.len() {
+            return Err(JsValue::from_str("Each Maildir file needs a matching path"));
+        }
+
+        let mut emails = Vec::new();
+        let mut error_count = 0;
+        let mut skipped = 0;
+        let mut input_hashes = IndexMap::new();
+        let id_base = self.positional_id_base("MAILDIR");
+
+        for (i, file) in files.iter().enumerate() {
+            self.check_cancelled()?;
+            let Some(folder) = maildir_folder(&paths[i]) else {
+                skipped += 1;
+                self.emit_progress("Maildir", i + 1, Some(paths.len()));
+                continue;
+            };
+
+            let data = js_sys::Uint8Array::new(&file).to_vec();
+            input_hashes.insert(paths[i].clone(), audit::sha256_hex(&data));
+            match rfc5322::parse_message(&String::from_utf8_lossy(&data)) {
+                Ok(mut email) => {
+                    email.id = format!("MAILDIR{:06}", id_base + emails.len() + 1);
+                    email.file_name = paths[i].clone();
+                    email.folder = folder;
+                    emails.push(email);
+                }
+                Err(e) => {
+                    error_count += 1;
+                    console_log!("Error parsing Maildir message {}: {}", paths[i], e);
+                    self.emit_warning(&format!("Error parsing Maildir message {}: {}", paths[i], e), "Maildir");
+                    if error_count > 5 {
+                        return Err(JsValue::from_str(&format!("Too many Maildir parsing errors ({}), stopping", error_count)));
+                    }
+                }
+            }
+            self.emit_progress("Maildir", i + 1, Some(paths.len()));
+        }
+
+        infer_thread_ids(&mut emails);
+
+        let count = self.finish_load(emails, "Maildir");
+        self.audit_inputs("load_emails_from_maildir", format!("{} emails loaded", count), input_hashes);
+        console_log!(
+            "Successfully loaded {} emails from Maildir ({} errors, {} non-message entries skipped)",
+            count,
+            error_count,
+            skipped
+        );
+
+        if count == 0 {
+            return Err(JsValue::from_str("No valid emails were parsed from Maildir"));
+        }
+
+        Ok(count)
+    }
+}
+
+// Only delivered mail lives in cur/ and new/; tmp/ and metadata files are
+// skipped. Maildir++ subfolders are dot-separated (".Sent.2024" -> "Sent/2024").
+pub(crate) fn maildir_folder(path: &str) -> Option<String> {
+    let parts: Vec<&str> = path
+        .split(['/', '\\'])
+        .filter(|p| !p.is_empty() && *p != ".")
+        .collect();
+    if parts.len() < 2 {
+        return None;
+    }
+
+    let box_dir = parts[parts.len() - 2];
+    if box_dir != "cur" && box_dir != "new" {
+        return None;
+    }
+
+    let folder: Vec<String> = parts[..parts.len() - 2]
+        .iter()
+        .flat_map(|p| p.split('.'))
+        .filter(|p| !p.is_empty())
+        .map(|p| p.to_string())
+        .collect();
+
+    if folder.is_empty() {
+        Some("INBOX".to_string())
+    } else {
+        Some(folder.join("/"))
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{network, EmailMessage, EmailThreadProcessor};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use wasm_bindgen::prelude::*;
+
+/// When an email counts as a blast, and what leaves blasts out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MassMailOptions {
+    // Emails to more distinct recipients than this are mass mail
+    pub threshold: usize,
+    // Leave mass mail out of key players, the traffic matrix and subgraphs
+    pub exclude_from_graph: bool,
+    // Leave replies to or as mass mail out of get_response_times
+    pub exclude_from_response_metrics: bool,
+}
+
+impl Default for MassMailOptions {
+    fn default() -> Self {
+        MassMailOptions {
+            threshold: 25,
+            exclude_from_graph: false,
+            exclude_from_response_metrics: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipientCount {
+    pub email_id: String,
+    pub thread_id: String,
+    pub from: String,
+    pub subject: String,
+    // Distinct addresses in each field
+    pub to_count: usize,
+    pub cc_count: usize,
+    pub bcc_count: usize,
+    // Distinct addresses across all three
+    pub recipient_count: usize,
+    pub is_mass_mail: bool,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Sets mass mail handling from a `MassMailOptions` object: `threshold`
+    /// (default 25 distinct recipients; more makes an email mass mail),
+    /// `exclude_from_graph` and `exclude_from_response_metrics` (both default
+    /// false). Thread trees flag mass mail on each node either way.
+    #[wasm_bindgen]
+    pub fn set_mass_mail_options(&mut self, options: JsValue) -> Result<(), JsValue> {
+        let options: MassMailOptions = if options.is_undefined() || options.is_null() {
+            MassMailOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options)?
+        };
+        self.audit("set_mass_mail_options", serde_json::to_string(&options).unwrap_or_default());
+        self.mass_mail = options;
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_mass_mail_options(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.mass_mail)
+    }
+
+    /// Recipient counts per in-scope email with the mass mail flag, for one
+    /// thread or all of them, largest audience first. `mass_mail_only` keeps
+    /// just the blasts.
+    #[wasm_bindgen]
+    pub fn get_recipient_counts(
+        &self,
+        thread_id: Option<String>,
+        mass_mail_only: Option<bool>,
+    ) -> Result<JsValue, JsValue> {
+        let emails: Vec<&EmailMessage> = match &thread_id {
+            Some(id) => self.threads.get(id).ok_or_else(|| JsValue::from_str("Thread not found"))?.iter().collect(),
+            None => self.included_emails().collect(),
+        };
+        let mut counts: Vec<RecipientCount> = emails
+            .into_iter()
+            .map(|email| {
+                let distinct = |fields: &[String]| distinct_identities(fields.iter()).len();
+                let recipient_count = recipient_count(email);
+                RecipientCount {
+                    email_id: email.id.clone(),
+                    thread_id: email.thread_id.clone(),
+                    from: email.from.clone(),
+                    subject: email.subject.clone(),
+                    to_count: distinct(&email.to),
+                    cc_count: distinct(&email.cc),
+                    bcc_count: distinct(&email.bcc),
+                    recipient_count,
+                    is_mass_mail: recipient_count > self.mass_mail.threshold,
+                }
+            })
+            .filter(|count| count.is_mass_mail || !mass_mail_only.unwrap_or(false))
+            .collect();
+        counts.sort_by(|a, b| b.recipient_count.cmp(&a.recipient_count).then_with(|| a.email_id.cmp(&b.email_id)));
+        self.to_js(&counts)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn is_mass_mail(&self, email: &EmailMessage) -> bool {
+        recipient_count(email) > self.mass_mail.threshold
+    }
+
+    // Mass mail left out of the communication graph per the options
+    pub(crate) fn excluded_from_graph(&self, email: &EmailMessage) -> bool {
+        self.mass_mail.exclude_from_graph && self.is_mass_mail(email)
+    }
+}
+
+/// Distinct addresses across To, Cc and Bcc.
+pub(crate) fn recipient_count(email: &EmailMessage) -> usize {
+    distinct_identities(email.to.iter().chain(&email.cc).chain(&email.bcc)).len()
+}
+
+fn distinct_identities<'a>(fields: impl Iterator<Item = &'a String>) -> BTreeSet<String> {
+    fields.flat_map(|f| network::identities(f)).collect()
+}
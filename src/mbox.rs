@@ -0,0 +1,85 @@
+use crate::rfc5322;
+use crate::{infer_thread_ids, EmailThreadProcessor};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Loads an mbox file such as a Google Takeout export. Gmail thread ids and
+    /// labels are kept (see the `gmail_thread_id` threading mode).
+    #[wasm_bindgen]
+    pub fn load_emails_from_mbox(&mut self, mbox_data: &str) -> Result<usize, JsValue> {
+        console_log!("Loading emails from mbox data, length: {}", mbox_data.len());
+        self.reset_cancellation();
+
+        if mbox_data.is_empty() {
+            return Err(JsValue::from_str("mbox data is empty"));
+        }
+
+        let mut emails = Vec::new();
+        let mut error_count = 0;
+
+        let messages = split_mbox(mbox_data);
+        let id_base = self.positional_id_base("MBOX");
+        for (i, raw) in messages.iter().enumerate() {
+            self.check_cancelled()?;
+            match rfc5322::parse_message(raw) {
+                Ok(mut email) => {
+                    email.id = format!("MBOX{:06}", id_base + i + 1);
+                    emails.push(email);
+                }
+                Err(e) => {
+                    error_count += 1;
+                    console_log!("Error parsing mbox message {}: {}", i + 1, e);
+                    self.emit_warning(&format!("Error parsing mbox message {}: {}", i + 1, e), "mbox");
+                    if error_count > 5 {
+                        return Err(JsValue::from_str(&format!("Too many mbox parsing errors ({}), stopping", error_count)));
+                    }
+                }
+            }
+            self.emit_progress("mbox", i + 1, Some(messages.len()));
+        }
+
+        infer_thread_ids(&mut emails);
+
+        let count = self.finish_load(emails, "mbox");
+        self.audit_load("load_emails_from_mbox", format!("{} emails loaded", count), &[("mbox", mbox_data.as_bytes())]);
+        console_log!("Successfully loaded {} emails from mbox ({} errors)", count, error_count);
+
+        if count == 0 {
+            return Err(JsValue::from_str("No valid emails were parsed from mbox"));
+        }
+
+        Ok(count)
+    }
+}
+
+// Messages start at "From " lines; body lines that began with "From " were
+// escaped as ">From " by the writer (mboxrd), so undo one level of that.
+pub(crate) fn split_mbox(data: &str) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in data.lines() {
+        if line.starts_with("From ") {
+            if let Some(message) = current.take() {
+                messages.push(message);
+            }
+            current = Some(String::new());
+            continue;
+        }
+
+        if let Some(message) = current.as_mut() {
+            let unescaped = match line.strip_prefix('>') {
+                Some(rest) if rest.trim_start_matches('>').starts_with("From ") => rest,
+                _ => line,
+            };
+            message.push_str(unescaped);
+            message.push('\n');
+        }
+    }
+
+    if let Some(message) = current {
+        messages.push(message);
+    }
+    messages
+}
@@ -0,0 +1,257 @@
+use crate::config::DedupPolicy;
+use crate::{
+    config, exclusion, filetypes, filter, languages, mass_mail, stop_words, EmailThreadProcessor, ThreadingMode,
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// What was loaded and set aside, as counts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MethodologyCorpus {
+    pub source: String,
+    pub rows_read: usize,
+    pub emails_loaded: usize,
+    // Dropped by the exclusion rules, per rule
+    pub excluded_count: usize,
+    pub excluded_by: IndexMap<String, usize>,
+    pub repaired_count: usize,
+    pub thread_count: usize,
+    pub reattached_count: usize,
+    pub sequestered_count: usize,
+    pub locked_thread_count: usize,
+}
+
+/// One processing step in plain language, for a protocol or declaration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MethodologyStep {
+    pub name: String,
+    pub description: String,
+}
+
+/// The algorithms and settings behind the current threads and analytics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Methodology {
+    // Crate name and version
+    pub software: String,
+    pub threading: config::ThreadingConfig,
+    pub include_singletons: bool,
+    // As get_display_timezone reports it
+    pub display_timezone: String,
+    pub exclusion_rules: exclusion::ExclusionRules,
+    pub type_filter: filetypes::TypeFilter,
+    pub global_filter: filter::GlobalFilter,
+    pub mass_mail: mass_mail::MassMailOptions,
+    pub stop_words: stop_words::StopWordConfig,
+    pub language_options: languages::LanguageOptions,
+    pub identity_map_persons: usize,
+    pub confidentiality_values: usize,
+    pub corpus: MethodologyCorpus,
+    // In processing order
+    pub steps: Vec<MethodologyStep>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Describes how the current threads were built: the threading mode,
+    /// dedup policy, date handling, exclusions and filters in effect, the
+    /// software version and corpus counts, with each step spelled out in
+    /// plain language for an ESI protocol or expert declaration. `format` is
+    /// "json" (default) or "text".
+    #[wasm_bindgen]
+    pub fn export_methodology(&self, format: Option<String>) -> Result<String, JsValue> {
+        let methodology = self.methodology();
+        let format = format.unwrap_or_else(|| "json".to_string());
+        console_log!("Exporting methodology as {}", format);
+
+        match format.as_str() {
+            "json" => self.to_json(&methodology),
+            "text" => Ok(to_text(&methodology)),
+            other => Err(JsValue::from_str(&format!("Unknown export format: {}", other))),
+        }
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn methodology(&self) -> Methodology {
+        let report = &self.load_report;
+        Methodology {
+            software: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            threading: self.threading_config(),
+            include_singletons: self.include_singletons,
+            display_timezone: self.get_display_timezone(),
+            exclusion_rules: self.exclusion_rules.clone(),
+            type_filter: self.type_filter.clone(),
+            global_filter: self.global_filter.clone(),
+            mass_mail: self.mass_mail.clone(),
+            stop_words: self.stop_words.clone(),
+            language_options: self.language_options.clone(),
+            identity_map_persons: self.identity_map.person_count(),
+            confidentiality_values: self.confidentiality_map.len(),
+            corpus: MethodologyCorpus {
+                source: report.source.clone(),
+                rows_read: report.rows_read,
+                emails_loaded: report.emails_loaded,
+                excluded_count: report.excluded_count,
+                excluded_by: report.excluded_by.clone(),
+                repaired_count: report.repaired_count,
+                thread_count: self.threads.len(),
+                reattached_count: self.reattachments.len(),
+                sequestered_count: self.sequestered.len(),
+                locked_thread_count: self.locked_threads.len(),
+            },
+            steps: self.methodology_steps(),
+        }
+    }
+
+    fn methodology_steps(&self) -> Vec<MethodologyStep> {
+        let mut steps = Vec::new();
+        let mut step =
+            |name: &str, description: String| steps.push(MethodologyStep { name: name.to_string(), description });
+
+        let formats = if self.date_formats.is_empty() {
+            String::new()
+        } else {
+            format!(", then with the formats {}", quoted(&self.date_formats))
+        };
+        step(
+            "Date handling",
+            format!(
+                "Dates are parsed as RFC 3339{} and stored in UTC. Results are displayed in {}.",
+                formats,
+                self.get_display_timezone()
+            ),
+        );
+
+        let rules = &self.exclusion_rules;
+        step(
+            "Exclusions",
+            if rules.hashes.is_empty() && rules.file_types.is_empty() && rules.sender_patterns.is_empty() {
+                "No records are excluded at load.".to_string()
+            } else {
+                format!(
+                    "Records are dropped at load when their hash is one of {} listed, their file type is one of {} or \
+                     their sender matches one of {}. {} records were excluded from the last load.",
+                    rules.hashes.len(),
+                    quoted(&rules.file_types),
+                    quoted(&rules.sender_patterns),
+                    self.load_report.excluded_count
+                )
+            },
+        );
+
+        let grouping = match self.threading_mode {
+            ThreadingMode::ThreadId => "by the thread id supplied in the load file",
+            ThreadingMode::ConversationIndex => {
+                "by the GUID of the Outlook Conversation Index, falling back to the supplied thread id"
+            }
+            ThreadingMode::GmailThreadId => {
+                "by the Gmail thread id (X-GM-THRID), falling back to the supplied thread id"
+            }
+        };
+        let fallback = if self.subject_fallback {
+            " Emails with neither are grouped by normalized subject, with reply and forward prefixes removed."
+        } else {
+            " Emails with neither are left unthreaded."
+        };
+        let naming = if self.stable_thread_ids {
+            " Each thread is named \"T-\" and the first 16 hex digits of the SHA-256 of its root's Message-ID, or of \
+             its normalized subject when the root has none, so ids are stable across reprocessing."
+        } else {
+            ""
+        };
+        let locks = if self.locked_threads.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " {} threads were locked after manual review and keep the emails and reply structure they had then.",
+                self.locked_threads.len()
+            )
+        };
+        step("Threading", format!("Emails are grouped into threads {}.{}{}{}", grouping, fallback, naming, locks));
+
+        let index = if self.threading_mode == ThreadingMode::ConversationIndex {
+            "the nearest ancestor in the Conversation Index, then "
+        } else {
+            ""
+        };
+        step(
+            "Reply structure",
+            format!(
+                "Each email's parent is {}the email whose Message-ID matches its In-Reply-To header, then the \
+                 nearest email in its References header not dated after it. {} emails were attached to a parent \
+                 by heuristic reattachment and are marked as such.",
+                index,
+                self.reattachments.len()
+            ),
+        );
+
+        step(
+            "Deduplication",
+            match self.dedup_policy {
+                DedupPolicy::None => "Every copy of a message is kept, e.g. one per custodian.".to_string(),
+                DedupPolicy::MessageId => {
+                    "Within a thread, only the earliest copy of each Message-ID is kept.".to_string()
+                }
+                DedupPolicy::Hash => "Within a thread, only the earliest copy of each hash is kept.".to_string(),
+            },
+        );
+
+        let singletons = if self.include_singletons { "included" } else { "left out" };
+        step(
+            "Scope",
+            format!(
+                "Threads of a single email are {}. {} emails are sequestered and shown only as placeholders. \
+                 Document type and working-set filters are applied as listed in the settings.",
+                singletons,
+                self.sequestered.len()
+            ),
+        );
+
+        step(
+            "Ordering",
+            if self.deterministic {
+                "Threads are kept in id order and ties between emails sent at the same time are broken by email \
+                 id, so identical input and settings give identical output."
+                    .to_string()
+            } else {
+                "Emails within a thread are ordered by date sent, ties keeping load order.".to_string()
+            },
+        );
+
+        step(
+            "Identities",
+            format!(
+                "Addresses are compared lowercased without display names. {} people are mapped to their \
+                 addresses and counted as one participant. Emails to more than {} distinct recipients are \
+                 treated as mass mail.",
+                self.identity_map.person_count(),
+                self.mass_mail.threshold
+            ),
+        );
+        steps
+    }
+}
+
+fn quoted(values: &[String]) -> String {
+    if values.is_empty() {
+        return "none".to_string();
+    }
+    values.iter().map(|v| format!("\"{}\"", v)).collect::<Vec<_>>().join(", ")
+}
+
+fn to_text(methodology: &Methodology) -> String {
+    let mut text = format!("Processing methodology ({})\n", methodology.software);
+    let corpus = &methodology.corpus;
+    if !corpus.source.is_empty() {
+        text.push_str(&format!("Source: {}\n", corpus.source));
+    }
+    text.push_str(&format!(
+        "{} rows read, {} emails loaded, {} excluded, {} threads\n",
+        corpus.rows_read, corpus.emails_loaded, corpus.excluded_count, corpus.thread_count
+    ));
+    for (i, step) in methodology.steps.iter().enumerate() {
+        text.push_str(&format!("\n{}. {}\n{}\n", i + 1, step.name, step.description));
+    }
+    text
+}
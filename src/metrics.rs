@@ -0,0 +1,68 @@
+use crate::{inclusive, EmailMessage, EmailThreadProcessor};
+use serde::{Deserialize, Serialize};
+
+// Typical reading speed for business correspondence
+const WORDS_PER_MINUTE: usize = 200;
+
+// Load file columns carrying a document's size in bytes, compared with case,
+// spaces and punctuation ignored
+const SIZE_COLUMNS: &[&str] = &["filesize", "nativefilesize", "nativesize", "size"];
+
+/// Reading effort for one email, or summed over a thread.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReadingMetrics {
+    pub word_count: usize,
+    // Words the email adds itself, quoted history excluded
+    pub new_word_count: usize,
+    // From new_word_count, so quoted text is not read twice over a thread
+    pub reading_seconds: usize,
+    pub attachment_count: usize,
+    // Attachments with a file size column only
+    pub attachment_bytes: u64,
+}
+
+impl ReadingMetrics {
+    pub(crate) fn add(&mut self, other: &ReadingMetrics) {
+        self.word_count += other.word_count;
+        self.new_word_count += other.new_word_count;
+        self.reading_seconds += other.reading_seconds;
+        self.attachment_count += other.attachment_count;
+        self.attachment_bytes += other.attachment_bytes;
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn reading_metrics(&self, email: &EmailMessage) -> ReadingMetrics {
+        let new_word_count = word_count(inclusive::new_content(&email.full_text));
+        ReadingMetrics {
+            word_count: word_count(&email.full_text),
+            new_word_count,
+            reading_seconds: (new_word_count * 60).div_ceil(WORDS_PER_MINUTE),
+            attachment_count: email.attachment_ids.len(),
+            attachment_bytes: email
+                .attachment_ids
+                .iter()
+                .filter_map(|id| self.email_by_id(id))
+                .filter_map(file_size)
+                .sum(),
+        }
+    }
+
+    pub(crate) fn email_by_id(&self, email_id: &str) -> Option<&EmailMessage> {
+        self.email_index(email_id).map(|i| &self.emails[i])
+    }
+}
+
+fn word_count(text: &str) -> usize {
+    text.split_whitespace().filter(|w| w.chars().any(char::is_alphanumeric)).count()
+}
+
+fn file_size(email: &EmailMessage) -> Option<u64> {
+    email.extra.iter().find_map(|(column, value)| {
+        let column: String = column.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+        if !SIZE_COLUMNS.contains(&column.as_str()) {
+            return None;
+        }
+        value.trim().replace(',', "").parse().ok()
+    })
+}
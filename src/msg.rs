@@ -0,0 +1,284 @@
+use crate::{audit, rfc5322};
+use crate::{infer_thread_ids, EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::io::{Cursor, Read, Seek};
+use wasm_bindgen::prelude::*;
+
+const PR_SUBJECT: u16 = 0x0037;
+const PR_CLIENT_SUBMIT_TIME: u16 = 0x0039;
+const PR_CONVERSATION_INDEX: u16 = 0x0071;
+const PR_TRANSPORT_MESSAGE_HEADERS: u16 = 0x007D;
+const PR_RECIPIENT_TYPE: u16 = 0x0C15;
+const PR_SENDER_EMAIL_ADDRESS: u16 = 0x0C1F;
+const PR_MESSAGE_DELIVERY_TIME: u16 = 0x0E06;
+const PR_BODY: u16 = 0x1000;
+const PR_INTERNET_MESSAGE_ID: u16 = 0x1035;
+const PR_INTERNET_REFERENCES: u16 = 0x1039;
+const PR_IN_REPLY_TO_ID: u16 = 0x1042;
+const PR_EMAIL_ADDRESS: u16 = 0x3003;
+const PR_CREATION_TIME: u16 = 0x3007;
+const PR_LAST_MODIFICATION_TIME: u16 = 0x3008;
+const PR_SMTP_ADDRESS: u16 = 0x39FE;
+const PR_SENDER_SMTP_ADDRESS: u16 = 0x5D01;
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Loads loose Outlook .msg files, passed as an array of Uint8Arrays with
+    /// their file names in the same order. Each email's id is its file name
+    /// without the extension, including any folders (e.g. "Inbox/msg0001"),
+    /// and a repeated id takes "-2", "-3" and so on.
+    #[wasm_bindgen]
+    pub fn load_emails_from_msg(&mut self, files: js_sys::Array, file_names: Vec<String>) -> Result<usize, JsValue> {
+        console_log!("Loading {} MSG files", files.length());
+        self.reset_cancellation();
+
+        if files.length() == 0 {
+            return Err(JsValue::from_str("No MSG files provided"));
+        }
+
+        let mut emails = Vec::new();
+        let mut errors = Vec::new();
+        let mut input_hashes = IndexMap::new();
+        let mut used_ids: HashSet<String> = HashSet::new();
+        let id_base = self.positional_id_base("MSG");
+
+        for (i, file) in files.iter().enumerate() {
+            self.check_cancelled()?;
+            let data = js_sys::Uint8Array::new(&file).to_vec();
+            let file_name = file_names.get(i).cloned().unwrap_or_default();
+            let input_name = if file_name.is_empty() { format!("file {}", i + 1) } else { file_name.clone() };
+            input_hashes.insert(input_name, audit::sha256_hex(&data));
+
+            match parse_msg(&data, &file_name) {
+                Ok(mut email) => {
+                    if email.id.is_empty() {
+                        email.id = format!("MSG{:06}", id_base + i + 1);
+                    }
+                    // The same name twice, e.g. files from two folders passed
+                    // without their paths; the suffix must not be a name
+                    // already used either
+                    let mut suffix = 1;
+                    let mut id = email.id.clone();
+                    while used_ids.contains(&id) {
+                        suffix += 1;
+                        id = format!("{}-{}", email.id, suffix);
+                    }
+                    used_ids.insert(id.clone());
+                    email.id = id;
+                    emails.push(email);
+                }
+                Err(e) => {
+                    let error = format!("Error parsing MSG file {} ({}): {}", i + 1, file_name, e);
+                    console_log!("{}", error);
+                    self.emit_warning(&error, "MSG");
+                    errors.push(error);
+                    if errors.len() > 5 {
+                        let message = format!("Too many MSG parsing errors ({}), stopping", errors.len());
+                        return Err(JsValue::from_str(&message));
+                    }
+                }
+            }
+            self.emit_progress("MSG", i + 1, Some(files.length() as usize));
+        }
+
+        infer_thread_ids(&mut emails);
+
+        let count = self.finish_load(emails, "MSG");
+        self.load_report.rows_read = files.length() as usize;
+        self.load_report.errors = errors;
+        self.audit_inputs("load_emails_from_msg", format!("{} emails loaded", count), input_hashes);
+        let error_count = self.load_report.errors.len();
+        console_log!("Successfully loaded {} emails from MSG files ({} errors)", count, error_count);
+
+        if count == 0 {
+            return Err(JsValue::from_str("No valid emails were parsed from MSG files"));
+        }
+
+        Ok(count)
+    }
+}
+
+pub(crate) fn parse_msg(data: &[u8], file_name: &str) -> Result<EmailMessage, String> {
+    let mut cf = cfb::CompoundFile::open(Cursor::new(data)).map_err(|e| format!("Not an Outlook MSG file: {}", e))?;
+
+    // The top-level property stream has a 32-byte header; recipient and
+    // attachment storages use an 8-byte one.
+    let fixed = read_fixed_properties(&mut cf, "/", 32);
+    let headers = read_string_property(&mut cf, "/", PR_TRANSPORT_MESSAGE_HEADERS)
+        .map(|h| rfc5322::parse_headers(&h))
+        .unwrap_or_default();
+
+    let from = rfc5322::header(&headers, "From")
+        .and_then(|v| rfc5322::parse_addresses(v).into_iter().next())
+        .or_else(|| read_string_property(&mut cf, "/", PR_SENDER_SMTP_ADDRESS))
+        .or_else(|| read_string_property(&mut cf, "/", PR_SENDER_EMAIL_ADDRESS))
+        .unwrap_or_default();
+
+    let message_id = rfc5322::header(&headers, "Message-ID")
+        .map(|v| v.to_string())
+        .or_else(|| read_string_property(&mut cf, "/", PR_INTERNET_MESSAGE_ID))
+        .unwrap_or_default();
+    let in_reply_to = rfc5322::header(&headers, "In-Reply-To")
+        .map(|v| v.to_string())
+        .or_else(|| read_string_property(&mut cf, "/", PR_IN_REPLY_TO_ID))
+        .and_then(|v| rfc5322::parse_message_ids(&v).into_iter().next());
+    let references = rfc5322::header(&headers, "References")
+        .map(|v| v.to_string())
+        .or_else(|| read_string_property(&mut cf, "/", PR_INTERNET_REFERENCES))
+        .map(|v| rfc5322::parse_message_ids(&v))
+        .unwrap_or_default();
+
+    let date_sent = rfc5322::header(&headers, "Date")
+        .and_then(rfc5322::parse_date)
+        .or_else(|| fixed_time(&fixed, PR_CLIENT_SUBMIT_TIME))
+        .or_else(|| fixed_time(&fixed, PR_MESSAGE_DELIVERY_TIME))
+        .ok_or_else(|| "MSG file has no sent date".to_string())?;
+    let date_created = fixed_time(&fixed, PR_CREATION_TIME).unwrap_or(date_sent);
+    let date_last_modified = fixed_time(&fixed, PR_LAST_MODIFICATION_TIME).unwrap_or(date_created);
+
+    let (to, cc, bcc) = read_recipients(&mut cf);
+    let subject = read_string_property(&mut cf, "/", PR_SUBJECT).unwrap_or_default();
+    // The path relative to the upload, so same-named files in different
+    // folders keep apart
+    let path = file_name.replace('\\', "/");
+    let path = path.trim_start_matches("./").trim_start_matches('/');
+    let path = match path.len().checked_sub(4).and_then(|at| Some((at, path.get(at..)?))) {
+        Some((at, extension)) if extension.eq_ignore_ascii_case(".msg") => &path[..at],
+        _ => path,
+    };
+
+    Ok(EmailMessage {
+        id: path.to_string(),
+        message_id: rfc5322::parse_message_ids(&message_id).into_iter().next().unwrap_or_default(),
+        in_reply_to,
+        references,
+        from: from.clone(),
+        to,
+        cc,
+        bcc,
+        title: subject.clone(),
+        subject,
+        date_sent,
+        date_created,
+        date_last_modified,
+        file_name: file_name.to_string(),
+        file_type: "email".to_string(),
+        full_text: read_string_property(&mut cf, "/", PR_BODY).unwrap_or_default(),
+        author: from,
+        conversation_index: read_binary_property(&mut cf, "/", PR_CONVERSATION_INDEX).map(|b| to_hex(&b)),
+        ..Default::default()
+    })
+}
+
+fn read_recipients<F: Read + Seek>(cf: &mut cfb::CompoundFile<F>) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let storages: Vec<String> = cf
+        .read_root_storage()
+        .filter(|e| e.is_storage() && e.name().starts_with("__recip_version1.0_"))
+        .map(|e| format!("/{}", e.name()))
+        .collect();
+
+    let (mut to, mut cc, mut bcc) = (Vec::new(), Vec::new(), Vec::new());
+    for storage in storages {
+        let address = read_string_property(cf, &storage, PR_SMTP_ADDRESS)
+            .or_else(|| read_string_property(cf, &storage, PR_EMAIL_ADDRESS))
+            .unwrap_or_default();
+        if address.is_empty() {
+            continue;
+        }
+
+        let fixed = read_fixed_properties(cf, &storage, 8);
+        match fixed_u32(&fixed, PR_RECIPIENT_TYPE) {
+            Some(2) => cc.push(address),
+            Some(3) => bcc.push(address),
+            _ => to.push(address),
+        }
+    }
+
+    (to, cc, bcc)
+}
+
+fn read_stream<F: Read + Seek>(cf: &mut cfb::CompoundFile<F>, path: &str) -> Option<Vec<u8>> {
+    let mut stream = cf.open_stream(path).ok()?;
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).ok()?;
+    Some(buf)
+}
+
+fn stream_path(storage: &str, prop: u16, prop_type: u16) -> String {
+    format!("{}/__substg1.0_{:04X}{:04X}", storage.trim_end_matches('/'), prop, prop_type)
+}
+
+fn read_string_property<F: Read + Seek>(cf: &mut cfb::CompoundFile<F>, storage: &str, prop: u16) -> Option<String> {
+    let value = if let Some(bytes) = read_stream(cf, &stream_path(storage, prop, 0x001F)) {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        // PT_STRING8 is in the sender's code page; Latin-1 covers the common case
+        let bytes = read_stream(cf, &stream_path(storage, prop, 0x001E))?;
+        bytes.iter().map(|&b| b as char).collect()
+    };
+
+    let value = value.trim_end_matches('\0').trim().to_string();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+fn read_binary_property<F: Read + Seek>(cf: &mut cfb::CompoundFile<F>, storage: &str, prop: u16) -> Option<Vec<u8>> {
+    read_stream(cf, &stream_path(storage, prop, 0x0102)).filter(|b| !b.is_empty())
+}
+
+// Fixed-width properties live in one stream of 16-byte entries:
+// type (u16), id (u16), flags (u32), value (8 bytes).
+fn read_fixed_properties<F: Read + Seek>(
+    cf: &mut cfb::CompoundFile<F>,
+    storage: &str,
+    header_len: usize,
+) -> Vec<(u16, [u8; 8])> {
+    let path = format!("{}/__properties_version1.0", storage.trim_end_matches('/'));
+    let Some(bytes) = read_stream(cf, &path) else {
+        return Vec::new();
+    };
+
+    bytes
+        .get(header_len..)
+        .unwrap_or_default()
+        .chunks_exact(16)
+        .map(|entry| {
+            let id = u16::from_le_bytes([entry[2], entry[3]]);
+            let mut value = [0u8; 8];
+            value.copy_from_slice(&entry[8..16]);
+            (id, value)
+        })
+        .collect()
+}
+
+fn fixed_u32(props: &[(u16, [u8; 8])], prop: u16) -> Option<u32> {
+    props
+        .iter()
+        .find(|(id, _)| *id == prop)
+        .map(|(_, v)| u32::from_le_bytes([v[0], v[1], v[2], v[3]]))
+}
+
+fn fixed_time(props: &[(u16, [u8; 8])], prop: u16) -> Option<DateTime<Utc>> {
+    let (_, value) = props.iter().find(|(id, _)| *id == prop)?;
+    filetime_to_datetime(u64::from_le_bytes(*value))
+}
+
+pub(crate) fn filetime_to_datetime(filetime: u64) -> Option<DateTime<Utc>> {
+    const EPOCH_DIFF_SECS: i64 = 11_644_473_600;
+    if filetime == 0 {
+        return None;
+    }
+    let secs = (filetime / 10_000_000) as i64 - EPOCH_DIFF_SECS;
+    let nanos = ((filetime % 10_000_000) * 100) as u32;
+    DateTime::from_timestamp(secs, nanos)
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
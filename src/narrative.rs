@@ -0,0 +1,152 @@
+use crate::{inclusive, redactions, EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+const COLLAPSED: &str = "[... quoted text collapsed ...]";
+
+/// One message of a thread narrative, in reading order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NarrativeEntry {
+    // 1-based place in the narrative
+    pub position: usize,
+    pub email_id: String,
+    // "BEG-END", or BegBates alone for a one-page document
+    pub bates: String,
+    pub from: String,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub date_sent: DateTime<Utc>,
+    pub subject: String,
+    // Position of the message this one replies to
+    pub reply_to: Option<usize>,
+    // What the message adds itself, redactions applied
+    pub body: String,
+    pub quoted_collapsed: bool,
+    pub sequestered: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadNarrative {
+    pub thread_id: String,
+    pub subject: String,
+    pub entries: Vec<NarrativeEntry>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// The whole thread as one document to read top to bottom: every message
+    /// oldest first with sender, recipients, date and Bates cite, and only the
+    /// text it adds, quoted earlier messages collapsed. `format` is "text"
+    /// (default) or "json".
+    #[wasm_bindgen]
+    pub fn export_thread_narrative(&self, thread_id: &str, format: Option<String>) -> Result<String, JsValue> {
+        let narrative = self.thread_narrative(thread_id)?;
+        let format = format.unwrap_or_else(|| "text".to_string());
+        console_log!("Exporting narrative of thread {} as {}", thread_id, format);
+
+        match format.as_str() {
+            "text" => Ok(to_text(&narrative)),
+            "json" => self.to_json(&narrative),
+            other => Err(JsValue::from_str(&format!("Unknown export format: {}", other))),
+        }
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn thread_narrative(&self, thread_id: &str) -> Result<ThreadNarrative, JsValue> {
+        let emails = self
+            .threads
+            .get(thread_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Thread not found: {}", thread_id)))?;
+        let mut ordered: Vec<&EmailMessage> = emails.iter().collect();
+        ordered.sort_by(|a, b| a.date_sent.cmp(&b.date_sent).then_with(|| a.beg_bates.cmp(&b.beg_bates)));
+        let positions: HashMap<&str, usize> = ordered.iter().enumerate().map(|(i, e)| (e.id.as_str(), i + 1)).collect();
+        let parents = self.resolve_parents(emails);
+
+        let entries = ordered
+            .iter()
+            .enumerate()
+            .map(|(i, email)| {
+                let text = redactions::export_text(email);
+                let body = inclusive::new_content(&text).trim_end();
+                NarrativeEntry {
+                    position: i + 1,
+                    email_id: email.id.clone(),
+                    bates: bates_cite(email),
+                    from: email.from.clone(),
+                    to: email.to.clone(),
+                    cc: email.cc.clone(),
+                    date_sent: email.date_sent,
+                    subject: email.subject.clone(),
+                    reply_to: parents.get(&email.id).and_then(|p| positions.get(p.as_str()).copied()),
+                    quoted_collapsed: body.len() < text.trim_end().len(),
+                    body: body.to_string(),
+                    sequestered: self.is_sequestered(&email.id),
+                }
+            })
+            .collect();
+
+        Ok(ThreadNarrative {
+            thread_id: thread_id.to_string(),
+            subject: ordered.first().map(|e| e.subject.clone()).unwrap_or_default(),
+            entries,
+        })
+    }
+}
+
+fn bates_cite(email: &EmailMessage) -> String {
+    let (beg, end) = (email.beg_bates.trim(), email.end_bates.trim());
+    if end.is_empty() || end == beg {
+        beg.to_string()
+    } else {
+        format!("{}-{}", beg, end)
+    }
+}
+
+pub(crate) fn to_text(narrative: &ThreadNarrative) -> String {
+    let mut text = format!("Thread: {}\n", narrative.subject);
+    text.push_str(&format!("Thread ID: {}\n", narrative.thread_id));
+    if let (Some(first), Some(last)) = (narrative.entries.first(), narrative.entries.last()) {
+        text.push_str(&format!(
+            "{} messages, {} to {}\n",
+            narrative.entries.len(),
+            first.date_sent.format("%Y-%m-%d %H:%M UTC"),
+            last.date_sent.format("%Y-%m-%d %H:%M UTC")
+        ));
+    }
+
+    for entry in &narrative.entries {
+        text.push_str(&format!("\n{}\n", "=".repeat(72)));
+        let cite = if entry.bates.is_empty() { entry.email_id.clone() } else { entry.bates.clone() };
+        text.push_str(&format!("[{}] {}\n", entry.position, cite));
+        text.push_str(&format!("From:    {}\n", entry.from));
+        if !entry.to.is_empty() {
+            text.push_str(&format!("To:      {}\n", entry.to.join(", ")));
+        }
+        if !entry.cc.is_empty() {
+            text.push_str(&format!("Cc:      {}\n", entry.cc.join(", ")));
+        }
+        text.push_str(&format!("Date:    {}\n", entry.date_sent.format("%Y-%m-%d %H:%M UTC")));
+        text.push_str(&format!("Subject: {}\n", entry.subject));
+        if let Some(parent) = entry.reply_to {
+            text.push_str(&format!("Reply to [{}]\n", parent));
+        }
+        text.push('\n');
+        if entry.sequestered {
+            text.push_str("[Withheld: sequestered document]\n");
+            continue;
+        }
+        if !entry.body.trim().is_empty() {
+            text.push_str(&entry.body);
+            text.push('\n');
+        }
+        if entry.quoted_collapsed {
+            text.push_str(COLLAPSED);
+            text.push('\n');
+        }
+    }
+    text
+}
@@ -0,0 +1,426 @@
+use crate::identity_map::IdentityMap;
+use crate::{rfc5322, EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{btree_map::Entry, BTreeMap, BTreeSet, VecDeque};
+use wasm_bindgen::prelude::*;
+
+/// How central one identity is in the communication graph, where identities
+/// are linked when one emailed the other.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityRank {
+    pub rank: usize,
+    pub identity: String,
+    pub sent: usize,
+    pub received: usize,
+    // Distinct identities they exchanged email with
+    pub degree: usize,
+    // Share of shortest paths between other identities running through them, 0.0-1.0
+    pub betweenness: f64,
+    // Share of their contacts' pairs with no direct link of their own, 0.0-1.0
+    pub broker_score: f64,
+    // Identities they exchanged the most email with, busiest first
+    pub top_contacts: Vec<String>,
+}
+
+pub(crate) struct Graph {
+    // Identity -> (sent, received)
+    pub ids: IndexMap<String, (usize, usize)>,
+    // (lower index, higher index) -> emails between the pair
+    pub weights: BTreeMap<(usize, usize), usize>,
+    pub neighbours: Vec<BTreeSet<usize>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubgraphNode {
+    pub identity: String,
+    // Steps from the center identity, 0 for the center itself
+    pub hops: usize,
+    pub sent: usize,
+    pub received: usize,
+    // Distinct identities they exchanged email with across the whole graph
+    pub degree: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubgraphEdge {
+    pub source: String,
+    pub target: String,
+    pub email_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubgraphThread {
+    pub thread_id: String,
+    pub subject: String,
+    // Emails in the thread sent between two identities of the subgraph
+    pub email_count: usize,
+}
+
+/// The part of the communication graph within a number of hops of one person.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommunicationSubgraph {
+    pub center: String,
+    pub hops: usize,
+    // Nearest first, then by identity
+    pub nodes: Vec<SubgraphNode>,
+    // Every link between two nodes, busiest first
+    pub edges: Vec<SubgraphEdge>,
+    // Threads carrying those links, busiest first
+    pub threads: Vec<SubgraphThread>,
+}
+
+/// Who emailed whom how often.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrafficMatrix {
+    // Busiest first, unless a set of identities was asked for
+    pub identities: Vec<String>,
+    // counts[i][j]: emails identities[i] sent with identities[j] as a
+    // recipient (To, Cc or Bcc)
+    pub counts: Vec<Vec<usize>>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Identities ranked by betweenness, then degree: the people most messages
+    /// and introductions flow through, to find key players early.
+    #[wasm_bindgen]
+    pub fn get_key_players(&self, limit: Option<usize>) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let mut ranks = self.identity_ranks()?;
+        if let Some(limit) = limit {
+            ranks.truncate(limit);
+        }
+        self.to_js(&ranks)
+    }
+
+    /// Sender x recipient message counts. `identities` restricts the matrix
+    /// to those people (addresses or mapped person names), in that order,
+    /// e.g. the key players; by default every identity is included.
+    #[wasm_bindgen]
+    pub fn get_traffic_matrix(&self, identities: Option<Vec<String>>) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let matrix = self.traffic_matrix(identities)?;
+        self.to_js(&matrix)
+    }
+
+    /// Everyone within `hops` links of `identity` (an address, optionally
+    /// with a display name), the links among them and the threads those
+    /// links were made in. One hop gives the person's direct contacts.
+    #[wasm_bindgen]
+    pub fn extract_subgraph(&self, identity: &str, hops: usize) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let subgraph = self.communication_subgraph(identity, hops)?;
+        console_log!(
+            "Extracted {} identities and {} threads around {}",
+            subgraph.nodes.len(),
+            subgraph.threads.len(),
+            subgraph.center
+        );
+        self.to_js(&subgraph)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn identity_ranks(&self) -> Result<Vec<IdentityRank>, JsValue> {
+        let Graph { ids, weights, neighbours } = self.communication_graph();
+        let betweenness = self.betweenness(&neighbours)?;
+
+        let mut ranks: Vec<IdentityRank> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, (identity, &(sent, received)))| {
+                let mut contacts: Vec<(usize, usize)> = neighbours[i]
+                    .iter()
+                    .map(|&n| (n, weights[&(i.min(n), i.max(n))]))
+                    .collect();
+                contacts.sort_by(|x, y| y.1.cmp(&x.1).then_with(|| x.0.cmp(&y.0)));
+                IdentityRank {
+                    rank: 0,
+                    identity: identity.clone(),
+                    sent,
+                    received,
+                    degree: neighbours[i].len(),
+                    betweenness: betweenness[i],
+                    broker_score: broker_score(&neighbours, i),
+                    top_contacts: contacts.iter().take(5).map(|&(n, _)| ids.get_index(n).unwrap().0.clone()).collect(),
+                }
+            })
+            .collect();
+        ranks.sort_by(|x, y| {
+            y.betweenness
+                .total_cmp(&x.betweenness)
+                .then_with(|| y.degree.cmp(&x.degree))
+                .then_with(|| (y.sent + y.received).cmp(&(x.sent + x.received)))
+                .then_with(|| x.identity.cmp(&y.identity))
+        });
+        for (i, rank) in ranks.iter_mut().enumerate() {
+            rank.rank = i + 1;
+        }
+        Ok(ranks)
+    }
+
+    /// Identities (with sent and received counts) linked by how many emails
+    /// passed between each pair, over the in-scope emails less any mass mail
+    /// excluded by set_mass_mail_options.
+    pub(crate) fn communication_graph(&self) -> Graph {
+        let mut ids: IndexMap<String, (usize, usize)> = IndexMap::new();
+        let mut weights: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        for email in self.graph_emails() {
+            let Some((sender, recipients)) = links(email, &self.identity_map) else {
+                continue;
+            };
+            let from = index_of(&mut ids, sender);
+            ids[from].0 += 1;
+            for recipient in recipients {
+                let to = index_of(&mut ids, recipient);
+                ids[to].1 += 1;
+                *weights.entry((from.min(to), from.max(to))).or_default() += 1;
+            }
+        }
+
+        let mut neighbours: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); ids.len()];
+        for &(a, b) in weights.keys() {
+            neighbours[a].insert(b);
+            neighbours[b].insert(a);
+        }
+        Graph { ids, weights, neighbours }
+    }
+
+    fn graph_emails(&self) -> impl Iterator<Item = &EmailMessage> {
+        self.emails.iter().filter(|e| !self.filtered_out(e) && !self.excluded_from_graph(e))
+    }
+
+    // An identity as a caller gives it: a mapped person's name or an address
+    pub(crate) fn lookup_identity(&self, identity: &str) -> Option<String> {
+        match self.identity_map.find_person(identity) {
+            Some(person) => Some(person.to_string()),
+            None => self.identity_map.identities(identity).into_iter().next(),
+        }
+    }
+
+    fn traffic_matrix(&self, identities: Option<Vec<String>>) -> Result<TrafficMatrix, JsValue> {
+        let mut sent: BTreeMap<(String, String), usize> = BTreeMap::new();
+        for email in self.graph_emails() {
+            self.check_cancelled()?;
+            let Some((sender, recipients)) = links(email, &self.identity_map) else {
+                continue;
+            };
+            for recipient in recipients {
+                *sent.entry((sender.clone(), recipient)).or_default() += 1;
+            }
+        }
+
+        let identities: Vec<String> = match identities {
+            Some(requested) => {
+                let mut identities: Vec<String> = Vec::new();
+                for identity in requested.iter().filter_map(|i| self.lookup_identity(i)) {
+                    if !identities.contains(&identity) {
+                        identities.push(identity);
+                    }
+                }
+                identities
+            }
+            None => {
+                let mut totals: IndexMap<&str, usize> = IndexMap::new();
+                for ((from, to), &count) in &sent {
+                    *totals.entry(from).or_default() += count;
+                    *totals.entry(to).or_default() += count;
+                }
+                totals.sort_by(|a, x, b, y| y.cmp(x).then_with(|| a.cmp(b)));
+                totals.keys().map(|i| i.to_string()).collect()
+            }
+        };
+
+        let counts = identities
+            .iter()
+            .map(|from| {
+                identities
+                    .iter()
+                    .map(|to| sent.get(&(from.clone(), to.clone())).copied().unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+        Ok(TrafficMatrix { identities, counts })
+    }
+
+    fn communication_subgraph(&self, identity: &str, hops: usize) -> Result<CommunicationSubgraph, JsValue> {
+        let Some(center) = self.lookup_identity(identity) else {
+            return Err(JsValue::from_str("Invalid identity"));
+        };
+        let Graph { ids, weights, neighbours } = self.communication_graph();
+        let Some(start) = ids.get_index_of(&center) else {
+            return Err(JsValue::from_str("Identity not found"));
+        };
+
+        let mut distance: BTreeMap<usize, usize> = BTreeMap::from([(start, 0)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            let d = distance[&node];
+            if d == hops {
+                continue;
+            }
+            for &next in &neighbours[node] {
+                if let Entry::Vacant(entry) = distance.entry(next) {
+                    entry.insert(d + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let name = |i: usize| ids.get_index(i).unwrap().0.clone();
+        let mut nodes: Vec<SubgraphNode> = distance
+            .iter()
+            .map(|(&i, &d)| SubgraphNode {
+                identity: name(i),
+                hops: d,
+                sent: ids[i].0,
+                received: ids[i].1,
+                degree: neighbours[i].len(),
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.hops.cmp(&b.hops).then_with(|| a.identity.cmp(&b.identity)));
+
+        let mut edges: Vec<SubgraphEdge> = weights
+            .iter()
+            .filter(|((a, b), _)| distance.contains_key(a) && distance.contains_key(b))
+            .map(|(&(a, b), &email_count)| SubgraphEdge {
+                source: name(a),
+                target: name(b),
+                email_count,
+            })
+            .collect();
+        edges.sort_by(|x, y| {
+            y.email_count
+                .cmp(&x.email_count)
+                .then_with(|| x.source.cmp(&y.source))
+                .then_with(|| x.target.cmp(&y.target))
+        });
+
+        let members: BTreeSet<String> = nodes.iter().map(|n| n.identity.clone()).collect();
+        let mut threads: Vec<SubgraphThread> = Vec::new();
+        for (thread_id, emails) in self.visible_threads() {
+            self.check_cancelled()?;
+            let email_count = emails
+                .iter()
+                .filter(|e| !self.excluded_from_graph(e))
+                .filter_map(|e| links(e, &self.identity_map))
+                .filter(|(sender, recipients)| members.contains(sender) && recipients.iter().any(|r| members.contains(r)))
+                .count();
+            if email_count > 0 {
+                threads.push(SubgraphThread {
+                    thread_id: thread_id.clone(),
+                    subject: emails.first().map(|e| e.subject.clone()).unwrap_or_default(),
+                    email_count,
+                });
+            }
+        }
+        threads.sort_by(|a, b| b.email_count.cmp(&a.email_count).then_with(|| a.thread_id.cmp(&b.thread_id)));
+
+        Ok(CommunicationSubgraph {
+            center,
+            hops,
+            nodes,
+            edges,
+            threads,
+        })
+    }
+
+    // Brandes' algorithm on the unweighted graph, normalized by the number of
+    // pairs of other identities
+    fn betweenness(&self, neighbours: &[BTreeSet<usize>]) -> Result<Vec<f64>, JsValue> {
+        let n = neighbours.len();
+        let mut centrality = vec![0.0; n];
+        for source in 0..n {
+            self.check_cancelled()?;
+            let mut stack = Vec::new();
+            let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+            let mut paths = vec![0.0; n];
+            let mut distance: Vec<Option<usize>> = vec![None; n];
+            paths[source] = 1.0;
+            distance[source] = Some(0);
+
+            let mut queue = VecDeque::from([source]);
+            while let Some(v) = queue.pop_front() {
+                stack.push(v);
+                let next = distance[v].unwrap_or_default() + 1;
+                for &w in &neighbours[v] {
+                    if distance[w].is_none() {
+                        distance[w] = Some(next);
+                        queue.push_back(w);
+                    }
+                    if distance[w] == Some(next) {
+                        paths[w] += paths[v];
+                        predecessors[w].push(v);
+                    }
+                }
+            }
+
+            let mut dependency = vec![0.0; n];
+            while let Some(w) = stack.pop() {
+                for &v in &predecessors[w] {
+                    dependency[v] += paths[v] / paths[w] * (1.0 + dependency[w]);
+                }
+                if w != source {
+                    centrality[w] += dependency[w];
+                }
+            }
+            self.emit_progress("centrality", source + 1, Some(n));
+        }
+
+        // Each pair was counted from both ends
+        let pairs = (n.saturating_sub(1) * n.saturating_sub(2)) as f64;
+        if pairs > 0.0 {
+            for c in &mut centrality {
+                *c /= pairs;
+            }
+        }
+        Ok(centrality)
+    }
+}
+
+/// Bare, lowercased addresses in an address field.
+pub(crate) fn identities(field: &str) -> Vec<String> {
+    rfc5322::parse_addresses(field).into_iter().map(|a| a.to_lowercase()).collect()
+}
+
+/// The sender and distinct other recipients (To, Cc, Bcc) of an email, as
+/// identities with mapped addresses resolved to their person; None when it
+/// has no parseable sender.
+pub(crate) fn links(email: &EmailMessage, identity_map: &IdentityMap) -> Option<(String, BTreeSet<String>)> {
+    let sender = identity_map.identities(&email.from).into_iter().next()?;
+    let recipients = email
+        .to
+        .iter()
+        .chain(&email.cc)
+        .chain(&email.bcc)
+        .flat_map(|r| identity_map.identities(r))
+        .filter(|r| *r != sender)
+        .collect();
+    Some((sender, recipients))
+}
+
+fn index_of(ids: &mut IndexMap<String, (usize, usize)>, identity: String) -> usize {
+    match ids.get_index_of(&identity) {
+        Some(i) => i,
+        None => ids.insert_full(identity, (0, 0)).0,
+    }
+}
+
+fn broker_score(neighbours: &[BTreeSet<usize>], node: usize) -> f64 {
+    let contacts: Vec<usize> = neighbours[node].iter().copied().collect();
+    if contacts.len() < 2 {
+        return 0.0;
+    }
+    let mut pairs = 0;
+    let mut open = 0;
+    for (i, &a) in contacts.iter().enumerate() {
+        for &b in &contacts[i + 1..] {
+            pairs += 1;
+            if !neighbours[a].contains(&b) {
+                open += 1;
+            }
+        }
+    }
+    open as f64 / pairs as f64
+}
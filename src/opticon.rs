@@ -0,0 +1,108 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Attaches page images from an Opticon (.opt) cross-reference to the loaded
+    /// documents. Returns the number of documents that received images.
+    #[wasm_bindgen]
+    pub fn load_opticon(&mut self, opt_data: &str) -> Result<usize, JsValue> {
+        console_log!("Loading Opticon image cross-reference, length: {}", opt_data.len());
+
+        if self.emails.is_empty() {
+            return Err(JsValue::from_str("No emails loaded; load documents before their images"));
+        }
+
+        let documents = parse_opticon(opt_data).map_err(|e| JsValue::from_str(&e))?;
+        let matched = apply_images(&mut self.emails, &documents);
+        self.refresh_thread_copies();
+        self.audit_load(
+            "load_opticon",
+            format!("Images attached to {} of {} image documents", matched, documents.len()),
+            &[("Opticon", opt_data.as_bytes())],
+        );
+
+        console_log!("Matched images for {} of {} image documents", matched, documents.len());
+        Ok(matched)
+    }
+}
+
+pub(crate) struct ImageDocument {
+    pub first_page: String,
+    pub pages: Vec<String>,
+}
+
+// ImageKey,VolumeLabel,ImagePath,DocBreak,FolderBreak,BoxBreak,PageCount
+pub(crate) fn parse_opticon(opt_data: &str) -> Result<Vec<ImageDocument>, String> {
+    let mut documents: Vec<ImageDocument> = Vec::new();
+
+    for (line_no, line) in opt_data.lines().enumerate() {
+        let line = line.trim_start_matches('\u{feff}').trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        if fields.len() < 4 {
+            return Err(format!("Invalid Opticon line {}: expected at least 4 fields", line_no + 1));
+        }
+
+        let image_key = fields[0];
+        let image_path = fields[2];
+        let doc_break = fields[3].eq_ignore_ascii_case("Y");
+
+        match documents.last_mut() {
+            Some(doc) if !doc_break => doc.pages.push(image_path.to_string()),
+            _ => documents.push(ImageDocument {
+                first_page: image_key.to_string(),
+                pages: vec![image_path.to_string()],
+            }),
+        }
+    }
+
+    Ok(documents)
+}
+
+fn apply_images(emails: &mut [EmailMessage], documents: &[ImageDocument]) -> usize {
+    let by_bates: HashMap<String, usize> = emails
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.beg_bates.clone(), i))
+        .collect();
+
+    let mut matched = 0;
+    for doc in documents {
+        let idx = by_bates.get(&doc.first_page).copied().or_else(|| {
+            emails
+                .iter()
+                .position(|e| bates_in_range(&doc.first_page, &e.beg_bates, &e.end_bates))
+        });
+
+        match idx {
+            Some(i) => {
+                emails[i].image_paths = doc.pages.clone();
+                emails[i].page_count = doc.pages.len();
+                matched += 1;
+            }
+            None => console_log!("No loaded document for image key {}", doc.first_page),
+        }
+    }
+
+    matched
+}
+
+pub(crate) fn split_bates(bates: &str) -> Option<(&str, u64)> {
+    let digits = bates.len() - bates.chars().rev().take_while(|c| c.is_ascii_digit()).count();
+    let number = bates[digits..].parse().ok()?;
+    Some((&bates[..digits], number))
+}
+
+pub(crate) fn bates_in_range(bates: &str, beg: &str, end: &str) -> bool {
+    match (split_bates(bates), split_bates(beg), split_bates(end)) {
+        (Some((prefix, n)), Some((beg_prefix, lo)), Some((end_prefix, hi))) => {
+            prefix == beg_prefix && prefix == end_prefix && n >= lo && n <= hi
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{inclusive, EmailThreadProcessor, ThreadNode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+const OVERLAY_HEADERS: &[&str] = &[
+    "BegBates",
+    "EndBates",
+    "ThreadId",
+    "ThreadSortOrder",
+    "ThreadDepth",
+    "ParentBates",
+    "RootBates",
+    "IsInclusive",
+    "ThreadLabels",
+];
+
+/// One row per email of the thread overlay review platforms import for
+/// thread-based sorting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayRow {
+    pub email_id: String,
+    pub beg_bates: String,
+    pub end_bates: String,
+    pub thread_id: String,
+    // 1-based position in a depth-first walk of the thread tree
+    pub thread_sort_order: Option<usize>,
+    pub thread_depth: Option<usize>,
+    pub parent_bates: String,
+    pub root_bates: String,
+    // False when a later email in the same branch quotes all of its content
+    pub is_inclusive: bool,
+    pub thread_labels: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    #[wasm_bindgen]
+    pub fn get_overlay_rows(&self) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let rows = self.overlay_rows()?;
+        self.to_js(&rows)
+    }
+
+    /// Overlay load file (CSV) keyed by BegBates with the computed threading
+    /// columns: ThreadSortOrder, ThreadDepth, ParentBates, RootBates,
+    /// IsInclusive and ThreadLabels.
+    #[wasm_bindgen]
+    pub fn export_overlay_csv(&self) -> Result<String, JsValue> {
+        self.reset_cancellation();
+        let rows = self.overlay_rows()?;
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let to_js = |e: csv::Error| JsValue::from_str(&format!("Error writing overlay: {}", e));
+
+        writer.write_record(OVERLAY_HEADERS).map_err(to_js)?;
+        for row in &rows {
+            writer
+                .write_record([
+                    row.beg_bates.as_str(),
+                    row.end_bates.as_str(),
+                    row.thread_id.as_str(),
+                    &row.thread_sort_order.map(|o| o.to_string()).unwrap_or_default(),
+                    &row.thread_depth.map(|d| d.to_string()).unwrap_or_default(),
+                    row.parent_bates.as_str(),
+                    row.root_bates.as_str(),
+                    if row.is_inclusive { "Y" } else { "N" },
+                    &row.thread_labels.join("; "),
+                ])
+                .map_err(to_js)?;
+        }
+
+        let data = writer.into_inner().map_err(|e| JsValue::from_str(&e.to_string()))?;
+        String::from_utf8(data).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn overlay_rows(&self) -> Result<Vec<OverlayRow>, JsValue> {
+        let mut placed: HashMap<String, OverlayRow> = HashMap::new();
+
+        for (i, thread_id) in self.threads.keys().enumerate() {
+            self.check_cancelled()?;
+            let tree = self.thread_tree(thread_id)?;
+            let labels = self.get_thread_labels(thread_id);
+            let mut order = 0;
+            for root in &tree.roots {
+                walk(root, None, root, &mut order, &mut |node, parent, root, order| {
+                    placed.insert(
+                        node.email.id.clone(),
+                        OverlayRow {
+                            email_id: node.email.id.clone(),
+                            beg_bates: node.email.beg_bates.clone(),
+                            end_bates: node.email.end_bates.clone(),
+                            thread_id: thread_id.clone(),
+                            thread_sort_order: Some(order),
+                            thread_depth: Some(node.depth),
+                            parent_bates: parent.map(|p| p.email.beg_bates.clone()).unwrap_or_default(),
+                            root_bates: root.email.beg_bates.clone(),
+                            is_inclusive: is_inclusive(node),
+                            thread_labels: labels.clone(),
+                        },
+                    );
+                });
+            }
+            self.emit_progress("overlay", i + 1, Some(self.threads.len()));
+        }
+
+        // Load order, with unthreaded emails as their own one-email threads
+        Ok(self
+            .emails
+            .iter()
+            .filter(|e| !self.filtered_out(e))
+            .map(|email| {
+                placed.remove(&email.id).unwrap_or_else(|| OverlayRow {
+                    email_id: email.id.clone(),
+                    beg_bates: email.beg_bates.clone(),
+                    end_bates: email.end_bates.clone(),
+                    thread_id: String::new(),
+                    thread_sort_order: None,
+                    thread_depth: None,
+                    parent_bates: String::new(),
+                    root_bates: email.beg_bates.clone(),
+                    is_inclusive: true,
+                    thread_labels: Vec::new(),
+                })
+            })
+            .collect())
+    }
+}
+
+fn walk<'a>(
+    node: &'a ThreadNode,
+    parent: Option<&'a ThreadNode>,
+    root: &'a ThreadNode,
+    order: &mut usize,
+    visit: &mut impl FnMut(&'a ThreadNode, Option<&'a ThreadNode>, &'a ThreadNode, usize),
+) {
+    *order += 1;
+    visit(node, parent, root, *order);
+    for child in &node.children {
+        walk(child, Some(node), root, order, visit);
+    }
+}
+
+// An email is inclusive unless some reply or forward below it carries its
+// full content, in which case reviewing the later message covers it
+pub(crate) fn is_inclusive(node: &ThreadNode) -> bool {
+    let mut stack: Vec<&ThreadNode> = node.children.iter().collect();
+    while let Some(descendant) = stack.pop() {
+        if inclusive::is_contained(&node.email.full_text, &descendant.email.full_text) {
+            return false;
+        }
+        stack.extend(descendant.children.iter());
+    }
+    true
+}
@@ -0,0 +1,85 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Which slice of a listing to return. `cursor` is the `next_cursor` of the
+/// previous page (the id of its last item) and takes precedence over
+/// `offset`; without `limit` everything from the start position is returned.
+/// Listings keep a stable order: threads as grouped, emails and search hits
+/// in load order.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageRequest {
+    pub offset: usize,
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    // Size of the full listing
+    pub total: usize,
+    pub offset: usize,
+    // None on the last page
+    pub next_cursor: Option<String>,
+}
+
+impl PageRequest {
+    pub(crate) fn parse(page: JsValue) -> Result<PageRequest, JsValue> {
+        if page.is_undefined() || page.is_null() {
+            Ok(PageRequest::default())
+        } else {
+            serde_wasm_bindgen::from_value(page).map_err(|e| JsValue::from_str(&format!("Invalid page request: {}", e)))
+        }
+    }
+
+    /// Cuts the page out of the full listing. Pass cheap items (references or
+    /// indices) and map them to their output form afterwards.
+    pub(crate) fn apply<'a, T>(&self, mut items: Vec<T>, id_of: impl Fn(&T) -> &'a str) -> Result<Page<T>, JsValue> {
+        let total = items.len();
+        let start = match &self.cursor {
+            Some(cursor) => {
+                items
+                    .iter()
+                    .position(|item| id_of(item) == cursor)
+                    .ok_or_else(|| JsValue::from_str(&format!("Unknown cursor: {}", cursor)))?
+                    + 1
+            }
+            None => self.offset.min(total),
+        };
+        let end = self.limit.map_or(total, |limit| start.saturating_add(limit).min(total));
+        let next_cursor = (end < total && end > start).then(|| id_of(&items[end - 1]).to_string());
+        items.truncate(end);
+        items.drain(..start);
+        Ok(Page {
+            items,
+            total,
+            offset: start,
+            next_cursor,
+        })
+    }
+}
+
+impl<T> Page<T> {
+    pub(crate) fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            offset: self.offset,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Loaded emails in load order, a page at a time; see `PageRequest`.
+    /// Emails the type filter excludes are not listed.
+    #[wasm_bindgen]
+    pub fn get_emails(&self, page: JsValue) -> Result<JsValue, JsValue> {
+        let emails: Vec<&EmailMessage> = self.included_emails().collect();
+        let page = PageRequest::parse(page)?.apply(emails, |&e| e.id.as_str())?;
+        self.to_js(&page)
+    }
+}
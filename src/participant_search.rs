@@ -0,0 +1,212 @@
+use crate::EmailThreadProcessor;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+const DEFAULT_LIMIT: usize = 20;
+// Candidates scoring below this are not returned
+const MIN_SCORE: f64 = 0.6;
+// Scores for a query word that starts a name word ("jon" -> "jonathan") and
+// for one that only sounds alike
+const PREFIX_SCORE: f64 = 0.9;
+const PHONETIC_SCORE: f64 = 0.8;
+
+/// A person or address that may be who the analyst is looking for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParticipantMatch {
+    // The person per the identity map, else the bare address
+    pub identity: String,
+    pub person: Option<String>,
+    pub addresses: Vec<String>,
+    pub display_names: Vec<String>,
+    // Summed over its addresses
+    pub message_count: usize,
+    // 0..1, 1 for an exact match
+    pub score: f64,
+    // The name or address that scored best
+    pub matched_on: String,
+    // "exact", "prefix", "fuzzy" or "phonetic"
+    pub match_kind: String,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Participants whose names, display names or addresses resemble `query`
+    /// despite typos and variants: "jon smth" finds John Smith and
+    /// jsmith@acme.com. Each query word is matched to its closest name word by
+    /// edit distance, prefix or Soundex code, and the scores averaged. Mapped
+    /// addresses come back as their person. Best `limit` (default 20) first.
+    #[wasm_bindgen]
+    pub fn find_participants(&self, query: &str, limit: Option<usize>) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let words = words(query);
+        if words.is_empty() {
+            return Err(JsValue::from_str("Nothing to search for in the query"));
+        }
+        let compact: String = words.concat();
+
+        let mut candidates: IndexMap<String, ParticipantMatch> = IndexMap::new();
+        for entry in self.address_book()? {
+            let identity = entry.person.clone().unwrap_or_else(|| entry.identity.clone());
+            let candidate = candidates.entry(identity.clone()).or_insert_with(|| ParticipantMatch {
+                identity,
+                person: entry.person.clone(),
+                addresses: Vec::new(),
+                display_names: Vec::new(),
+                message_count: 0,
+                score: 0.0,
+                matched_on: String::new(),
+                match_kind: String::new(),
+            });
+            candidate.addresses.push(entry.identity.clone());
+            candidate.message_count += entry.message_count;
+            for name in entry.display_names {
+                if !candidate.display_names.contains(&name) {
+                    candidate.display_names.push(name);
+                }
+            }
+        }
+
+        let mut matches: Vec<ParticipantMatch> = candidates
+            .into_values()
+            .filter_map(|mut candidate| {
+                let local_parts = candidate.addresses.iter().map(|a| a.split('@').next().unwrap_or(a).to_string());
+                let texts: Vec<String> = candidate
+                    .person
+                    .iter()
+                    .chain(&candidate.display_names)
+                    .cloned()
+                    .chain(local_parts)
+                    .collect();
+                let (score, kind, text) = texts
+                    .iter()
+                    .map(|text| {
+                        let (score, kind) = score_text(&words, &compact, text);
+                        (score, kind, text)
+                    })
+                    .max_by(|a, b| a.0.total_cmp(&b.0))?;
+                if score < MIN_SCORE {
+                    return None;
+                }
+                candidate.score = (score * 1000.0).round() / 1000.0;
+                candidate.match_kind = kind.to_string();
+                candidate.matched_on = text.clone();
+                Some(candidate)
+            })
+            .collect();
+        matches.sort_by(|a, b| {
+            b.score.total_cmp(&a.score).then(b.message_count.cmp(&a.message_count)).then(a.identity.cmp(&b.identity))
+        });
+        matches.truncate(limit.unwrap_or(DEFAULT_LIMIT));
+
+        console_log!("Found {} participants like {:?}", matches.len(), query);
+        self.to_js(&matches)
+    }
+}
+
+// Lowercased runs of letters and digits; "john.smith" is two words
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase).collect()
+}
+
+// How well the query words match one name or address local part, and the
+// weakest kind of match it took. The whole query run together is also tried
+// against the text run together, for "jsmth" against "jsmith".
+fn score_text(query: &[String], compact: &str, text: &str) -> (f64, &'static str) {
+    let targets = words(text);
+    if targets.is_empty() {
+        return (0.0, "fuzzy");
+    }
+    let mut total = 0.0;
+    let mut weakest = "exact";
+    for word in query {
+        let (score, kind) = targets
+            .iter()
+            .map(|target| score_word(word, target))
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap_or((0.0, "fuzzy"));
+        total += score;
+        if rank(kind) > rank(weakest) {
+            weakest = kind;
+        }
+    }
+    let by_words = (total / query.len() as f64, weakest);
+    let joined = score_word(compact, &targets.concat());
+    if joined.0 > by_words.0 {
+        joined
+    } else {
+        by_words
+    }
+}
+
+fn score_word(word: &str, target: &str) -> (f64, &'static str) {
+    if word == target {
+        return (1.0, "exact");
+    }
+    let distance = levenshtein(word, target);
+    let longest = word.chars().count().max(target.chars().count());
+    let fuzzy = 1.0 - distance as f64 / longest as f64;
+    let mut best = (fuzzy, "fuzzy");
+    if word.chars().count() >= 2 && target.starts_with(word) && PREFIX_SCORE > best.0 {
+        best = (PREFIX_SCORE, "prefix");
+    }
+    if PHONETIC_SCORE > best.0 && soundex(word).is_some_and(|code| soundex(target) == Some(code)) {
+        best = (PHONETIC_SCORE, "phonetic");
+    }
+    best
+}
+
+fn rank(kind: &str) -> usize {
+    match kind {
+        "exact" => 0,
+        "prefix" => 1,
+        "phonetic" => 2,
+        _ => 3,
+    }
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous + usize::from(ca != *cb);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// American Soundex; None for words that do not start with a Latin letter
+fn soundex(word: &str) -> Option<String> {
+    let code = |c: char| match c {
+        'b' | 'f' | 'p' | 'v' => Some('1'),
+        'c' | 'g' | 'j' | 'k' | 'q' | 's' | 'x' | 'z' => Some('2'),
+        'd' | 't' => Some('3'),
+        'l' => Some('4'),
+        'm' | 'n' => Some('5'),
+        'r' => Some('6'),
+        _ => None,
+    };
+    let mut letters = word.chars().filter(|c| c.is_ascii_alphabetic());
+    let first = letters.next()?;
+    let mut result = first.to_ascii_uppercase().to_string();
+    let mut last = code(first);
+    for c in letters {
+        let current = code(c);
+        if current.is_some() && current != last {
+            result.extend(current);
+            if result.len() == 4 {
+                break;
+            }
+        }
+        // H and W do not separate letters with the same code; vowels do
+        if c != 'h' && c != 'w' {
+            last = current;
+        }
+    }
+    Some(format!("{:0<4}", result))
+}
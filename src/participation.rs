@@ -0,0 +1,63 @@
+use crate::identity_map::IdentityMap;
+use crate::{network, EmailMessage};
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// When a participant was first and last on a thread and who brought them in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParticipantSpan {
+    pub participant: String,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub joined: DateTime<Utc>,
+    pub joined_email_id: String,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub left: DateTime<Utc>,
+    pub left_email_id: String,
+    pub message_count: usize,
+    // Sender of the first email they were on; None when they started there
+    // themselves
+    pub added_by: Option<String>,
+}
+
+/// Participant spans in order of joining, for emails already sorted by date.
+/// Addresses on From, To and Cc are matched case-insensitively, and mapped
+/// addresses are merged under their person's name.
+pub(crate) fn participant_timeline(emails: &[EmailMessage], identity_map: &IdentityMap) -> Vec<ParticipantSpan> {
+    let mut spans: IndexMap<String, ParticipantSpan> = IndexMap::new();
+    let name = |field: &str| -> String {
+        let address = network::identities(field).into_iter().next();
+        match address.as_deref().and_then(|a| identity_map.person_of(a)) {
+            Some(person) => person.to_string(),
+            None => field.trim().to_string(),
+        }
+    };
+
+    for email in emails {
+        let sender = name(&email.from);
+        let mut seen_here = Vec::new();
+        for address in std::iter::once(&email.from).chain(&email.to).chain(&email.cc) {
+            let participant = name(address);
+            let key = participant.to_lowercase();
+            if key.is_empty() || seen_here.contains(&key) {
+                continue;
+            }
+            seen_here.push(key.clone());
+
+            let span = spans.entry(key).or_insert_with(|| ParticipantSpan {
+                participant: participant.clone(),
+                joined: email.date_sent,
+                joined_email_id: email.id.clone(),
+                left: email.date_sent,
+                left_email_id: email.id.clone(),
+                message_count: 0,
+                added_by: (!sender.is_empty() && !sender.eq_ignore_ascii_case(&participant)).then(|| sender.clone()),
+            });
+            span.left = email.date_sent;
+            span.left_email_id = email.id.clone();
+            span.message_count += 1;
+        }
+    }
+
+    spans.into_values().collect()
+}
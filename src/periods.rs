@@ -0,0 +1,147 @@
+use crate::{custodians, DateRange, EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PeriodCounts {
+    pub email_count: usize,
+    pub thread_count: usize,
+    pub participant_count: usize,
+    pub external_count: usize,
+}
+
+/// Counts for one custodian, thread or the whole corpus in each window, with
+/// the change from A to B.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodDelta {
+    pub key: String,
+    pub a: PeriodCounts,
+    pub b: PeriodCounts,
+    pub email_change: i64,
+    pub participant_change: i64,
+    pub external_change: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodComparison {
+    pub range_a: DateRange,
+    pub range_b: DateRange,
+    pub overall: PeriodDelta,
+    // Addresses seen in B but not A, and in A but not B
+    pub new_participants: Vec<String>,
+    pub dropped_participants: Vec<String>,
+    // Largest change in email volume first
+    pub custodians: Vec<PeriodDelta>,
+    pub threads: Vec<PeriodDelta>,
+}
+
+#[derive(Default)]
+struct Tally {
+    email_count: usize,
+    threads: BTreeSet<String>,
+    participants: BTreeSet<String>,
+    external_count: usize,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Email volume, participants and external traffic in two time windows
+    /// (`{ start, end }`, RFC 3339, inclusive), overall, per custodian and per
+    /// thread, e.g. before and after a litigation hold. Windows may overlap.
+    #[wasm_bindgen]
+    pub fn compare_periods(&self, range_a: JsValue, range_b: JsValue) -> Result<JsValue, JsValue> {
+        let range_a: DateRange = serde_wasm_bindgen::from_value(range_a)?;
+        let range_b: DateRange = serde_wasm_bindgen::from_value(range_b)?;
+        for range in [&range_a, &range_b] {
+            if range.start > range.end {
+                return Err(JsValue::from_str("Period start is after its end"));
+            }
+        }
+        let comparison = self.period_comparison(range_a, range_b);
+        self.to_js(&comparison)
+    }
+}
+
+impl EmailThreadProcessor {
+    fn period_comparison(&self, range_a: DateRange, range_b: DateRange) -> PeriodComparison {
+        let mut overall = [Tally::default(), Tally::default()];
+        let mut by_custodian: IndexMap<String, [Tally; 2]> = IndexMap::new();
+        let mut by_thread: IndexMap<String, [Tally; 2]> = IndexMap::new();
+
+        for email in self.included_emails() {
+            let thread_id = self.thread_key(email);
+            let participants: BTreeSet<String> = std::iter::once(&email.from)
+                .chain(&email.to)
+                .chain(&email.cc)
+                .flat_map(|address| self.identity_map.identities(address))
+                .collect();
+            for (period, range) in [&range_a, &range_b].into_iter().enumerate() {
+                if email.date_sent < range.start || email.date_sent > range.end {
+                    continue;
+                }
+                overall[period].add(email, thread_id.as_deref(), &participants);
+                for custodian in custodians::custodians_of(email) {
+                    by_custodian.entry(custodian.to_string()).or_default()[period].add(email, thread_id.as_deref(), &participants);
+                }
+                if let Some(thread_id) = &thread_id {
+                    by_thread.entry(thread_id.clone()).or_default()[period].add(email, Some(thread_id), &participants);
+                }
+            }
+        }
+
+        let [a, b] = &overall;
+        let new_participants = b.participants.difference(&a.participants).cloned().collect();
+        let dropped_participants = a.participants.difference(&b.participants).cloned().collect();
+        PeriodComparison {
+            overall: delta("all".to_string(), &overall),
+            new_participants,
+            dropped_participants,
+            custodians: deltas(by_custodian),
+            threads: deltas(by_thread),
+            range_a,
+            range_b,
+        }
+    }
+}
+
+impl Tally {
+    fn add(&mut self, email: &EmailMessage, thread_id: Option<&str>, participants: &BTreeSet<String>) {
+        self.email_count += 1;
+        if let Some(thread_id) = thread_id {
+            self.threads.insert(thread_id.to_string());
+        }
+        self.participants.extend(participants.iter().cloned());
+        if email.is_external {
+            self.external_count += 1;
+        }
+    }
+
+    fn counts(&self) -> PeriodCounts {
+        PeriodCounts {
+            email_count: self.email_count,
+            thread_count: self.threads.len(),
+            participant_count: self.participants.len(),
+            external_count: self.external_count,
+        }
+    }
+}
+
+fn delta(key: String, tallies: &[Tally; 2]) -> PeriodDelta {
+    let (a, b) = (tallies[0].counts(), tallies[1].counts());
+    PeriodDelta {
+        key,
+        email_change: b.email_count as i64 - a.email_count as i64,
+        participant_change: b.participant_count as i64 - a.participant_count as i64,
+        external_change: b.external_count as i64 - a.external_count as i64,
+        a,
+        b,
+    }
+}
+
+fn deltas(tallies: IndexMap<String, [Tally; 2]>) -> Vec<PeriodDelta> {
+    let mut deltas: Vec<PeriodDelta> = tallies.into_iter().map(|(key, t)| delta(key, &t)).collect();
+    deltas.sort_by(|x, y| y.email_change.abs().cmp(&x.email_change.abs()).then_with(|| x.key.cmp(&y.key)));
+    deltas
+}
@@ -0,0 +1,147 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TimelineRange {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+/// One email in a person's timeline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineEntry {
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub date_sent: DateTime<Utc>,
+    pub email_id: String,
+    pub bates: String,
+    // "sent" or "received"
+    pub direction: String,
+    // "to", "cc" or "bcc" for received email
+    pub received_as: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub subject: String,
+    // Thread context; None for an email no thread holds
+    pub thread_id: Option<String>,
+    pub thread_subject: Option<String>,
+    // 1-based, chronological within the thread
+    pub thread_position: Option<usize>,
+    pub thread_email_count: Option<usize>,
+    // The email this one replies to
+    pub reply_to_email_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonTimeline {
+    pub identity: String,
+    pub sent_count: usize,
+    pub received_count: usize,
+    pub thread_count: usize,
+    // Oldest first
+    pub entries: Vec<TimelineEntry>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Every in-scope email `identity` (an address or mapped person) sent or
+    /// received, oldest first, each with its direction and the thread it sits
+    /// in, for an individual custodian's chronology. `date_range` takes
+    /// optional `start`/`end` (RFC 3339).
+    #[wasm_bindgen]
+    pub fn get_person_timeline(&self, identity: &str, date_range: JsValue) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let range: TimelineRange = if date_range.is_undefined() || date_range.is_null() {
+            TimelineRange::default()
+        } else {
+            serde_wasm_bindgen::from_value(date_range)?
+        };
+        let timeline = self.person_timeline(identity, &range)?;
+        console_log!("Timeline for {} has {} emails", timeline.identity, timeline.entries.len());
+        self.to_js(&timeline)
+    }
+}
+
+// Parent links and chronological positions of one thread's emails
+struct ThreadContext {
+    subject: String,
+    email_count: usize,
+    positions: HashMap<String, usize>,
+    parents: HashMap<String, String>,
+}
+
+impl EmailThreadProcessor {
+    fn person_timeline(&self, identity: &str, range: &TimelineRange) -> Result<PersonTimeline, JsValue> {
+        let person = self.lookup_identity(identity).ok_or_else(|| JsValue::from_str("Invalid identity"))?;
+        let is_person = |field: &String| self.identity_map.identities(field).contains(&person);
+
+        let mut contexts: HashMap<String, ThreadContext> = HashMap::new();
+        let mut entries = Vec::new();
+        let in_range = |email: &EmailMessage| {
+            range.start.is_none_or(|start| email.date_sent >= start)
+                && range.end.is_none_or(|end| email.date_sent <= end)
+        };
+        for email in self.included_emails().filter(|e| in_range(e)) {
+            let received_as = if email.to.iter().any(is_person) {
+                Some("to")
+            } else if email.cc.iter().any(is_person) {
+                Some("cc")
+            } else if email.bcc.iter().any(is_person) {
+                Some("bcc")
+            } else {
+                None
+            };
+            let direction = match (is_person(&email.from), received_as) {
+                (true, _) => "sent",
+                (false, Some(_)) => "received",
+                (false, None) => continue,
+            };
+            self.check_cancelled()?;
+
+            let thread_id = self.thread_key(email).filter(|key| self.threads.contains_key(key));
+            let context = thread_id.as_ref().map(|key| {
+                &*contexts.entry(key.clone()).or_insert_with(|| self.thread_context(&self.threads[key]))
+            });
+            entries.push(TimelineEntry {
+                date_sent: email.date_sent,
+                email_id: email.id.clone(),
+                bates: email.beg_bates.clone(),
+                direction: direction.to_string(),
+                received_as: received_as.filter(|_| direction == "received").map(str::to_string),
+                from: email.from.clone(),
+                to: email.to.clone(),
+                cc: email.cc.clone(),
+                subject: email.subject.clone(),
+                thread_subject: context.map(|c| c.subject.clone()),
+                thread_position: context.and_then(|c| c.positions.get(&email.id).copied()),
+                thread_email_count: context.map(|c| c.email_count),
+                reply_to_email_id: context.and_then(|c| c.parents.get(&email.id).cloned()),
+                thread_id,
+            });
+        }
+        entries.sort_by(|a, b| a.date_sent.cmp(&b.date_sent).then_with(|| a.bates.cmp(&b.bates)));
+
+        Ok(PersonTimeline {
+            identity: person,
+            sent_count: entries.iter().filter(|e| e.direction == "sent").count(),
+            received_count: entries.iter().filter(|e| e.direction == "received").count(),
+            thread_count: contexts.len(),
+            entries,
+        })
+    }
+
+    fn thread_context(&self, emails: &[EmailMessage]) -> ThreadContext {
+        let mut ordered: Vec<&EmailMessage> = emails.iter().collect();
+        ordered.sort_by(|a, b| a.date_sent.cmp(&b.date_sent).then_with(|| a.id.cmp(&b.id)));
+        ThreadContext {
+            subject: ordered.first().map(|e| e.subject.clone()).unwrap_or_default(),
+            email_count: emails.len(),
+            positions: ordered.iter().enumerate().map(|(i, e)| (e.id.clone(), i + 1)).collect(),
+            parents: self.resolve_parents(emails),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{custodians, DateRange, EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+// Load file columns a vendor may already carry the production volume in
+const PRODUCTION_COLUMNS: &[&str] = &["Production", "ProductionVolume", "Volume", "ProdVol"];
+
+/// One production volume within the corpus.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductionStats {
+    // Empty for emails loaded without a production label
+    pub production: String,
+    pub email_count: usize,
+    pub thread_count: usize,
+    pub custodians: Vec<String>,
+    pub date_range: Option<DateRange>,
+    // Lowest and highest BegBates, compared as text
+    pub first_bates: String,
+    pub last_bates: String,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Labels the emails of every following load with `label` (e.g. "VOL003")
+    /// unless the load file has its own Production or Volume column. With
+    /// `append`, those loads add to the corpus rather than replacing it, so
+    /// rolling productions can be analyzed together; an email whose source
+    /// id (DocID, BegBates, file name) is already loaded is replaced by the
+    /// new copy, while loaders that number emails by position (mbox, Maildir)
+    /// carry on from the numbers already used. Threads must be regrouped
+    /// after an appending load. `label` null stops labelling.
+    #[wasm_bindgen]
+    pub fn set_production(&mut self, label: Option<String>, append: bool) {
+        self.production = label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+        self.append_loads = append;
+        let mode = if append { "append" } else { "replace" };
+        self.audit("set_production", format!("{} ({})", self.production.as_deref().unwrap_or("none"), mode));
+    }
+
+    #[wasm_bindgen]
+    pub fn get_production(&self) -> Option<String> {
+        self.production.clone()
+    }
+
+    /// Per-production counts over the emails in scope, in load order.
+    #[wasm_bindgen]
+    pub fn get_production_stats(&self) -> Result<JsValue, JsValue> {
+        let mut emails: IndexMap<&str, Vec<&EmailMessage>> = IndexMap::new();
+        for email in self.included_emails() {
+            emails.entry(email.production.as_str()).or_default().push(email);
+        }
+
+        let stats: Vec<ProductionStats> = emails
+            .into_iter()
+            .map(|(production, emails)| {
+                let mut custodians: Vec<String> = Vec::new();
+                for custodian in emails.iter().flat_map(|e| custodians::custodians_of(e)) {
+                    if !custodians.iter().any(|c| c == custodian) {
+                        custodians.push(custodian.to_string());
+                    }
+                }
+                let threads: HashSet<String> = emails.iter().filter_map(|e| self.thread_key(e)).collect();
+                let start = emails.iter().map(|e| e.date_sent).min();
+                let end = emails.iter().map(|e| e.date_sent).max();
+                let bates = || emails.iter().map(|e| e.beg_bates.as_str()).filter(|b| !b.is_empty());
+                ProductionStats {
+                    production: production.to_string(),
+                    email_count: emails.len(),
+                    thread_count: threads.len(),
+                    custodians,
+                    date_range: start.zip(end).map(|(start, end)| DateRange { start, end }),
+                    first_bates: bates().min().unwrap_or_default().to_string(),
+                    last_bates: bates().max().unwrap_or_default().to_string(),
+                }
+            })
+            .collect();
+        self.to_js(&stats)
+    }
+}
+
+impl EmailThreadProcessor {
+    /// Makes a finished load the corpus: labels its emails with their
+    /// production, then replaces the loaded emails or, per `set_production`,
+    /// adds to them.
+    /// The number to count on from for ids a loader gives by position, e.g.
+    /// "MBOX000001": the highest already loaded when appending, so a second
+    /// mbox production adds to the first instead of replacing it by id.
+    pub(crate) fn positional_id_base(&self, prefix: &str) -> usize {
+        if !self.append_loads {
+            return 0;
+        }
+        self.emails
+            .iter()
+            .filter_map(|e| e.id.strip_prefix(prefix)?.parse::<usize>().ok())
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn store_loaded(&mut self, mut emails: Vec<EmailMessage>) {
+        for email in &mut emails {
+            if email.production.is_empty() {
+                let column = email
+                    .extra
+                    .iter()
+                    .find(|(column, value)| is_production_column(column) && !value.trim().is_empty())
+                    .map(|(_, value)| value.trim().to_string());
+                email.production = column.or_else(|| self.production.clone()).unwrap_or_default();
+            }
+        }
+
+        if self.append_loads {
+            let incoming: HashSet<&str> = emails.iter().map(|e| e.id.as_str()).collect();
+            let mut merged: Vec<EmailMessage> =
+                self.emails.iter().filter(|e| !incoming.contains(e.id.as_str())).cloned().collect();
+            merged.append(&mut emails);
+            self.replace_emails(merged);
+        } else {
+            self.replace_emails(emails);
+        }
+    }
+}
+
+fn is_production_column(column: &str) -> bool {
+    PRODUCTION_COLUMNS.iter().any(|c| c.eq_ignore_ascii_case(column.trim()))
+}
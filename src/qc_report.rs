@@ -0,0 +1,234 @@
+use crate::opticon::split_bates;
+use crate::{EmailMessage, EmailThreadProcessor, LoadReport};
+use chrono::{TimeZone, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+// Earliest plausible sent date; anything before is a processing default or
+// a bad parse
+const EARLIEST_YEAR: i32 = 1990;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QcFinding {
+    pub kind: String,
+    pub email_ids: Vec<String>,
+    pub message: String,
+}
+
+/// Everything the processing QC checklist asks about, in one place.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QcReport {
+    pub emails_checked: usize,
+    // The last load, with its row errors and repairs
+    pub load: LoadReport,
+    // "before_1990" or "future_date"
+    pub date_anomalies: Vec<QcFinding>,
+    // "missing_bates", "duplicate_bates", "reversed_range", "prefix_mismatch"
+    // or "overlapping_range"
+    pub bates_issues: Vec<QcFinding>,
+    // "duplicate_message_id", one per Message-ID
+    pub duplicate_message_ids: Vec<QcFinding>,
+    // "empty_body"
+    pub empty_bodies: Vec<QcFinding>,
+    // "unthreaded", "missing_parent" or a thread integrity violation kind
+    pub threading_warnings: Vec<QcFinding>,
+    // Findings by kind, across every section
+    pub finding_counts: IndexMap<String, usize>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Corpus health report for processing QC: the last load's errors and
+    /// repairs, emails dated before 1990 or in the future, missing, duplicate
+    /// and inconsistent Bates numbers, Message-IDs shared by several
+    /// documents, documents without extracted text, and threading warnings
+    /// (unthreaded emails, replies to messages not loaded, failed integrity
+    /// checks). Covers every loaded email regardless of filters.
+    #[wasm_bindgen]
+    pub fn generate_qc_report(&self) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let report = self.qc_report()?;
+        let findings: usize = report.finding_counts.values().sum();
+        console_log!("QC report: {} findings over {} emails", findings, report.emails_checked);
+        self.to_js(&report)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn qc_report(&self) -> Result<QcReport, JsValue> {
+        let mut report = QcReport {
+            emails_checked: self.emails.len(),
+            load: self.load_report.clone(),
+            date_anomalies: date_anomalies(&self.emails),
+            bates_issues: bates_issues(&self.emails),
+            duplicate_message_ids: duplicate_message_ids(&self.emails),
+            empty_bodies: Vec::new(),
+            threading_warnings: self.threading_warnings()?,
+            finding_counts: IndexMap::new(),
+        };
+        let empty: Vec<String> =
+            self.emails.iter().filter(|e| e.full_text.trim().is_empty()).map(|e| e.id.clone()).collect();
+        if !empty.is_empty() {
+            let message = |n| format!("{} documents have no extracted text", n);
+            report.empty_bodies.push(finding("empty_body", empty, message));
+        }
+
+        let sections = [
+            &report.date_anomalies,
+            &report.bates_issues,
+            &report.duplicate_message_ids,
+            &report.empty_bodies,
+            &report.threading_warnings,
+        ];
+        for finding in sections.into_iter().flatten() {
+            *report.finding_counts.entry(finding.kind.clone()).or_default() += 1;
+        }
+        Ok(report)
+    }
+
+    fn threading_warnings(&self) -> Result<Vec<QcFinding>, JsValue> {
+        let mut warnings = Vec::new();
+        let unthreaded: Vec<String> =
+            self.included_emails().filter(|e| self.thread_key(e).is_none()).map(|e| e.id.clone()).collect();
+        if !unthreaded.is_empty() {
+            warnings.push(finding("unthreaded", unthreaded, |n| format!("{} emails have no thread key", n)));
+        }
+
+        let loaded: HashSet<&str> =
+            self.emails.iter().map(|e| e.message_id.as_str()).filter(|m| !m.is_empty()).collect();
+        for email in &self.emails {
+            if let Some(parent) = email.in_reply_to.as_deref().filter(|p| !p.trim().is_empty()) {
+                if !loaded.contains(parent.trim()) {
+                    warnings.push(QcFinding {
+                        kind: "missing_parent".to_string(),
+                        email_ids: vec![email.id.clone()],
+                        message: format!("Replies to {}, which is not loaded", parent.trim()),
+                    });
+                }
+            }
+        }
+
+        for thread_id in self.threads.keys() {
+            self.check_cancelled()?;
+            for violation in self.verify(thread_id)?.violations {
+                warnings.push(QcFinding {
+                    kind: violation.kind,
+                    email_ids: vec![violation.email_id],
+                    message: format!("Thread {}: {}", violation.thread_id, violation.message),
+                });
+            }
+        }
+        Ok(warnings)
+    }
+}
+
+fn finding(kind: &str, email_ids: Vec<String>, message: impl Fn(usize) -> String) -> QcFinding {
+    QcFinding {
+        kind: kind.to_string(),
+        message: message(email_ids.len()),
+        email_ids,
+    }
+}
+
+fn date_anomalies(emails: &[EmailMessage]) -> Vec<QcFinding> {
+    let earliest = Utc.with_ymd_and_hms(EARLIEST_YEAR, 1, 1, 0, 0, 0).unwrap();
+    let now = Utc::now();
+    let mut anomalies = Vec::new();
+    for email in emails {
+        let kind = if email.date_sent < earliest {
+            "before_1990"
+        } else if email.date_sent > now {
+            "future_date"
+        } else {
+            continue;
+        };
+        anomalies.push(QcFinding {
+            kind: kind.to_string(),
+            email_ids: vec![email.id.clone()],
+            message: format!("Sent {}", email.date_sent.to_rfc3339()),
+        });
+    }
+    anomalies
+}
+
+fn bates_issues(emails: &[EmailMessage]) -> Vec<QcFinding> {
+    let mut issues = Vec::new();
+    let missing: Vec<String> = emails.iter().filter(|e| e.beg_bates.trim().is_empty()).map(|e| e.id.clone()).collect();
+    if !missing.is_empty() {
+        issues.push(finding("missing_bates", missing, |n| format!("{} documents have no BegBates", n)));
+    }
+
+    let mut by_bates: IndexMap<String, Vec<String>> = IndexMap::new();
+    for email in emails.iter().filter(|e| !e.beg_bates.trim().is_empty()) {
+        by_bates.entry(email.beg_bates.trim().to_uppercase()).or_default().push(email.id.clone());
+    }
+    for (bates, ids) in by_bates.into_iter().filter(|(_, ids)| ids.len() > 1) {
+        issues.push(finding("duplicate_bates", ids, |n| format!("{} documents share BegBates {}", n, bates)));
+    }
+
+    // (prefix, first, last, email id) of every well-formed range
+    let mut ranges = Vec::new();
+    for email in emails {
+        let beg = email.beg_bates.trim();
+        let end = if email.end_bates.trim().is_empty() { beg } else { email.end_bates.trim() };
+        let (Some((beg_prefix, lo)), Some((end_prefix, hi))) = (split_bates(beg), split_bates(end)) else {
+            continue;
+        };
+        let issue = if !beg_prefix.eq_ignore_ascii_case(end_prefix) {
+            ("prefix_mismatch", format!("BegBates {} and EndBates {} have different prefixes", beg, end))
+        } else if hi < lo {
+            ("reversed_range", format!("EndBates {} is before BegBates {}", end, beg))
+        } else {
+            ranges.push((beg_prefix.to_uppercase(), lo, hi, email.id.as_str()));
+            continue;
+        };
+        issues.push(QcFinding {
+            kind: issue.0.to_string(),
+            email_ids: vec![email.id.clone()],
+            message: issue.1,
+        });
+    }
+
+    ranges.sort();
+    // The range reaching furthest so far, as a later range may start inside
+    // any earlier one
+    let mut reach: Option<(&str, u64, u64, &str)> = None;
+    for (prefix, lo, hi, id) in &ranges {
+        if let Some((reach_prefix, reach_lo, reach_hi, reach_id)) = reach {
+            if reach_prefix == prefix && *lo <= reach_hi && *lo != reach_lo {
+                issues.push(QcFinding {
+                    kind: "overlapping_range".to_string(),
+                    email_ids: vec![reach_id.to_string(), id.to_string()],
+                    message: format!("{}{} starts inside the range ending {}{}", prefix, lo, prefix, reach_hi),
+                });
+            }
+            if reach_prefix == prefix && reach_hi >= *hi {
+                continue;
+            }
+        }
+        reach = Some((prefix, *lo, *hi, id));
+    }
+    issues
+}
+
+fn duplicate_message_ids(emails: &[EmailMessage]) -> Vec<QcFinding> {
+    let mut by_message_id: IndexMap<&str, Vec<&EmailMessage>> = IndexMap::new();
+    for email in emails.iter().filter(|e| !e.message_id.trim().is_empty()) {
+        by_message_id.entry(email.message_id.trim()).or_default().push(email);
+    }
+    by_message_id
+        .into_iter()
+        .filter(|(_, copies)| copies.len() > 1)
+        .map(|(message_id, copies)| {
+            let hashes: HashSet<&str> = copies.iter().map(|e| e.hash.trim()).collect();
+            let detail = if hashes.len() == 1 { "identical copies" } else { "different hashes" };
+            QcFinding {
+                kind: "duplicate_message_id".to_string(),
+                email_ids: copies.iter().map(|e| e.id.clone()).collect(),
+                message: format!("{} documents share Message-ID {} ({})", copies.len(), message_id, detail),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,270 @@
+use crate::{inclusive, topics, EmailMessage, EmailThreadProcessor, ThreadNode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+// Points per signal; a candidate needs a references or subject match to be
+// considered at all
+const REFERENCES_POINTS: f64 = 3.0;
+const SUBJECT_POINTS: f64 = 1.0;
+const QUOTED_POINTS: f64 = 2.0;
+const PARTICIPANTS_POINTS: f64 = 1.0;
+
+/// Thresholds for `reattach_orphans`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReattachOptions {
+    // Points a candidate parent needs: 3 for a cited Message-ID, 1 for the
+    // same subject, 2 for quoting its content, 1 for shared participants
+    pub min_score: f64,
+    // Share of the parent's new content the orphan must repeat, 0 to 1
+    pub min_containment: f64,
+}
+
+impl Default for ReattachOptions {
+    fn default() -> Self {
+        ReattachOptions {
+            min_score: 3.0,
+            min_containment: 0.8,
+        }
+    }
+}
+
+/// A parent edge made by `reattach_orphans` rather than read from headers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reattachment {
+    pub email_id: String,
+    pub parent_id: String,
+    // Threads the email left and joined, as grouped when the pass ran
+    pub original_thread_id: String,
+    pub thread_id: String,
+    pub score: f64,
+    // "references", "subject", "quoted" and/or "participants"
+    pub signals: Vec<String>,
+}
+
+/// What the pass decided for one orphan reply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReattachDecision {
+    pub email_id: String,
+    pub thread_id: String,
+    // The best candidate found, whether or not it scored enough
+    pub candidate_parent_id: Option<String>,
+    pub candidate_thread_id: Option<String>,
+    pub score: f64,
+    pub signals: Vec<String>,
+    pub reattached: bool,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Repairs threading for orphan replies: emails that look like replies
+    /// (In-Reply-To, References or a Re:/Fwd: subject) but have no parent in
+    /// their thread and no replies of their own. Each is matched against
+    /// earlier emails in every thread on cited Message-IDs, subject, quoted
+    /// content and shared participants, and moved under the best candidate
+    /// scoring at least `min_score` (see `ReattachOptions`). Those edges are
+    /// flagged `heuristic_parent` in thread trees. Replaces the reattachments
+    /// of any earlier pass and regroups threads. Returns a decision for every
+    /// orphan considered.
+    #[wasm_bindgen]
+    pub fn reattach_orphans(&mut self, options: JsValue) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let options: ReattachOptions = if options.is_undefined() || options.is_null() {
+            ReattachOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options)?
+        };
+
+        self.reattachments.clear();
+        self.group_by_threads();
+        let decisions = self.reattach_decisions(&options)?;
+        for decision in decisions.iter().filter(|d| d.reattached) {
+            let (Some(parent_id), Some(thread_id)) = (&decision.candidate_parent_id, &decision.candidate_thread_id) else {
+                continue;
+            };
+            let reattachment = Reattachment {
+                email_id: decision.email_id.clone(),
+                parent_id: parent_id.clone(),
+                original_thread_id: decision.thread_id.clone(),
+                thread_id: thread_id.clone(),
+                score: decision.score,
+                signals: decision.signals.clone(),
+            };
+            self.reattachments.insert(decision.email_id.clone(), reattachment);
+        }
+        if !self.reattachments.is_empty() {
+            self.group_by_threads();
+        }
+
+        console_log!("Reattached {} of {} orphan replies", self.reattachments.len(), decisions.len());
+        let details = self
+            .reattachments
+            .values()
+            .map(|r| format!("{} -> {} ({})", r.email_id, r.parent_id, r.signals.join(", ")))
+            .collect::<Vec<_>>()
+            .join("; ");
+        self.audit(
+            "reattach_orphans",
+            format!("{} of {} orphans reattached: {}", self.reattachments.len(), decisions.len(), details),
+        );
+        self.to_js(&decisions)
+    }
+
+    /// The edges the last `reattach_orphans` made, in the order made.
+    #[wasm_bindgen]
+    pub fn get_reattachments(&self) -> Result<JsValue, JsValue> {
+        let reattachments: Vec<&Reattachment> = self.reattachments.values().collect();
+        self.to_js(&reattachments)
+    }
+
+    /// Drops all reattachments, returning emails to the threads their own
+    /// headers put them in.
+    #[wasm_bindgen]
+    pub fn clear_reattachments(&mut self) {
+        if self.reattachments.is_empty() {
+            return;
+        }
+        let count = self.reattachments.len();
+        self.reattachments.clear();
+        self.audit("clear_reattachments", format!("{} reattachments dropped", count));
+        self.group_by_threads();
+    }
+}
+
+impl EmailThreadProcessor {
+    /// The parent `reattach_orphans` gave an email, if any.
+    pub(crate) fn reattached_parent(&self, email_id: &str) -> Option<&str> {
+        self.reattachments.get(email_id).map(|r| r.parent_id.as_str())
+    }
+
+    /// Sets `heuristic_parent` on every node below `node` hung there by
+    /// `reattach_orphans`.
+    pub(crate) fn mark_heuristic_edges(&self, node: &mut ThreadNode) {
+        for child in &mut node.children {
+            child.heuristic_parent = self.reattached_parent(&child.email.id) == Some(node.email.id.as_str());
+            self.mark_heuristic_edges(child);
+        }
+    }
+
+    fn reattach_decisions(&self, options: &ReattachOptions) -> Result<Vec<ReattachDecision>, JsValue> {
+        let mut candidates: Vec<(&String, &EmailMessage)> = Vec::new();
+        let mut orphans: Vec<(&String, &EmailMessage)> = Vec::new();
+        // Locked threads neither give up orphans nor take them in
+        for (thread_id, emails) in self.threads.iter().filter(|(id, _)| !self.locked_threads.contains_key(*id)) {
+            let parents = self.resolve_parents(emails);
+            let has_replies: HashSet<&str> = parents.values().map(String::as_str).collect();
+            for email in emails {
+                candidates.push((thread_id, email));
+                if !parents.contains_key(&email.id) && !has_replies.contains(email.id.as_str()) && is_reply(email) {
+                    orphans.push((thread_id, email));
+                }
+            }
+        }
+
+        let mut by_message_id: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut by_subject: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, (_, email)) in candidates.iter().enumerate() {
+            if !email.message_id.is_empty() {
+                by_message_id.entry(email.message_id.as_str()).or_default().push(i);
+            }
+            let subject = topics::normalize_subject(&email.subject);
+            if !subject.is_empty() {
+                by_subject.entry(subject).or_default().push(i);
+            }
+        }
+
+        let mut decisions = Vec::new();
+        for (n, &(thread_id, orphan)) in orphans.iter().enumerate() {
+            self.check_cancelled()?;
+            let cited: HashSet<&str> =
+                orphan.in_reply_to.iter().chain(&orphan.references).map(String::as_str).collect();
+            let subject = topics::normalize_subject(&orphan.subject);
+            let mut pool: Vec<usize> = cited.iter().flat_map(|id| by_message_id.get(id)).flatten().copied().collect();
+            pool.extend(by_subject.get(&subject).into_iter().flatten());
+            pool.sort_unstable();
+            pool.dedup();
+
+            // Strictly earlier, so reattached chains can never loop
+            let best = pool
+                .into_iter()
+                .map(|i| candidates[i])
+                .filter(|(_, c)| c.id != orphan.id && c.date_sent < orphan.date_sent)
+                .map(|(candidate_thread, candidate)| {
+                    let signals = self.signals(orphan, candidate, &cited, &subject, options);
+                    let score = signals.iter().map(|(_, points)| points).sum::<f64>();
+                    (candidate_thread, candidate, signals, score)
+                })
+                // Ties go to the candidate sent closest before the orphan
+                .max_by(|a, b| a.3.total_cmp(&b.3).then_with(|| a.1.date_sent.cmp(&b.1.date_sent)));
+
+            decisions.push(match best {
+                Some((candidate_thread, candidate, signals, score)) => ReattachDecision {
+                    email_id: orphan.id.clone(),
+                    thread_id: thread_id.clone(),
+                    candidate_parent_id: Some(candidate.id.clone()),
+                    candidate_thread_id: Some(candidate_thread.clone()),
+                    score,
+                    signals: signals.into_iter().map(|(name, _)| name.to_string()).collect(),
+                    reattached: score >= options.min_score,
+                },
+                None => ReattachDecision {
+                    email_id: orphan.id.clone(),
+                    thread_id: thread_id.clone(),
+                    candidate_parent_id: None,
+                    candidate_thread_id: None,
+                    score: 0.0,
+                    signals: Vec::new(),
+                    reattached: false,
+                },
+            });
+            self.emit_progress("reattaching", n + 1, Some(orphans.len()));
+        }
+        Ok(decisions)
+    }
+
+    fn signals(
+        &self,
+        orphan: &EmailMessage,
+        candidate: &EmailMessage,
+        cited: &HashSet<&str>,
+        subject: &str,
+        options: &ReattachOptions,
+    ) -> Vec<(&'static str, f64)> {
+        let mut signals = Vec::new();
+        if !candidate.message_id.is_empty() && cited.contains(candidate.message_id.as_str()) {
+            signals.push(("references", REFERENCES_POINTS));
+        }
+        if !subject.is_empty() && topics::normalize_subject(&candidate.subject) == subject {
+            signals.push(("subject", SUBJECT_POINTS));
+        }
+        let quotable = !inclusive::normalized_words(inclusive::new_content(&candidate.full_text)).is_empty();
+        let quoted = quotable
+            && inclusive::containment_score(&candidate.full_text, &orphan.full_text) >= options.min_containment;
+        if quoted {
+            signals.push(("quoted", QUOTED_POINTS));
+        }
+        let people = |email: &EmailMessage| -> HashSet<String> {
+            std::iter::once(&email.from)
+                .chain(&email.to)
+                .chain(&email.cc)
+                .flat_map(|a| self.identity_map.identities(a))
+                .collect()
+        };
+        let sender = self.identity_map.identities(&orphan.from);
+        let candidate_sender = self.identity_map.identities(&candidate.from);
+        let (orphan_people, candidate_people) = (people(orphan), people(candidate));
+        if sender.iter().any(|s| candidate_people.contains(s))
+            || candidate_sender.iter().any(|s| orphan_people.contains(s))
+        {
+            signals.push(("participants", PARTICIPANTS_POINTS));
+        }
+        signals
+    }
+}
+
+// Headers or subject say the email answers or passes on another
+fn is_reply(email: &EmailMessage) -> bool {
+    let plain = email.subject.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    email.in_reply_to.is_some() || !email.references.is_empty() || topics::normalize_subject(&email.subject) != plain
+}
@@ -0,0 +1,185 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// One redaction applied to a produced document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Redaction {
+    // Text blacked out, replaced in exports unless the vendor supplied
+    // the redacted text
+    pub text: Option<String>,
+    // Where on the image, for redactions drawn on pages only
+    pub page: Option<u32>,
+    pub region: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionImport {
+    pub applied: usize,
+    pub emails_redacted: usize,
+    // Bates numbers that matched no email
+    pub unmatched: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RedactionRecord {
+    #[serde(alias = "beg_bates")]
+    bates: String,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    page: Option<u32>,
+    #[serde(default)]
+    region: Option<String>,
+    #[serde(default)]
+    reason: Option<String>,
+    // The whole redacted text of the document, when the vendor supplies it
+    #[serde(default)]
+    redacted_text: Option<String>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Adds redactions from a vendor's redaction list: JSON, an array of
+    /// `{bates, text, page, region, reason, redacted_text}` objects, or CSV
+    /// with BegBates and any of Text, Page, Region, Reason and RedactedText
+    /// columns. Each entry needs text, a page or region, or redacted text.
+    /// Redacted emails are marked in rollups and thread stats, and exports use
+    /// the vendor's redacted text, or else the text with each redacted passage
+    /// replaced by "[REDACTED]".
+    #[wasm_bindgen]
+    pub fn import_redactions(&mut self, data: &str) -> Result<JsValue, JsValue> {
+        let data = data.trim_start_matches('\u{feff}').trim();
+        let records = if data.starts_with('[') {
+            serde_json::from_str(data).map_err(|e| e.to_string())
+        } else {
+            parse_csv(data)
+        }
+        .map_err(|e| JsValue::from_str(&format!("Error reading redactions: {}", e)))?;
+
+        let by_bates: HashMap<String, usize> = self
+            .emails
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.beg_bates.is_empty())
+            .map(|(i, e)| (e.beg_bates.trim().to_lowercase(), i))
+            .collect();
+        let mut applied = 0;
+        let mut touched = Vec::new();
+        let mut unmatched = Vec::new();
+        for (n, record) in records.into_iter().enumerate() {
+            let text = record.text.filter(|t| !t.trim().is_empty());
+            let region = record.region.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+            if text.is_none() && record.page.is_none() && region.is_none() && record.redacted_text.is_none() {
+                return Err(JsValue::from_str(&format!(
+                    "Redaction {} ({}) has no text, page, region or redacted text",
+                    n + 1,
+                    record.bates
+                )));
+            }
+            let Some(&i) = by_bates.get(&record.bates.trim().to_lowercase()) else {
+                unmatched.push(record.bates);
+                continue;
+            };
+
+            let redaction = Redaction {
+                text,
+                page: record.page,
+                region,
+                reason: record.reason.map(|r| r.trim().to_string()).unwrap_or_default(),
+            };
+            let email = &mut self.emails[i];
+            if record.redacted_text.is_some() {
+                email.redacted_text = record.redacted_text;
+            }
+            if !email.redactions.contains(&redaction) {
+                email.redactions.push(redaction);
+                applied += 1;
+            }
+            if !touched.contains(&i) {
+                touched.push(i);
+            }
+        }
+        self.refresh_thread_copies();
+
+        console_log!("Applied {} redactions to {} emails, {} unmatched", applied, touched.len(), unmatched.len());
+        self.audit_load(
+            "import_redactions",
+            format!("{} redactions on {} emails, {} unmatched", applied, touched.len(), unmatched.len()),
+            &[("redactions", data.as_bytes())],
+        );
+        let result = RedactionImport {
+            applied,
+            emails_redacted: touched.len(),
+            unmatched,
+        };
+        self.to_js(&result)
+    }
+
+    /// Drops every imported redaction, so exports use the full text again.
+    #[wasm_bindgen]
+    pub fn clear_redactions(&mut self) {
+        let mut count = 0;
+        for email in self.emails.iter_mut().filter(|e| !e.redactions.is_empty() || e.redacted_text.is_some()) {
+            email.redactions.clear();
+            email.redacted_text = None;
+            count += 1;
+        }
+        self.refresh_thread_copies();
+        self.audit("clear_redactions", format!("Redactions removed from {} emails", count));
+    }
+}
+
+/// The body as exports should carry it: the vendor's redacted text, or else
+/// the full text with each redacted passage blacked out.
+pub(crate) fn export_text(email: &EmailMessage) -> Cow<'_, str> {
+    if let Some(text) = &email.redacted_text {
+        return Cow::Borrowed(text);
+    }
+    let mut text = Cow::Borrowed(email.full_text.as_str());
+    for redaction in &email.redactions {
+        let Some(passage) = redaction.text.as_deref().filter(|p| text.contains(p)) else {
+            continue;
+        };
+        let marker = if redaction.reason.is_empty() {
+            "[REDACTED]".to_string()
+        } else {
+            format!("[REDACTED: {}]", redaction.reason)
+        };
+        text = Cow::Owned(text.replace(passage, &marker));
+    }
+    text
+}
+
+fn parse_csv(data: &str) -> Result<Vec<RedactionRecord>, String> {
+    let mut rdr = csv::ReaderBuilder::new().flexible(true).from_reader(data.as_bytes());
+    let headers = rdr.headers().map_err(|e| e.to_string())?.clone();
+    let column =
+        |names: &[&str]| headers.iter().position(|h| names.iter().any(|n| h.trim().eq_ignore_ascii_case(n)));
+    let bates_col = column(&["BegBates", "Bates"]).ok_or("CSV needs a BegBates column")?;
+    let (text_col, page_col, region_col) = (column(&["Text"]), column(&["Page"]), column(&["Region"]));
+    let (reason_col, redacted_col) = (column(&["Reason"]), column(&["RedactedText"]));
+
+    let mut records = Vec::new();
+    for (i, record) in rdr.records().enumerate() {
+        let record = record.map_err(|e| format!("row {}: {}", i + 1, e))?;
+        let field = |col: Option<usize>| {
+            col.and_then(|c| record.get(c)).map(str::to_string).filter(|v| !v.trim().is_empty())
+        };
+        let page = field(page_col)
+            .map(|p| p.trim().parse::<u32>().map_err(|_| format!("row {}: invalid page {}", i + 1, p)))
+            .transpose()?;
+        records.push(RedactionRecord {
+            bates: field(Some(bates_col)).unwrap_or_default(),
+            text: field(text_col),
+            page,
+            region: field(region_col),
+            reason: field(reason_col),
+            redacted_text: field(redacted_col),
+        });
+    }
+    Ok(records)
+}
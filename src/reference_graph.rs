@@ -0,0 +1,133 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// A message in the citation graph: one of the thread's emails, or a
+/// Message-ID they cite.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceNode {
+    // The Message-ID, or "email:<id>" for an email without one
+    pub key: String,
+    pub email_id: Option<String>,
+    // "in_thread", "other_thread" (loaded but grouped elsewhere) or
+    // "missing" (cited but not loaded)
+    pub status: String,
+    pub thread_id: Option<String>,
+    // For the thread's own emails, the parent the threading engine chose,
+    // to set against the headers
+    pub resolved_parent_id: Option<String>,
+}
+
+/// One citation as written in a header, from the citing message to the cited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceEdge {
+    pub from: String,
+    pub to: String,
+    // "in_reply_to" or "references"
+    pub kind: String,
+    // Place in the References header, oldest first
+    pub position: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceGraph {
+    pub thread_id: String,
+    pub nodes: Vec<ReferenceNode>,
+    pub edges: Vec<ReferenceEdge>,
+    pub dangling_count: usize,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// The Message-ID / In-Reply-To / References citations of a thread's
+    /// emails as the headers give them, including references to messages
+    /// that were never loaded or were grouped into another thread. Compare
+    /// with `build_thread_tree` to see how the threading engine resolved them.
+    #[wasm_bindgen]
+    pub fn get_reference_graph(&self, thread_id: &str) -> Result<JsValue, JsValue> {
+        let graph = self.reference_graph(thread_id)?;
+        self.to_js(&graph)
+    }
+}
+
+impl EmailThreadProcessor {
+    fn reference_graph(&self, thread_id: &str) -> Result<ReferenceGraph, JsValue> {
+        let emails = self
+            .threads
+            .get(thread_id)
+            .ok_or_else(|| JsValue::from_str("Thread not found"))?;
+        let loaded: HashMap<&str, &EmailMessage> = self
+            .emails
+            .iter()
+            .filter(|e| !e.message_id.is_empty())
+            .map(|e| (e.message_id.as_str(), e))
+            .collect();
+        let parents = self.resolve_parents(emails);
+
+        let mut nodes: IndexMap<String, ReferenceNode> = IndexMap::new();
+        // Copies sharing a Message-ID are one message; the first stands for it
+        for email in emails {
+            let key = node_key(email);
+            nodes.entry(key.clone()).or_insert_with(|| ReferenceNode {
+                key,
+                email_id: Some(email.id.clone()),
+                status: "in_thread".to_string(),
+                thread_id: Some(thread_id.to_string()),
+                resolved_parent_id: parents.get(&email.id).cloned(),
+            });
+        }
+
+        let mut edges = Vec::new();
+        for email in emails {
+            let from = node_key(email);
+            let cited = email
+                .in_reply_to
+                .iter()
+                .map(|id| (id, "in_reply_to", None))
+                .chain(email.references.iter().enumerate().map(|(i, id)| (id, "references", Some(i))));
+            for (to, kind, position) in cited {
+                let to = to.trim();
+                if to.is_empty() {
+                    continue;
+                }
+                if !nodes.contains_key(to) {
+                    let other = loaded.get(to);
+                    nodes.insert(
+                        to.to_string(),
+                        ReferenceNode {
+                            key: to.to_string(),
+                            email_id: other.map(|e| e.id.clone()),
+                            status: if other.is_some() { "other_thread" } else { "missing" }.to_string(),
+                            thread_id: other.and_then(|e| self.thread_key(e)),
+                            resolved_parent_id: None,
+                        },
+                    );
+                }
+                edges.push(ReferenceEdge {
+                    from: from.clone(),
+                    to: to.to_string(),
+                    kind: kind.to_string(),
+                    position,
+                });
+            }
+        }
+
+        let nodes: Vec<ReferenceNode> = nodes.into_values().collect();
+        Ok(ReferenceGraph {
+            thread_id: thread_id.to_string(),
+            dangling_count: nodes.iter().filter(|n| n.status == "missing").count(),
+            nodes,
+            edges,
+        })
+    }
+}
+
+fn node_key(email: &EmailMessage) -> String {
+    if email.message_id.is_empty() {
+        format!("email:{}", email.id)
+    } else {
+        email.message_id.clone()
+    }
+}
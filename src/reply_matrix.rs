@@ -0,0 +1,94 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplyPair {
+    // Sender of the reply, and of the email it answers
+    pub replier: String,
+    pub replied_to: String,
+    pub count: usize,
+    pub email_ids: Vec<String>,
+}
+
+/// Who replied to whom within one thread.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplyMatrix {
+    pub thread_id: String,
+    // Most replies given and received first
+    pub participants: Vec<String>,
+    // counts[i][j]: replies participants[i] sent to an email from
+    // participants[j]; the diagonal holds follow-ups to one's own email
+    pub counts: Vec<Vec<usize>>,
+    // The non-zero cells, busiest first
+    pub pairs: Vec<ReplyPair>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// For each pair of senders in a thread, how many times one replied to
+    /// the other, going by the resolved reply tree. Mapped addresses count
+    /// as their person.
+    #[wasm_bindgen]
+    pub fn get_reply_matrix(&self, thread_id: &str) -> Result<JsValue, JsValue> {
+        let matrix = self.reply_matrix(thread_id)?;
+        self.to_js(&matrix)
+    }
+}
+
+impl EmailThreadProcessor {
+    fn reply_matrix(&self, thread_id: &str) -> Result<ReplyMatrix, JsValue> {
+        let emails = self.threads.get(thread_id).ok_or_else(|| JsValue::from_str("Thread not found"))?;
+        let sender = |email: &EmailMessage| self.identity_map.identities(&email.from).into_iter().next();
+
+        let mut replies: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+        let parents = self.resolve_parents(emails);
+        for email in emails {
+            let Some(parent) = parents.get(&email.id).and_then(|p| emails.iter().find(|e| &e.id == p)) else {
+                continue;
+            };
+            if let (Some(replier), Some(replied_to)) = (sender(email), sender(parent)) {
+                replies.entry((replier, replied_to)).or_default().push(email.id.clone());
+            }
+        }
+
+        let mut totals: IndexMap<String, usize> = IndexMap::new();
+        for ((replier, replied_to), ids) in &replies {
+            *totals.entry(replier.clone()).or_default() += ids.len();
+            *totals.entry(replied_to.clone()).or_default() += ids.len();
+        }
+        totals.sort_by(|a, x, b, y| y.cmp(x).then_with(|| a.cmp(b)));
+        let participants: Vec<String> = totals.into_keys().collect();
+
+        let counts = participants
+            .iter()
+            .map(|replier| {
+                participants
+                    .iter()
+                    .map(|replied_to| replies.get(&(replier.clone(), replied_to.clone())).map_or(0, Vec::len))
+                    .collect()
+            })
+            .collect();
+        let mut pairs: Vec<ReplyPair> = replies
+            .into_iter()
+            .map(|((replier, replied_to), email_ids)| ReplyPair {
+                replier,
+                replied_to,
+                count: email_ids.len(),
+                email_ids,
+            })
+            .collect();
+        pairs.sort_by(|a, b| {
+            b.count.cmp(&a.count).then_with(|| (&a.replier, &a.replied_to).cmp(&(&b.replier, &b.replied_to)))
+        });
+
+        Ok(ReplyMatrix {
+            thread_id: thread_id.to_string(),
+            participants,
+            counts,
+            pairs,
+        })
+    }
+}
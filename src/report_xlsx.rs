@@ -0,0 +1,244 @@
+use crate::EmailThreadProcessor;
+use std::io::{Cursor, Write};
+use wasm_bindgen::prelude::*;
+
+enum Cell {
+    Text(String),
+    Number(f64),
+}
+
+struct Sheet {
+    name: &'static str,
+    headers: &'static [&'static str],
+    rows: Vec<Vec<Cell>>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Case-team report workbook with Thread Summaries, Custodian Stats,
+    /// Participant Stats and QC Findings tabs (integrity violations, hash
+    /// issues and coding conflicts). Built with the `xlsx-export` feature.
+    #[wasm_bindgen]
+    pub fn export_report_xlsx(&self) -> Result<Vec<u8>, JsValue> {
+        self.reset_cancellation();
+        let sheets = [
+            self.summary_sheet(),
+            self.custodian_sheet(),
+            self.participant_sheet()?,
+            self.qc_sheet()?,
+        ];
+        console_log!("Writing report workbook with {} sheets", sheets.len());
+        write_workbook(&sheets).map_err(|e| JsValue::from_str(&format!("Error writing workbook: {}", e)))
+    }
+}
+
+impl EmailThreadProcessor {
+    fn summary_sheet(&self) -> Sheet {
+        Sheet {
+            name: "Thread Summaries",
+            headers: &["Thread ID", "Subject", "Emails", "Participants", "Custodians", "Labels", "Start", "End"],
+            rows: self
+                .visible_threads()
+                .map(|(thread_id, emails)| {
+                    let s = self.summarize(thread_id, emails);
+                    vec![
+                        text(&s.thread_id),
+                        text(&s.subject),
+                        number(s.email_count),
+                        number(s.participant_count),
+                        text(&s.custodians.join("; ")),
+                        text(&s.labels.join("; ")),
+                        text(&s.date_range.start.to_rfc3339()),
+                        text(&s.date_range.end.to_rfc3339()),
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    fn custodian_sheet(&self) -> Sheet {
+        Sheet {
+            name: "Custodian Stats",
+            headers: &["Custodian", "Documents", "Primary", "Duplicate", "Unique", "Threads"],
+            rows: self
+                .custodian_stats()
+                .iter()
+                .map(|s| {
+                    vec![
+                        text(&s.custodian),
+                        number(s.document_count),
+                        number(s.primary_count),
+                        number(s.duplicate_count),
+                        number(s.unique_count),
+                        number(s.thread_count),
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    fn participant_sheet(&self) -> Result<Sheet, JsValue> {
+        Ok(Sheet {
+            name: "Participant Stats",
+            headers: &["Rank", "Participant", "Sent", "Received", "Contacts", "Betweenness", "Broker Score", "Top Contacts"],
+            rows: self
+                .identity_ranks()?
+                .iter()
+                .map(|r| {
+                    vec![
+                        number(r.rank),
+                        text(&r.identity),
+                        number(r.sent),
+                        number(r.received),
+                        number(r.degree),
+                        Cell::Number(r.betweenness),
+                        Cell::Number(r.broker_score),
+                        text(&r.top_contacts.join("; ")),
+                    ]
+                })
+                .collect(),
+        })
+    }
+
+    fn qc_sheet(&self) -> Result<Sheet, JsValue> {
+        let mut rows = Vec::new();
+        for thread_id in self.threads.keys() {
+            for v in self.verify(thread_id)?.violations {
+                rows.push(vec![text("thread_integrity"), text(&v.kind), text(&v.thread_id), text(&v.email_id), text(&v.message)]);
+            }
+        }
+        for issue in self.hash_report().issues {
+            rows.push(vec![
+                text("hash"),
+                text(&issue.kind),
+                text(&issue.hashes.join("; ")),
+                text(&issue.email_ids.join("; ")),
+                text(&issue.message),
+            ]);
+        }
+        for conflict in self.coding_conflicts() {
+            rows.push(vec![
+                text("coding"),
+                text(&conflict.scope),
+                text(&conflict.key),
+                text(&conflict.email_ids.join("; ")),
+                text(&format!("Coded both {}", conflict.tags.join(" and "))),
+            ]);
+        }
+        Ok(Sheet {
+            name: "QC Findings",
+            headers: &["Check", "Kind", "Key", "Email IDs", "Message"],
+            rows,
+        })
+    }
+}
+
+fn text(value: &str) -> Cell {
+    Cell::Text(value.to_string())
+}
+
+fn number(value: usize) -> Cell {
+    Cell::Number(value as f64)
+}
+
+// A minimal SpreadsheetML package: inline strings, no shared string table or
+// styles, which every spreadsheet application opens
+fn write_workbook(sheets: &[Sheet]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut put = |name: &str, content: String| -> zip::result::ZipResult<()> {
+        writer.start_file(name, options)?;
+        writer.write_all(content.as_bytes())?;
+        Ok(())
+    };
+
+    let overrides: String = (1..=sheets.len())
+        .map(|i| format!(r#"<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#, i))
+        .collect();
+    put(
+        "[Content_Types].xml",
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>{}</Types>"#,
+            overrides
+        ),
+    )?;
+    put(
+        "_rels/.rels",
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#.to_string(),
+    )?;
+
+    let sheet_entries: String = sheets
+        .iter()
+        .enumerate()
+        .map(|(i, s)| format!(r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#, escape(s.name), i + 1, i + 1))
+        .collect();
+    put(
+        "xl/workbook.xml",
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>{}</sheets></workbook>"#,
+            sheet_entries
+        ),
+    )?;
+    let relationships: String = (1..=sheets.len())
+        .map(|i| format!(r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{}.xml"/>"#, i, i))
+        .collect();
+    put(
+        "xl/_rels/workbook.xml.rels",
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#,
+            relationships
+        ),
+    )?;
+
+    for (i, sheet) in sheets.iter().enumerate() {
+        put(&format!("xl/worksheets/sheet{}.xml", i + 1), sheet_xml(sheet))?;
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+fn sheet_xml(sheet: &Sheet) -> String {
+    let header: Vec<Cell> = sheet.headers.iter().map(|h| text(h)).collect();
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+    );
+    for (r, row) in std::iter::once(&header).chain(&sheet.rows).enumerate() {
+        xml.push_str(&format!(r#"<row r="{}">"#, r + 1));
+        for (c, cell) in row.iter().enumerate() {
+            let reference = format!("{}{}", column_name(c), r + 1);
+            match cell {
+                Cell::Text(value) => xml.push_str(&format!(r#"<c r="{}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#, reference, escape(value))),
+                Cell::Number(value) => xml.push_str(&format!(r#"<c r="{}"><v>{}</v></c>"#, reference, value)),
+            }
+        }
+        xml.push_str("</row>");
+    }
+    xml.push_str("</sheetData></worksheet>");
+    xml
+}
+
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+// Excel rejects control characters other than tab and line breaks, and cells
+// longer than 32,767 characters
+fn escape(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+        .take(32_767)
+        .collect::<String>()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
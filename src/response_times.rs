@@ -0,0 +1,232 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc, Weekday};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// When work happens, for business-adjusted response times.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BusinessCalendar {
+    // e.g. ["Sat", "Sun"]
+    pub weekend_days: Vec<Weekday>,
+    pub holidays: Vec<NaiveDate>,
+    // Working hours in the calendar's local time, [start, end)
+    pub business_start_hour: u32,
+    pub business_end_hour: u32,
+    pub utc_offset_hours: i64,
+}
+
+impl Default for BusinessCalendar {
+    fn default() -> Self {
+        BusinessCalendar {
+            weekend_days: vec![Weekday::Sat, Weekday::Sun],
+            holidays: Vec::new(),
+            business_start_hour: 9,
+            business_end_hour: 17,
+            utc_offset_hours: 0,
+        }
+    }
+}
+
+/// How long one reply took after the message it answers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseTime {
+    pub email_id: String,
+    pub parent_id: String,
+    pub thread_id: String,
+    pub responder: String,
+    pub replied_to: String,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub sent_at: DateTime<Utc>,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub parent_sent_at: DateTime<Utc>,
+    pub raw_hours: f64,
+    // Working hours elapsed under the business calendar
+    pub business_hours: f64,
+    // business_hours in working days of the calendar's length
+    pub business_days: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub count: usize,
+    pub median_raw_hours: Option<f64>,
+    pub mean_raw_hours: Option<f64>,
+    pub median_business_hours: Option<f64>,
+    pub mean_business_hours: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseTimeReport {
+    pub calendar: BusinessCalendar,
+    pub overall: LatencySummary,
+    // By responder, most replies first
+    pub by_responder: IndexMap<String, LatencySummary>,
+    pub responses: Vec<ResponseTime>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Replaces the business calendar with a `BusinessCalendar` object:
+    /// `weekend_days` (default ["Sat", "Sun"]), `holidays` as "YYYY-MM-DD",
+    /// `business_start_hour`/`business_end_hour` (default 9 and 17) and
+    /// `utc_offset_hours` for the zone those hours are in. Omitted fields take
+    /// their defaults.
+    #[wasm_bindgen]
+    pub fn set_business_calendar(&mut self, calendar: JsValue) -> Result<(), JsValue> {
+        let calendar: BusinessCalendar = if calendar.is_undefined() || calendar.is_null() {
+            BusinessCalendar::default()
+        } else {
+            serde_wasm_bindgen::from_value(calendar)?
+        };
+        if calendar.business_end_hour > 24 || calendar.business_start_hour >= calendar.business_end_hour {
+            return Err(JsValue::from_str("Business hours must be a non-empty range between 0 and 24"));
+        }
+        if !(-14..=14).contains(&calendar.utc_offset_hours) {
+            return Err(JsValue::from_str("UTC offset must be between -14 and 14 hours"));
+        }
+        self.audit("set_business_calendar", serde_json::to_string(&calendar).unwrap_or_default());
+        self.business_calendar = calendar;
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_business_calendar(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.business_calendar)
+    }
+
+    /// Time from each message to each reply to it, both raw and counting
+    /// only working hours under the business calendar, with medians and means
+    /// overall and per responder. Replies to one's own message and replies
+    /// dated before their parent are left out, as is mass mail when
+    /// set_mass_mail_options says so. Limited to `thread_id` when given.
+    #[wasm_bindgen]
+    pub fn get_response_times(&self, thread_id: Option<String>) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let threads: Vec<(&String, &Vec<EmailMessage>)> = match &thread_id {
+            Some(id) => vec![self.threads.get_key_value(id).ok_or_else(|| JsValue::from_str("Thread not found"))?],
+            None => self.visible_threads().collect(),
+        };
+
+        let calendar = &self.business_calendar;
+        let mut responses = Vec::new();
+        for (thread_id, emails) in threads {
+            self.check_cancelled()?;
+            for (email_id, parent_id) in self.resolve_parents(emails) {
+                let (Some(email), Some(parent)) = (
+                    emails.iter().find(|e| e.id == email_id),
+                    emails.iter().find(|e| e.id == parent_id),
+                ) else {
+                    continue;
+                };
+                let (responder, replied_to) = (self.person_key(&email.from), self.person_key(&parent.from));
+                if email.date_sent < parent.date_sent || responder == replied_to {
+                    continue;
+                }
+                let excluded = |e| self.mass_mail.exclude_from_response_metrics && self.is_mass_mail(e);
+                if excluded(email) || excluded(parent) {
+                    continue;
+                }
+                let business_hours = calendar.business_hours_between(parent.date_sent, email.date_sent);
+                responses.push(ResponseTime {
+                    email_id: email.id.clone(),
+                    parent_id: parent.id.clone(),
+                    thread_id: thread_id.clone(),
+                    responder,
+                    replied_to,
+                    sent_at: email.date_sent,
+                    parent_sent_at: parent.date_sent,
+                    raw_hours: hours(email.date_sent - parent.date_sent),
+                    business_hours,
+                    business_days: business_hours / calendar.day_hours(),
+                });
+            }
+        }
+        responses.sort_by(|a, b| a.sent_at.cmp(&b.sent_at).then_with(|| a.email_id.cmp(&b.email_id)));
+
+        let mut grouped: IndexMap<String, Vec<&ResponseTime>> = IndexMap::new();
+        for response in &responses {
+            grouped.entry(response.responder.clone()).or_default().push(response);
+        }
+        grouped.sort_by(|a, x, b, y| y.len().cmp(&x.len()).then_with(|| a.cmp(b)));
+        let report = ResponseTimeReport {
+            calendar: calendar.clone(),
+            overall: summarize(&responses.iter().collect::<Vec<_>>()),
+            by_responder: grouped.into_iter().map(|(responder, rs)| (responder, summarize(&rs))).collect(),
+            responses,
+        };
+
+        console_log!("Measured {} response times", report.overall.count);
+        self.to_js(&report)
+    }
+}
+
+impl EmailThreadProcessor {
+    // A sender as the identity map knows them, else the bare address
+    fn person_key(&self, from: &str) -> String {
+        self.lookup_identity(from).unwrap_or_else(|| from.trim().to_lowercase())
+    }
+}
+
+impl BusinessCalendar {
+    fn day_hours(&self) -> f64 {
+        (self.business_end_hour - self.business_start_hour) as f64
+    }
+
+    fn is_business_day(&self, date: NaiveDate) -> bool {
+        !self.weekend_days.contains(&date.weekday()) && !self.holidays.contains(&date)
+    }
+
+    /// Working hours between two instants, walking the calendar's local days.
+    pub(crate) fn business_hours_between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> f64 {
+        let offset = Duration::hours(self.utc_offset_hours);
+        let (start, end) = ((start + offset).naive_utc(), (end + offset).naive_utc());
+        let mut total = Duration::zero();
+        let mut day = start.date();
+        while day <= end.date() {
+            if self.is_business_day(day) {
+                let open = at_hour(day, self.business_start_hour);
+                let close = at_hour(day, self.business_end_hour);
+                let (from, to) = (start.max(open), end.min(close));
+                if to > from {
+                    total += to - from;
+                }
+            }
+            day = match day.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        hours(total)
+    }
+}
+
+fn at_hour(day: NaiveDate, hour: u32) -> NaiveDateTime {
+    day.and_hms_opt(0, 0, 0).unwrap() + Duration::hours(hour as i64)
+}
+
+fn hours(duration: Duration) -> f64 {
+    duration.num_seconds() as f64 / 3600.0
+}
+
+fn summarize(responses: &[&ResponseTime]) -> LatencySummary {
+    let stats = |mut values: Vec<f64>| {
+        if values.is_empty() {
+            return (None, None);
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+        let mid = values.len() / 2;
+        let median = if values.len().is_multiple_of(2) { (values[mid - 1] + values[mid]) / 2.0 } else { values[mid] };
+        (Some(median), Some(values.iter().sum::<f64>() / values.len() as f64))
+    };
+    let (median_raw_hours, mean_raw_hours) = stats(responses.iter().map(|r| r.raw_hours).collect());
+    let (median_business_hours, mean_business_hours) = stats(responses.iter().map(|r| r.business_hours).collect());
+    LatencySummary {
+        count: responses.len(),
+        median_raw_hours,
+        mean_raw_hours,
+        median_business_hours,
+        mean_business_hours,
+    }
+}
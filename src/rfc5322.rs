@@ -0,0 +1,359 @@
+use crate::{conversation_index, EmailMessage};
+use chrono::{DateTime, Utc};
+
+// Minimal RFC 5322 / MIME handling shared by the mail-format loaders. Only what
+// threading and review need: unfolded headers, address lists, Message-ID lists,
+// dates, and the first text/plain body part.
+
+pub(crate) type Headers = Vec<(String, String)>;
+
+/// Splits a raw message into its header block and body at the first blank line.
+pub(crate) fn split_message(raw: &str) -> (&str, &str) {
+    for sep in ["\r\n\r\n", "\n\n"] {
+        if let Some(pos) = raw.find(sep) {
+            return (&raw[..pos], &raw[pos + sep.len()..]);
+        }
+    }
+    (raw, "")
+}
+
+pub(crate) fn parse_headers(block: &str) -> Headers {
+    let mut headers: Headers = Vec::new();
+
+    for line in block.lines() {
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    headers
+}
+
+pub(crate) fn header<'a>(headers: &'a Headers, name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Extracts the `<...>` identifiers from a Message-ID, In-Reply-To or References value.
+pub(crate) fn parse_message_ids(value: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('<') {
+        match rest[start..].find('>') {
+            Some(end) => {
+                ids.push(rest[start..start + end + 1].to_string());
+                rest = &rest[start + end + 1..];
+            }
+            None => break,
+        }
+    }
+    if ids.is_empty() && !value.trim().is_empty() {
+        ids.extend(value.split_whitespace().map(|s| s.to_string()));
+    }
+    ids
+}
+
+/// Returns bare addresses from an address-list header, dropping display names.
+pub(crate) fn parse_addresses(value: &str) -> Vec<String> {
+    split_address_list(value).iter().filter_map(|entry| bare_address(entry)).collect()
+}
+
+/// Returns (bare address, display name) pairs from an address-list header.
+pub(crate) fn parse_named_addresses(value: &str) -> Vec<(String, Option<String>)> {
+    split_address_list(value)
+        .iter()
+        .filter_map(|entry| bare_address(entry).map(|address| (address, display_name(entry))))
+        .collect()
+}
+
+fn split_address_list(value: &str) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut depth = 0;
+
+    for c in value.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '<' if !in_quotes => depth += 1,
+            '>' if !in_quotes => depth -= 1,
+            ',' | ';' if !in_quotes && depth == 0 => {
+                entries.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    entries.push(current);
+    entries
+}
+
+fn display_name(entry: &str) -> Option<String> {
+    let name = entry.trim();
+    let name = name[..name.rfind('<')?].trim().trim_matches('"').trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+fn bare_address(entry: &str) -> Option<String> {
+    let entry = entry.trim();
+    let addr = match (entry.rfind('<'), entry.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &entry[start + 1..end],
+        _ => entry.trim_matches('"'),
+    };
+    let addr = addr.trim();
+    if addr.is_empty() {
+        None
+    } else {
+        Some(addr.to_string())
+    }
+}
+
+pub(crate) fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    // Drop trailing comments such as "(PST)" that chrono rejects
+    let cleaned = match value.find('(') {
+        Some(pos) => value[..pos].trim(),
+        None => value.trim(),
+    };
+    // ...and a leading day name, which mailers frequently get wrong
+    let cleaned = match cleaned.split_once(',') {
+        Some((day, rest)) if day.trim().chars().all(|c| c.is_ascii_alphabetic()) => rest.trim(),
+        _ => cleaned,
+    };
+    DateTime::parse_from_rfc2822(cleaned)
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+pub(crate) fn parse_message(raw: &str) -> Result<EmailMessage, String> {
+    let (header_block, body) = split_message(raw);
+    let headers = parse_headers(header_block);
+    if headers.is_empty() {
+        return Err("Message has no headers".to_string());
+    }
+
+    let date_sent = header(&headers, "Date")
+        .and_then(parse_date)
+        .ok_or_else(|| "Message has no valid Date header".to_string())?;
+    let subject = header(&headers, "Subject").unwrap_or_default().to_string();
+    let from = header(&headers, "From")
+        .and_then(|v| parse_addresses(v).into_iter().next())
+        .unwrap_or_default();
+    let lowered = subject.to_lowercase();
+
+    Ok(EmailMessage {
+        message_id: header(&headers, "Message-ID")
+            .and_then(|v| parse_message_ids(v).into_iter().next())
+            .unwrap_or_default(),
+        in_reply_to: header(&headers, "In-Reply-To").and_then(|v| parse_message_ids(v).into_iter().next()),
+        references: header(&headers, "References").map(parse_message_ids).unwrap_or_default(),
+        to: header(&headers, "To").map(parse_addresses).unwrap_or_default(),
+        cc: header(&headers, "Cc").map(parse_addresses).unwrap_or_default(),
+        bcc: header(&headers, "Bcc").map(parse_addresses).unwrap_or_default(),
+        is_forward: lowered.starts_with("fwd:") || lowered.starts_with("fw:"),
+        title: subject.clone(),
+        subject,
+        author: from.clone(),
+        from,
+        date_sent,
+        date_created: date_sent,
+        date_last_modified: date_sent,
+        file_type: "email".to_string(),
+        full_text: extract_text(&headers, body),
+        conversation_index: header(&headers, "Thread-Index").and_then(conversation_index::normalize),
+        gmail_thread_id: header(&headers, "X-GM-THRID").map(|v| v.to_string()),
+        tags: header(&headers, "X-Gmail-Labels").map(parse_gmail_labels).unwrap_or_default(),
+        ..Default::default()
+    })
+}
+
+// Takeout writes labels comma-separated, quoting any that contain commas
+fn parse_gmail_labels(value: &str) -> Vec<String> {
+    let mut labels = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in value.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => labels.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    labels.push(current);
+
+    labels
+        .into_iter()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+fn extract_text(headers: &Headers, body: &str) -> String {
+    let content_type = header(headers, "Content-Type").unwrap_or("text/plain");
+    let encoding = header(headers, "Content-Transfer-Encoding").unwrap_or_default();
+
+    if content_type.to_lowercase().starts_with("multipart/") {
+        let Some(boundary) = content_type_param(content_type, "boundary") else {
+            return body.to_string();
+        };
+        let delimiter = format!("--{}", boundary);
+
+        let mut fallback = None;
+        for part in body.split(delimiter.as_str()).skip(1) {
+            if part.starts_with("--") {
+                break;
+            }
+            let (part_headers, part_body) = split_message(part.trim_start_matches(['\r', '\n']));
+            let part_headers = parse_headers(part_headers);
+            let part_type = header(&part_headers, "Content-Type").unwrap_or("text/plain").to_lowercase();
+
+            if part_type.starts_with("text/plain") || part_type.starts_with("multipart/") {
+                let text = extract_text(&part_headers, part_body);
+                if !text.trim().is_empty() {
+                    return text;
+                }
+            } else if part_type.starts_with("text/html") && fallback.is_none() {
+                fallback = Some(strip_html(&extract_text(&part_headers, part_body)));
+            }
+        }
+        return fallback.unwrap_or_default();
+    }
+
+    let decoded = decode_transfer_encoding(body, encoding);
+    if content_type.to_lowercase().starts_with("text/html") {
+        strip_html(&decoded)
+    } else {
+        decoded
+    }
+}
+
+fn content_type_param(content_type: &str, name: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        if key.trim().eq_ignore_ascii_case(name) {
+            Some(value.trim().trim_matches('"').to_string())
+        } else {
+            None
+        }
+    })
+}
+
+fn decode_transfer_encoding(body: &str, encoding: &str) -> String {
+    match encoding.trim().to_lowercase().as_str() {
+        "base64" => decode_base64(body)
+            .map(|b| String::from_utf8_lossy(&b).into_owned())
+            .unwrap_or_else(|| body.to_string()),
+        "quoted-printable" => decode_quoted_printable(body),
+        _ => body.to_string(),
+    }
+}
+
+fn decode_quoted_printable(body: &str) -> String {
+    let mut out = Vec::with_capacity(body.len());
+    let bytes = body.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'=' {
+            let rest = &bytes[i + 1..];
+            if rest.starts_with(b"\r\n") {
+                i += 3;
+                continue;
+            }
+            if rest.starts_with(b"\n") {
+                i += 2;
+                continue;
+            }
+            let hex = rest.get(..2).and_then(|h| std::str::from_utf8(h).ok());
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+}
+
+pub(crate) fn encode_base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+pub(crate) fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    let mut out = Vec::new();
+
+    for c in value.bytes().filter(|c| !c.is_ascii_whitespace() && *c != b'=') {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        bits = (bits << 6) | v as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            out.push((bits >> bit_count) as u8);
+            bits &= (1 << bit_count) - 1;
+        }
+    }
+
+    Some(out)
+}
@@ -0,0 +1,78 @@
+use crate::{EmailMessage, ThreadNode};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Truthy values of a load file redaction column
+const REDACTED_VALUES: &[&str] = &["y", "yes", "true", "1", "redacted"];
+
+/// What a node and everything below it carries, so a collapsed branch can be
+/// badged without expanding it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BranchRollup {
+    // Distinct confidentiality designations in the branch, e.g. "AEO"
+    pub confidentiality: BTreeSet<String>,
+    pub redacted_count: usize,
+    pub descendant_count: usize,
+    // Distinct "model: label" results from apply_classifications
+    #[serde(default)]
+    pub classifications: BTreeSet<String>,
+    // Highest score per model
+    #[serde(default)]
+    pub max_scores: BTreeMap<String, f64>,
+}
+
+impl BranchRollup {
+    pub(crate) fn of_email(email: &EmailMessage) -> BranchRollup {
+        let mut rollup = BranchRollup::default();
+        let designation = email.confidentiality.trim();
+        if !designation.is_empty() {
+            rollup.confidentiality.insert(designation.to_string());
+        }
+        if is_redacted(email) {
+            rollup.redacted_count = 1;
+        }
+        for (model, classification) in &email.classifications {
+            if let Some(label) = &classification.label {
+                rollup.classifications.insert(format!("{}: {}", model, label));
+            }
+            if let Some(score) = classification.score {
+                rollup.max_scores.insert(model.clone(), score);
+            }
+        }
+        rollup
+    }
+
+    /// Folds in a child branch's rollup.
+    pub(crate) fn add_child(&mut self, child: &BranchRollup) {
+        self.confidentiality.extend(child.confidentiality.iter().cloned());
+        self.redacted_count += child.redacted_count;
+        self.classifications.extend(child.classifications.iter().cloned());
+        for (model, &score) in &child.max_scores {
+            let max = self.max_scores.entry(model.clone()).or_insert(score);
+            *max = max.max(score);
+        }
+        self.descendant_count += 1 + child.descendant_count;
+    }
+}
+
+/// Fills in `rollup` for a node and all its descendants.
+pub(crate) fn roll_up(node: &mut ThreadNode) {
+    let mut rollup = BranchRollup::of_email(&node.email);
+    for child in &mut node.children {
+        roll_up(child);
+        rollup.add_child(&child.rollup);
+    }
+    node.rollup = rollup;
+}
+
+/// Redacted per `import_redactions`, a load file column named like "Redacted"
+/// or "HasRedactions" holding a yes value, or a tag such as "Redacted" (but
+/// not "Not Redacted").
+pub(crate) fn is_redacted(email: &EmailMessage) -> bool {
+    !email.redactions.is_empty() || email.extra.iter().any(|(column, value)| {
+        column.to_lowercase().contains("redact") && REDACTED_VALUES.contains(&value.trim().to_lowercase().as_str())
+    }) || email.tags.iter().any(|t| {
+        let tag = t.trim().to_lowercase();
+        tag.contains("redact") && !tag.starts_with("not ") && !tag.starts_with("no ")
+    })
+}
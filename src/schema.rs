@@ -0,0 +1,142 @@
+use crate::EmailThreadProcessor;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// Column layout of tabular load files (CSV, DAT, XLSX). `CsvRecord` holds the
+// serde mapping; this table adds what validation needs to know about each column.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnKind {
+    Text,
+    Date,
+    Bates,
+    Address,
+    AddressList,
+}
+
+pub(crate) struct ColumnSpec {
+    pub name: &'static str,
+    pub required: bool,
+    pub kind: ColumnKind,
+}
+
+const fn column(name: &'static str, required: bool, kind: ColumnKind) -> ColumnSpec {
+    ColumnSpec { name, required, kind }
+}
+
+pub(crate) const COLUMNS: &[ColumnSpec] = &[
+    column("BegBates", true, ColumnKind::Bates),
+    column("EndBates", true, ColumnKind::Bates),
+    column("BegAttach", false, ColumnKind::Bates),
+    column("EndAttach", false, ColumnKind::Bates),
+    column("Custodian", true, ColumnKind::Text),
+    column("DuplicateCustodian", false, ColumnKind::Text),
+    column("From", true, ColumnKind::Address),
+    column("To", true, ColumnKind::AddressList),
+    column("CC", false, ColumnKind::AddressList),
+    column("BCC", false, ColumnKind::AddressList),
+    column("Subject", true, ColumnKind::Text),
+    column("DateSent", true, ColumnKind::Date),
+    column("FileName", true, ColumnKind::Text),
+    column("FileType", true, ColumnKind::Text),
+    column("FileExtension", false, ColumnKind::Text),
+    column("ESIType", false, ColumnKind::Text),
+    column("DeDuplicatedPath", false, ColumnKind::Text),
+    column("DateCreated", true, ColumnKind::Date),
+    column("DateLastModified", true, ColumnKind::Date),
+    column("Title", true, ColumnKind::Text),
+    column("author", true, ColumnKind::Text),
+    column("Confidentiality", true, ColumnKind::Text),
+    column("Hash", true, ColumnKind::Text),
+    column("nativelink", true, ColumnKind::Text),
+    column("FullText", true, ColumnKind::Text),
+    column("EndAttach_Left", false, ColumnKind::Text),
+    column("column_history", true, ColumnKind::Text),
+    column("ConversationIndex", false, ColumnKind::Text),
+];
+
+/// Whether a header is one `CsvRecord` maps; anything else lands in `EmailMessage::extra`.
+pub(crate) fn is_mapped(header: &str) -> bool {
+    COLUMNS.iter().any(|c| c.name == header)
+}
+
+// Always tried before any configured formats
+const BUILTIN_DATE_FORMATS: &[&str] = &["RFC 3339", "%Y-%m-%dT%H:%M:%SZ"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaField {
+    pub name: String,
+    // Header expected in the load file, after any mapping override
+    pub source_column: String,
+    pub required: bool,
+    pub kind: ColumnKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpectedSchema {
+    pub required: Vec<SchemaField>,
+    pub optional: Vec<SchemaField>,
+    pub column_mapping: IndexMap<String, String>,
+    pub date_formats: Vec<String>,
+    pub column_history_format: String,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Maps canonical column names to the headers a load file actually uses,
+    /// e.g. `{"DateSent": "Sent Date"}`. Replaces any previous mapping.
+    #[wasm_bindgen]
+    pub fn set_column_mapping(&mut self, mapping: JsValue) -> Result<(), JsValue> {
+        let mapping: IndexMap<String, String> = serde_wasm_bindgen::from_value(mapping)?;
+
+        if let Some(unknown) = mapping.keys().find(|k| !is_mapped(k)) {
+            return Err(JsValue::from_str(&format!("Unknown column in mapping: {}", unknown)));
+        }
+
+        console_log!("Column mapping set for {} columns", mapping.len());
+        self.column_mapping = mapping;
+        Ok(())
+    }
+
+    /// Extra chrono format strings tried, in order, when a date is not RFC 3339.
+    #[wasm_bindgen]
+    pub fn set_date_formats(&mut self, formats: Vec<String>) {
+        console_log!("Date formats set: {:?}", formats);
+        self.date_formats = formats;
+    }
+
+    #[wasm_bindgen]
+    pub fn get_expected_schema(&self) -> Result<JsValue, JsValue> {
+        let (required, optional): (Vec<SchemaField>, Vec<SchemaField>) = COLUMNS
+            .iter()
+            .map(|spec| SchemaField {
+                name: spec.name.to_string(),
+                source_column: self
+                    .column_mapping
+                    .get(spec.name)
+                    .cloned()
+                    .unwrap_or_else(|| spec.name.to_string()),
+                required: spec.required,
+                kind: spec.kind,
+            })
+            .partition(|field| field.required);
+
+        let schema = ExpectedSchema {
+            required,
+            optional,
+            column_mapping: self.column_mapping.clone(),
+            date_formats: BUILTIN_DATE_FORMATS
+                .iter()
+                .map(|f| f.to_string())
+                .chain(self.date_formats.iter().cloned())
+                .collect(),
+            column_history_format:
+                "MSG-ID:<message-id>|REFS:<references>|THREAD:<thread-id>|IN-REPLY-TO:<parent-id>|FWD:true|EXTERNAL:true|CONV-INDEX:<conversation-index>"
+                    .to_string(),
+        };
+
+        self.to_js(&schema)
+    }
+}
@@ -0,0 +1,793 @@
+use crate::paging::PageRequest;
+use crate::languages::{self, Language, LanguageOptions};
+use crate::{custodians, EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Datelike, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+// Positional inverted index over subject and body text. Built on first search
+// and dropped whenever the loaded emails change.
+#[derive(Debug, Default)]
+pub(crate) struct SearchIndex {
+    // term -> email index -> token positions
+    postings: HashMap<String, Postings>,
+    doc_count: usize,
+    // email id -> index into the emails the index was built from
+    doc_ids: HashMap<String, usize>,
+    // Detected language of each email, by index
+    doc_languages: Vec<Language>,
+    // See LanguageOptions::stem_all_terms
+    stem_all_terms: bool,
+    // Indexed terms each wildcard or stem query stands for; finding them
+    // scans the whole vocabulary, so it is done once per query rather than
+    // for every email spans are asked for
+    expansions: RefCell<HashMap<Query, Rc<Expansion>>>,
+}
+
+// email index -> token positions of one term
+type Postings = HashMap<usize, Vec<u32>>;
+
+// Indexed terms, each with the one language whose emails it applies to where
+// that matters
+type Expansion = Vec<(String, Option<Language>)>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Query {
+    Term(String),
+    Phrase(Vec<String>),
+    // Lowercased pattern with `*` (any run of characters) and `?` (one character)
+    Wildcard(String),
+    // Matches every indexed term sharing the word's stem in the language of
+    // the email it occurs in
+    Stem(String),
+    // Both sides within N words of each other, either order
+    Near(Box<Query>, Box<Query>, u32),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub query: String,
+    pub total_hits: usize,
+    // The requested page of hits, in load order
+    pub email_ids: Vec<String>,
+    pub offset: usize,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchFacets {
+    pub query: String,
+    pub total_hits: usize,
+    pub thread_count: usize,
+    pub threads: Vec<FacetCount>,
+    pub custodians: Vec<FacetCount>,
+    // Senders and recipients, mapped addresses counted under their person
+    #[serde(default)]
+    pub participants: Vec<FacetCount>,
+    pub date_bucket: String,
+    // Chronological, unlike the other facets which are ordered by count
+    pub dates: Vec<FacetCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HitOffset {
+    // "subject" or "full_text"
+    pub field: String,
+    // UTF-16 code units, so they can be used directly as JS string indices
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub email_id: String,
+    pub offsets: Vec<HitOffset>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Full-text search over subject and body. Terms are ANDed by default;
+    /// supports OR, NOT, parentheses, "quoted phrases", `w/N` proximity,
+    /// `*`/`?` wildcards and `term~` stemming. `page` optionally limits the
+    /// hits returned; see `PageRequest`.
+    #[wasm_bindgen]
+    pub fn search(&self, query: &str, page: JsValue) -> Result<JsValue, JsValue> {
+        let hits = self.run_query(query)?;
+        let page = PageRequest::parse(page)?.apply(hits, |&i| self.emails[i].id.as_str())?;
+        let results = SearchResults {
+            query: query.to_string(),
+            total_hits: page.total,
+            email_ids: page.items.iter().map(|&i| self.emails[i].id.clone()).collect(),
+            offset: page.offset,
+            next_cursor: page.next_cursor,
+        };
+        self.to_js(&results)
+    }
+
+    /// Hit counts for `query` broken down by thread, custodian and date.
+    /// `date_bucket` is "day", "week", "month" (default) or "year".
+    #[wasm_bindgen]
+    pub fn search_facets(&self, query: &str, date_bucket: Option<String>) -> Result<JsValue, JsValue> {
+        let date_bucket = date_bucket.unwrap_or_else(|| "month".to_string());
+        if !matches!(date_bucket.as_str(), "day" | "week" | "month" | "year") {
+            return Err(JsValue::from_str(&format!("Unknown date bucket: {}", date_bucket)));
+        }
+
+        let hits = self.run_query(query)?;
+        let mut threads: IndexMap<String, usize> = IndexMap::new();
+        let mut custodians: IndexMap<String, usize> = IndexMap::new();
+        let mut participants: IndexMap<String, usize> = IndexMap::new();
+        let mut dates: IndexMap<String, usize> = IndexMap::new();
+
+        for &i in &hits {
+            let email = &self.emails[i];
+            if let Some(thread_id) = self.thread_key(email) {
+                *threads.entry(thread_id).or_default() += 1;
+            }
+            for custodian in custodians::custodians_of(email) {
+                *custodians.entry(custodian.to_string()).or_default() += 1;
+            }
+            let identities: BTreeSet<String> = std::iter::once(&email.from)
+                .chain(&email.to)
+                .chain(&email.cc)
+                .chain(&email.bcc)
+                .flat_map(|address| self.identity_map.identities(address))
+                .collect();
+            for identity in identities {
+                *participants.entry(identity).or_default() += 1;
+            }
+            *dates.entry(date_bucket_label(email.date_sent, &date_bucket)).or_default() += 1;
+        }
+        dates.sort_keys();
+
+        let facets = SearchFacets {
+            query: query.to_string(),
+            total_hits: hits.len(),
+            thread_count: threads.len(),
+            threads: by_count(threads),
+            custodians: by_count(custodians),
+            participants: by_count(participants),
+            date_bucket,
+            dates: dates.into_iter().map(|(value, count)| FacetCount { value, count }).collect(),
+        };
+        self.to_js(&facets)
+    }
+
+    /// Stores a named query; it is validated now and kept in the state snapshot.
+    #[wasm_bindgen]
+    pub fn save_search(&mut self, name: &str, query: &str) -> Result<(), JsValue> {
+        if name.trim().is_empty() {
+            return Err(JsValue::from_str("Saved search name is empty"));
+        }
+        parse_query(query).map_err(|e| JsValue::from_str(&e))?;
+        self.saved_searches.insert(name.to_string(), query.to_string());
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn run_saved_search(&self, name: &str, page: JsValue) -> Result<JsValue, JsValue> {
+        let query = self
+            .saved_searches
+            .get(name)
+            .ok_or_else(|| JsValue::from_str(&format!("Saved search not found: {}", name)))?;
+        self.search(query, page)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_saved_searches(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.saved_searches)
+    }
+
+    #[wasm_bindgen]
+    pub fn delete_saved_search(&mut self, name: &str) -> bool {
+        self.saved_searches.shift_remove(name).is_some()
+    }
+
+    /// Match offsets for highlighting, for every hit or just `email_id`.
+    #[wasm_bindgen]
+    pub fn get_search_hits(&self, query: &str, email_id: Option<String>) -> Result<JsValue, JsValue> {
+        let parsed = parse_query(query).map_err(|e| JsValue::from_str(&e))?;
+        let index = self.search_index();
+        let hits: Vec<SearchHit> = index
+            .evaluate(&parsed)
+            .into_iter()
+            .filter(|&doc| email_id.as_deref().is_none_or(|id| self.emails[doc].id == id))
+            .filter(|&doc| self.in_scope(&self.emails[doc]))
+            .map(|doc| SearchHit {
+                email_id: self.emails[doc].id.clone(),
+                offsets: hit_offsets(&self.emails[doc], &index.spans(&parsed, doc)),
+            })
+            .collect();
+        self.to_js(&hits)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn search_index(&self) -> &SearchIndex {
+        self.search_index.get_or_init(|| SearchIndex::build(&self.emails, &self.language_options))
+    }
+
+    // Indices into `self.emails` matching the query, in load order
+    pub(crate) fn run_query(&self, query: &str) -> Result<Vec<usize>, JsValue> {
+        let parsed = parse_query(query).map_err(|e| JsValue::from_str(&e))?;
+        Ok(self
+            .search_index()
+            .evaluate(&parsed)
+            .into_iter()
+            .filter(|&doc| self.in_scope(&self.emails[doc]))
+            .collect())
+    }
+}
+
+impl SearchIndex {
+    pub(crate) fn build(emails: &[EmailMessage], options: &LanguageOptions) -> SearchIndex {
+        let mut index = SearchIndex {
+            doc_count: emails.len(),
+            stem_all_terms: options.stem_all_terms,
+            ..Default::default()
+        };
+        for (doc, email) in emails.iter().enumerate() {
+            index.doc_ids.entry(email.id.clone()).or_insert(doc);
+            let text = indexed_text(email);
+            index.doc_languages.push(languages::detect(&text));
+            for (position, (term, _)) in tokenize(&text).into_iter().enumerate() {
+                index
+                    .postings
+                    .entry(term)
+                    .or_default()
+                    .entry(doc)
+                    .or_default()
+                    .push(position as u32);
+            }
+        }
+        if options.split_compounds {
+            index.split_compounds();
+        }
+        index
+    }
+
+    // Indexes the parts of German compounds at the compound's positions, so
+    // "vertrag" finds "Kaufvertrag". Only parts that are words elsewhere in
+    // the corpus count, which keeps "Bearbeitung" from yielding "beat".
+    fn split_compounds(&mut self) {
+        let mut parts: Vec<(String, usize, Vec<u32>)> = Vec::new();
+        for (term, docs) in &self.postings {
+            let german: Vec<(&usize, &Vec<u32>)> =
+                docs.iter().filter(|(&doc, _)| self.doc_languages[doc] == Language::German).collect();
+            if german.is_empty() {
+                continue;
+            }
+            if let Some(split) = languages::split_compound(term, |word| self.postings.contains_key(word)) {
+                for part in split {
+                    parts.extend(german.iter().map(|(&doc, positions)| (part.clone(), doc, positions.to_vec())));
+                }
+            }
+        }
+        for (part, doc, positions) in parts {
+            let existing = self.postings.entry(part).or_default().entry(doc).or_default();
+            existing.extend(positions);
+            existing.sort_unstable();
+            existing.dedup();
+        }
+    }
+
+    pub(crate) fn language_of(&self, doc: usize) -> Language {
+        self.doc_languages.get(doc).copied().unwrap_or_default()
+    }
+
+    pub(crate) fn doc_of(&self, email_id: &str) -> Option<usize> {
+        self.doc_ids.get(email_id).copied()
+    }
+
+    pub(crate) fn evaluate(&self, query: &Query) -> BTreeSet<usize> {
+        match query {
+            Query::Term(_) | Query::Wildcard(_) | Query::Stem(_) => self
+                .expand(query)
+                .into_iter()
+                .flat_map(|(docs, language)| {
+                    docs.keys().copied().filter(move |&doc| language.is_none_or(|l| self.language_of(doc) == l))
+                })
+                .collect(),
+            Query::Phrase(_) | Query::Near(..) => self
+                .candidates(query)
+                .into_iter()
+                .filter(|&doc| !self.spans(query, doc).is_empty())
+                .collect(),
+            Query::And(parts) => {
+                let mut positive = parts.iter().filter(|q| !matches!(q, Query::Not(_)));
+                let mut result = match positive.next() {
+                    Some(first) => self.evaluate(first),
+                    None => (0..self.doc_count).collect(),
+                };
+                for part in parts {
+                    match part {
+                        Query::Not(inner) => {
+                            let excluded = self.evaluate(inner);
+                            result.retain(|d| !excluded.contains(d));
+                        }
+                        _ => {
+                            let matched = self.evaluate(part);
+                            result.retain(|d| matched.contains(d));
+                        }
+                    }
+                }
+                result
+            }
+            Query::Or(parts) => parts.iter().flat_map(|q| self.evaluate(q)).collect(),
+            Query::Not(inner) => {
+                let excluded = self.evaluate(inner);
+                (0..self.doc_count).filter(|d| !excluded.contains(d)).collect()
+            }
+        }
+    }
+
+    /// Token spans (first and last position, inclusive) where the positive
+    /// parts of `query` match inside `doc`. Used for proximity and highlighting.
+    pub(crate) fn spans(&self, query: &Query, doc: usize) -> Vec<(u32, u32)> {
+        let mut spans: Vec<(u32, u32)> = match query {
+            Query::Term(_) | Query::Wildcard(_) | Query::Stem(_) => self
+                .expand(query)
+                .into_iter()
+                .filter(|(_, language)| language.is_none_or(|l| self.language_of(doc) == l))
+                .filter_map(|(docs, _)| docs.get(&doc))
+                .flatten()
+                .map(|&p| (p, p))
+                .collect(),
+            Query::Phrase(terms) => {
+                let positions = |term: &String| self.postings.get(term).and_then(|docs| docs.get(&doc));
+                match terms.first().and_then(positions) {
+                    Some(starts) => starts
+                        .iter()
+                        .filter(|&&start| {
+                            terms
+                                .iter()
+                                .enumerate()
+                                .skip(1)
+                                .all(|(offset, term)| positions(term).is_some_and(|p| p.contains(&(start + offset as u32))))
+                        })
+                        .map(|&start| (start, start + terms.len() as u32 - 1))
+                        .collect(),
+                    None => Vec::new(),
+                }
+            }
+            Query::Near(left, right, distance) => {
+                let left = self.spans(left, doc);
+                let right = self.spans(right, doc);
+                let mut near = Vec::new();
+                for &a in &left {
+                    for &b in &right {
+                        let gap = if a.0 > b.1 { a.0 - b.1 } else { b.0.saturating_sub(a.1) };
+                        if gap <= *distance {
+                            near.push(a);
+                            near.push(b);
+                        }
+                    }
+                }
+                near
+            }
+            Query::And(parts) | Query::Or(parts) => parts.iter().flat_map(|q| self.spans(q, doc)).collect(),
+            Query::Not(_) => Vec::new(),
+        };
+        spans.sort_unstable();
+        spans.dedup();
+        spans
+    }
+
+    // Postings for every indexed term a term-level query stands for, each
+    // with the one language whose emails it applies to where that matters
+    fn expand(&self, query: &Query) -> Vec<(&Postings, Option<Language>)> {
+        if let (Query::Term(term), false) = (query, self.stem_all_terms) {
+            return self.postings.get(term).into_iter().map(|docs| (docs, None)).collect();
+        }
+        self.expansion(query)
+            .iter()
+            .filter_map(|(term, language)| Some((self.postings.get(term)?, *language)))
+            .collect()
+    }
+
+    fn expansion(&self, query: &Query) -> Rc<Expansion> {
+        if let Some(expansion) = self.expansions.borrow().get(query) {
+            return expansion.clone();
+        }
+        let expansion: Expansion = match query {
+            Query::Term(word) => return self.expansion(&Query::Stem(word.clone())),
+            Query::Wildcard(pattern) => self
+                .postings
+                .keys()
+                .filter(|term| wildcard_match(pattern, term))
+                .map(|term| (term.clone(), None))
+                .collect(),
+            Query::Stem(word) => {
+                let present: HashSet<Language> = self.doc_languages.iter().copied().collect();
+                let mut expanded = Vec::new();
+                for language in present {
+                    let stemmed = languages::stem(language, word);
+                    expanded.extend(
+                        self.postings
+                            .keys()
+                            .filter(|term| languages::stem(language, term) == stemmed)
+                            .map(|term| (term.clone(), Some(language))),
+                    );
+                }
+                expanded
+            }
+            _ => Vec::new(),
+        };
+        let expansion = Rc::new(expansion);
+        self.expansions.borrow_mut().insert(query.clone(), expansion.clone());
+        expansion
+    }
+
+    // Docs that could satisfy a positional query, before checking positions
+    fn candidates(&self, query: &Query) -> BTreeSet<usize> {
+        match query {
+            Query::Phrase(terms) => match terms.first() {
+                Some(first) => self.evaluate(&Query::Term(first.clone())),
+                None => BTreeSet::new(),
+            },
+            Query::Near(left, right, _) => {
+                let right = self.candidates(right);
+                self.candidates(left).into_iter().filter(|d| right.contains(d)).collect()
+            }
+            _ => self.evaluate(query),
+        }
+    }
+}
+
+pub(crate) fn indexed_text(email: &EmailMessage) -> String {
+    format!("{}\n{}", email.subject, email.full_text)
+}
+
+// Maps token spans back to the subject or body they came from
+pub(crate) fn hit_offsets(email: &EmailMessage, spans: &[(u32, u32)]) -> Vec<HitOffset> {
+    let text = indexed_text(email);
+    let tokens = tokenize(&text);
+    let body_start = email.subject.len() + 1;
+
+    spans
+        .iter()
+        .filter_map(|&(first, last)| {
+            let start = tokens.get(first as usize)?.1 .0;
+            let end = tokens.get(last as usize)?.1 .1;
+            let (field, base) = if start < body_start {
+                ("subject", 0)
+            } else {
+                ("full_text", body_start)
+            };
+            let field_text = &text[base..];
+            Some(HitOffset {
+                field: field.to_string(),
+                start: utf16_len(&field_text[..start - base]),
+                end: utf16_len(&field_text[..end - base]),
+                text: text[start..end].to_string(),
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+/// Lowercased alphanumeric tokens with their byte offsets in `text`. Chinese,
+/// Japanese and Korean, written without spaces, become overlapping character
+/// pairs ("東京都" -> "東京", "京都"), so any word in them is a phrase of pairs.
+pub(crate) fn tokenize(text: &str) -> Vec<(String, (usize, usize))> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut cjk: Vec<(usize, usize)> = Vec::new();
+    for (i, c) in text.char_indices() {
+        let is_cjk = languages::is_cjk(c);
+        if let (true, Some(s)) = (is_cjk || !c.is_alphanumeric(), start) {
+            tokens.push((text[s..i].to_lowercase(), (s, i)));
+            start = None;
+        }
+        if !is_cjk && !cjk.is_empty() {
+            push_cjk_pairs(text, &mut cjk, &mut tokens);
+        }
+        if is_cjk {
+            cjk.push((i, i + c.len_utf8()));
+        } else if c.is_alphanumeric() && start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        tokens.push((text[s..].to_lowercase(), (s, text.len())));
+    }
+    push_cjk_pairs(text, &mut cjk, &mut tokens);
+    tokens
+}
+
+// A run of CJK characters as overlapping pairs, or the lone character
+fn push_cjk_pairs(text: &str, run: &mut Vec<(usize, usize)>, tokens: &mut Vec<(String, (usize, usize))>) {
+    match run.as_slice() {
+        [] => {}
+        [(s, e)] => tokens.push((text[*s..*e].to_string(), (*s, *e))),
+        chars => {
+            for pair in chars.windows(2) {
+                let (s, e) = (pair[0].0, pair[1].1);
+                tokens.push((text[s..e].to_string(), (s, e)));
+            }
+        }
+    }
+    run.clear();
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum QueryToken {
+    Word(String),
+    Phrase(String),
+    Open,
+    Close,
+}
+
+fn lex_query(query: &str) -> Vec<QueryToken> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' || c == ')' {
+            chars.next();
+            tokens.push(if c == '(' { QueryToken::Open } else { QueryToken::Close });
+        } else if c == '"' {
+            chars.next();
+            let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+            tokens.push(QueryToken::Phrase(phrase));
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(QueryToken::Word(word));
+        }
+    }
+    tokens
+}
+
+pub(crate) fn parse_query(query: &str) -> Result<Query, String> {
+    let tokens = lex_query(query);
+    if tokens.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    let mut parser = QueryParser { tokens, pos: 0 };
+    let parsed = parser.parse_or()?;
+    if parser.pos < parser.tokens.len() {
+        return Err("Unbalanced ')' in search query".to_string());
+    }
+    Ok(parsed)
+}
+
+// Precedence, loosest first: OR, AND (explicit or implied by adjacency), NOT, w/N
+struct QueryParser {
+    tokens: Vec<QueryToken>,
+    pos: usize,
+}
+
+impl QueryParser {
+    fn peek_operator(&self, operator: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(QueryToken::Word(w)) if w == operator)
+    }
+
+    fn parse_or(&mut self) -> Result<Query, String> {
+        let mut parts = vec![self.parse_and()?];
+        while self.peek_operator("OR") {
+            self.pos += 1;
+            parts.push(self.parse_and()?);
+        }
+        Ok(if parts.len() == 1 { parts.remove(0) } else { Query::Or(parts) })
+    }
+
+    fn parse_and(&mut self) -> Result<Query, String> {
+        let mut parts = vec![self.parse_unary()?];
+        loop {
+            if self.peek_operator("AND") {
+                self.pos += 1;
+            } else if self.pos >= self.tokens.len()
+                || self.peek_operator("OR")
+                || self.tokens[self.pos] == QueryToken::Close
+            {
+                break;
+            }
+            parts.push(self.parse_unary()?);
+        }
+        Ok(if parts.len() == 1 { parts.remove(0) } else { Query::And(parts) })
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, String> {
+        if self.peek_operator("NOT") {
+            self.pos += 1;
+            return Ok(Query::Not(Box::new(self.parse_unary()?)));
+        }
+        let mut query = self.parse_primary()?;
+        while let Some(distance) = self.peek_proximity() {
+            self.pos += 1;
+            query = Query::Near(Box::new(query), Box::new(self.parse_primary()?), distance);
+        }
+        Ok(query)
+    }
+
+    // dtSearch-style `w/N`
+    fn peek_proximity(&self) -> Option<u32> {
+        match self.tokens.get(self.pos) {
+            Some(QueryToken::Word(w)) => w
+                .strip_prefix("w/")
+                .or_else(|| w.strip_prefix("W/"))
+                .and_then(|n| n.parse().ok()),
+            _ => None,
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Query, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| "Search query ends unexpectedly".to_string())?;
+        self.pos += 1;
+        match token {
+            QueryToken::Open => {
+                let inner = self.parse_or()?;
+                if self.tokens.get(self.pos) != Some(&QueryToken::Close) {
+                    return Err("Missing ')' in search query".to_string());
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            QueryToken::Close => Err("Unexpected ')' in search query".to_string()),
+            QueryToken::Phrase(text) => terms_query(&text, true),
+            QueryToken::Word(word) => terms_query(&word, false),
+        }
+    }
+}
+
+// A word like "e-mail" tokenizes to several terms and is matched as a phrase.
+// Words with `*`/`?` are wildcards and a trailing `~` asks for stemming.
+fn terms_query(text: &str, phrase: bool) -> Result<Query, String> {
+    if !phrase && text.contains(['*', '?']) {
+        let pattern = text.to_lowercase();
+        if !pattern.chars().any(char::is_alphanumeric) {
+            return Err(format!("Wildcard needs at least one letter or digit: {}", text));
+        }
+        return Ok(Query::Wildcard(pattern));
+    }
+    if let Some(word) = text.strip_suffix('~').filter(|_| !phrase) {
+        if let [(term, _)] = tokenize(word).as_slice() {
+            return Ok(Query::Stem(term.clone()));
+        }
+    }
+
+    let mut terms: Vec<String> = tokenize(text).into_iter().map(|(t, _)| t).collect();
+    if terms.is_empty() {
+        return Err(format!("Nothing searchable in: {}", text));
+    }
+    // A single CJK character is indexed inside the pairs around it
+    if let [term] = terms.as_slice() {
+        if term.chars().count() == 1 && term.chars().all(languages::is_cjk) {
+            return Ok(Query::Wildcard(format!("*{}*", term)));
+        }
+    }
+    Ok(if terms.len() == 1 && !phrase {
+        Query::Term(terms.remove(0))
+    } else {
+        Query::Phrase(terms)
+    })
+}
+
+pub(crate) fn wildcard_match(pattern: &str, term: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let term: Vec<char> = term.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < term.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == term[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Light suffix-stripping stemmer for English, enough to fold plurals and
+/// common verb forms ("approve~" finds approved, approves, approving).
+pub(crate) fn stem(term: &str) -> String {
+    const SUFFIXES: &[(&str, &str)] = &[
+        ("ational", "ate"),
+        ("ations", "ate"),
+        ("ation", "ate"),
+        ("iveness", "ive"),
+        ("fulness", "ful"),
+        ("ements", ""),
+        ("ement", ""),
+        ("ments", ""),
+        ("ment", ""),
+        ("ingly", ""),
+        ("edly", ""),
+        ("ings", ""),
+        ("ing", ""),
+        ("ies", "y"),
+        ("ied", "y"),
+        ("ers", ""),
+        ("er", ""),
+        ("es", ""),
+        ("ed", ""),
+        ("ly", ""),
+        ("s", ""),
+    ];
+    if term.ends_with("ss") {
+        return term.to_string();
+    }
+    for (suffix, replacement) in SUFFIXES {
+        if let Some(base) = term.strip_suffix(suffix) {
+            if base.chars().count() < 3 {
+                continue;
+            }
+            let mut stemmed = format!("{}{}", base, replacement);
+            // approv(e) / approv(ed): drop a trailing e so both forms meet
+            if stemmed.ends_with('e') && stemmed.chars().count() > 3 {
+                stemmed.pop();
+            }
+            // stopp(ed) -> stop
+            let chars: Vec<char> = stemmed.chars().collect();
+            if let [.., a, b] = chars.as_slice() {
+                if a == b && !"lsz".contains(*b) && !"aeiou".contains(*b) {
+                    stemmed.pop();
+                }
+            }
+            return stemmed;
+        }
+    }
+    let mut stemmed = term.to_string();
+    if stemmed.ends_with('e') && stemmed.chars().count() > 3 {
+        stemmed.pop();
+    }
+    stemmed
+}
+
+fn date_bucket_label(date: DateTime<Utc>, bucket: &str) -> String {
+    match bucket {
+        "day" => date.format("%Y-%m-%d").to_string(),
+        "week" => {
+            let week = date.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        }
+        "year" => date.format("%Y").to_string(),
+        _ => date.format("%Y-%m").to_string(),
+    }
+}
+
+fn by_count(counts: IndexMap<String, usize>) -> Vec<FacetCount> {
+    let mut facets: Vec<FacetCount> = counts
+        .into_iter()
+        .map(|(value, count)| FacetCount { value, count })
+        .collect();
+    facets.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    facets
+}
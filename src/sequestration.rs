@@ -0,0 +1,119 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+const PLACEHOLDER_SUBJECT: &str = "[Sequestered]";
+
+/// A document withdrawn from review, e.g. clawed back as privileged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sequestration {
+    pub email_id: String,
+    pub beg_bates: String,
+    pub reason: String,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub sequestered_at: DateTime<Utc>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Withdraws emails from review: they drop out of search, stats, reports
+    /// and exports, and thread trees show a placeholder in their place (Bates
+    /// and threading headers only, flagged `sequestered`) so replies still
+    /// hang together. Sequestrations are keyed by email id, so `clear()`
+    /// drops them with the dataset. Returns how many emails were newly
+    /// sequestered; unknown ids are an error.
+    #[wasm_bindgen]
+    pub fn sequester_emails(&mut self, ids: Vec<String>, reason: &str) -> Result<usize, JsValue> {
+        let reason = reason.trim();
+        if reason.is_empty() {
+            return Err(JsValue::from_str("A reason is required to sequester emails"));
+        }
+        let unknown: Vec<&str> = ids.iter().filter(|id| self.email_by_id(id).is_none()).map(String::as_str).collect();
+        if !unknown.is_empty() {
+            return Err(JsValue::from_str(&format!("Emails not found: {}", unknown.join(", "))));
+        }
+
+        let now = Utc::now();
+        let mut added = Vec::new();
+        for id in &ids {
+            if self.sequestered.contains_key(id) {
+                continue;
+            }
+            let beg_bates = self.email_by_id(id).map(|e| e.beg_bates.clone()).unwrap_or_default();
+            let sequestration = Sequestration {
+                email_id: id.clone(),
+                beg_bates,
+                reason: reason.to_string(),
+                sequestered_at: now,
+            };
+            self.sequestered.insert(id.clone(), sequestration);
+            added.push(id.as_str());
+        }
+
+        console_log!("Sequestered {} emails", added.len());
+        self.audit("sequester_emails", format!("\"{}\": {}", reason, added.join(", ")));
+        self.regroup_after_sequestration();
+        Ok(added.len())
+    }
+
+    /// Returns sequestered emails to review. Returns how many were released.
+    #[wasm_bindgen]
+    pub fn release_sequestered(&mut self, ids: Vec<String>) -> usize {
+        let released: Vec<&str> = ids
+            .iter()
+            .filter(|id| self.sequestered.shift_remove(id.as_str()).is_some())
+            .map(String::as_str)
+            .collect();
+        let count = released.len();
+        console_log!("Released {} sequestered emails", count);
+        self.audit("release_sequestered", released.join(", "));
+        self.regroup_after_sequestration();
+        count
+    }
+
+    /// Every sequestration in force, oldest first.
+    #[wasm_bindgen]
+    pub fn get_sequestered(&self) -> Result<JsValue, JsValue> {
+        let sequestered: Vec<&Sequestration> = self.sequestered.values().collect();
+        self.to_js(&sequestered)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn is_sequestered(&self, email_id: &str) -> bool {
+        self.sequestered.contains_key(email_id)
+    }
+
+    /// The copy of an email a thread holds: the email itself, or for a
+    /// sequestered one a placeholder carrying only what threading needs.
+    pub(crate) fn thread_copy(&self, email: &EmailMessage) -> EmailMessage {
+        if !self.is_sequestered(&email.id) {
+            return email.clone();
+        }
+        EmailMessage {
+            id: email.id.clone(),
+            message_id: email.message_id.clone(),
+            in_reply_to: email.in_reply_to.clone(),
+            references: email.references.clone(),
+            thread_id: email.thread_id.clone(),
+            conversation_index: email.conversation_index.clone(),
+            gmail_thread_id: email.gmail_thread_id.clone(),
+            subject: PLACEHOLDER_SUBJECT.to_string(),
+            date_sent: email.date_sent,
+            date_created: email.date_sent,
+            date_last_modified: email.date_sent,
+            beg_bates: email.beg_bates.clone(),
+            end_bates: email.end_bates.clone(),
+            hash: email.hash.clone(),
+            production: email.production.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn regroup_after_sequestration(&mut self) {
+        if !self.threads.is_empty() {
+            self.group_by_threads();
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadShape {
+    pub thread_id: String,
+    pub total_emails: usize,
+    pub root_count: usize,
+    // Node count at each depth, roots first
+    pub level_counts: Vec<usize>,
+    pub max_depth: usize,
+    pub max_width: usize,
+    // Shallowest level with max_width nodes
+    pub widest_depth: usize,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Node counts per depth level of a thread's tree, without building it,
+    /// so a layout can be chosen and sized before `build_thread_tree`.
+    #[wasm_bindgen]
+    pub fn get_thread_shape(&self, thread_id: &str) -> Result<JsValue, JsValue> {
+        let shape = self.thread_shape(thread_id)?;
+        self.to_js(&shape)
+    }
+}
+
+impl EmailThreadProcessor {
+    fn thread_shape(&self, thread_id: &str) -> Result<ThreadShape, JsValue> {
+        let emails = self
+            .threads
+            .get(thread_id)
+            .ok_or_else(|| JsValue::from_str("Thread not found"))?;
+
+        let level_counts = self.level_counts(emails);
+        let max_width = level_counts.iter().copied().max().unwrap_or(0);
+        Ok(ThreadShape {
+            thread_id: thread_id.to_string(),
+            total_emails: emails.len(),
+            root_count: level_counts.first().copied().unwrap_or(0),
+            max_depth: level_counts.len().saturating_sub(1),
+            max_width,
+            widest_depth: level_counts.iter().position(|&c| c == max_width).unwrap_or(0),
+            level_counts,
+        })
+    }
+
+    pub(crate) fn level_counts(&self, emails: &[EmailMessage]) -> Vec<usize> {
+        self.outline(emails).level_counts
+    }
+
+    /// Node count per depth of a thread's tree, roots first, and its branch
+    /// count, from the parent links alone. Same roots as thread_tree; emails
+    /// only reachable through a parent cycle are left out there and here.
+    pub(crate) fn outline(&self, emails: &[EmailMessage]) -> Outline {
+        let parents = self.resolve_parents(emails);
+        let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (child, parent) in &parents {
+            children.entry(parent.as_str()).or_default().push(child.as_str());
+        }
+
+        let mut level: Vec<&str> = emails
+            .iter()
+            .filter(|e| !parents.contains_key(&e.id))
+            .map(|e| e.id.as_str())
+            .collect();
+        let mut level_counts = Vec::new();
+        let mut branch_count = 0;
+        while !level.is_empty() {
+            level_counts.push(level.len());
+            branch_count += level
+                .iter()
+                .map(|id| children.get(id).map_or(0, Vec::len))
+                .filter(|&n| n > 1)
+                .sum::<usize>();
+            level = level
+                .iter()
+                .flat_map(|id| children.get(id).into_iter().flatten().copied())
+                .collect();
+        }
+        Outline {
+            level_counts,
+            branch_count,
+        }
+    }
+}
+
+/// The shape of a thread's tree without building it.
+pub(crate) struct Outline {
+    pub level_counts: Vec<usize>,
+    // Children of nodes with more than one, summed, as in ThreadStats
+    pub branch_count: usize,
+}
+
+impl Outline {
+    pub(crate) fn max_depth(&self) -> usize {
+        self.level_counts.len().saturating_sub(1)
+    }
+}
@@ -0,0 +1,316 @@
+use crate::audit::AuditEntry;
+use crate::confidentiality::default_confidentiality_map;
+use crate::config::DedupPolicy;
+use crate::display::DisplayTimezone;
+use crate::exclusion::ExclusionRules;
+use crate::filter::GlobalFilter;
+use crate::hot_documents::ScoringConfig;
+use crate::identity_map::IdentityMap;
+use crate::languages::LanguageOptions;
+use crate::mass_mail::MassMailOptions;
+use crate::reattach::Reattachment;
+use crate::response_times::BusinessCalendar;
+use crate::sequestration::Sequestration;
+use crate::stop_words::StopWordConfig;
+use crate::thread_locks::ThreadLock;
+use crate::topic_clusters::TopicModel;
+use crate::{filetypes::TypeFilter, EmailMessage, EmailThreadProcessor, LoadReport, ThreadingMode};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+// Snapshot layout: MAGIC, a little-endian u32 format version, then the JSON
+// payload. Snapshots written before the header existed are bare JSON and are
+// treated as version 0.
+const MAGIC: &[u8; 6] = b"ETSNAP";
+const HEADER_LEN: usize = MAGIC.len() + 4;
+pub(crate) const SNAPSHOT_VERSION: u32 = 1;
+
+// Everything needed to resume work on a processed corpus. Threads are stored as
+// email ids and rebuilt from `emails` on load. Fields added in later versions
+// must be `#[serde(default)]` or come with a migration step.
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    #[serde(default)]
+    created_by: String,
+    emails: Vec<EmailMessage>,
+    threads: IndexMap<String, Vec<String>>,
+    threading_mode: ThreadingMode,
+    load_report: LoadReport,
+    column_mapping: IndexMap<String, String>,
+    date_formats: Vec<String>,
+    include_singletons: bool,
+    saved_searches: IndexMap<String, String>,
+    #[serde(default)]
+    thread_labels: IndexMap<String, Vec<String>>,
+    #[serde(default)]
+    type_filter: TypeFilter,
+    #[serde(default)]
+    exclusion_rules: ExclusionRules,
+    #[serde(default)]
+    internal_domains: Vec<String>,
+    #[serde(default)]
+    global_filter: GlobalFilter,
+    #[serde(default)]
+    audit_log: Vec<AuditEntry>,
+    #[serde(default)]
+    subject_fallback: bool,
+    #[serde(default)]
+    dedup_policy: DedupPolicy,
+    #[serde(default)]
+    deterministic: bool,
+    #[serde(default)]
+    stable_thread_ids: bool,
+    #[serde(default)]
+    thread_aliases: HashMap<String, String>,
+    #[serde(default = "default_confidentiality_map")]
+    confidentiality_map: IndexMap<String, String>,
+    #[serde(default)]
+    identity_map: IdentityMap,
+    #[serde(default)]
+    scoring_config: ScoringConfig,
+    #[serde(default)]
+    topic_model: Option<TopicModel>,
+    #[serde(default)]
+    reattachments: IndexMap<String, Reattachment>,
+    #[serde(default)]
+    production: Option<String>,
+    #[serde(default)]
+    append_loads: bool,
+    #[serde(default)]
+    sequestered: IndexMap<String, Sequestration>,
+    #[serde(default)]
+    display_timezone: DisplayTimezone,
+    #[serde(default)]
+    business_calendar: BusinessCalendar,
+    #[serde(default)]
+    stop_words: StopWordConfig,
+    #[serde(default)]
+    language_options: LanguageOptions,
+    #[serde(default)]
+    mass_mail: MassMailOptions,
+    #[serde(default)]
+    locked_threads: IndexMap<String, ThreadLock>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SnapshotError {
+    // Not a snapshot at all
+    InvalidFormat { message: String },
+    // Written by a newer crate than this one
+    UnsupportedVersion { found: u32, supported: u32 },
+    // Recognized version, but the payload does not match it
+    Corrupt { version: u32, message: String },
+}
+
+impl SnapshotError {
+    fn into_js(self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self).unwrap_or_else(|e| JsValue::from_str(&e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    pub version: u32,
+    pub current_version: u32,
+    pub needs_migration: bool,
+    pub created_by: String,
+    pub email_count: usize,
+    pub thread_count: usize,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Serializes the loaded corpus, threading and settings so a later session
+    /// can pick up where this one stopped via `load_state`.
+    #[wasm_bindgen]
+    pub fn save_state(&self) -> Result<Vec<u8>, JsValue> {
+        let snapshot = Snapshot {
+            created_by: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            emails: self.emails.clone(),
+            threads: self
+                .threads
+                .iter()
+                .map(|(id, emails)| (id.clone(), emails.iter().map(|e| e.id.clone()).collect()))
+                .collect(),
+            threading_mode: self.threading_mode,
+            load_report: self.load_report.clone(),
+            column_mapping: self.column_mapping.clone(),
+            date_formats: self.date_formats.clone(),
+            include_singletons: self.include_singletons,
+            saved_searches: self.saved_searches.clone(),
+            thread_labels: self.thread_labels.clone(),
+            type_filter: self.type_filter.clone(),
+            exclusion_rules: self.exclusion_rules.clone(),
+            internal_domains: self.internal_domains.clone(),
+            global_filter: self.global_filter.clone(),
+            audit_log: self.audit_log.clone(),
+            subject_fallback: self.subject_fallback,
+            dedup_policy: self.dedup_policy,
+            deterministic: self.deterministic,
+            stable_thread_ids: self.stable_thread_ids,
+            thread_aliases: self.thread_aliases.clone(),
+            confidentiality_map: self.confidentiality_map.clone(),
+            identity_map: self.identity_map.clone(),
+            scoring_config: self.scoring_config.clone(),
+            topic_model: self.topic_model.clone(),
+            reattachments: self.reattachments.clone(),
+            production: self.production.clone(),
+            append_loads: self.append_loads,
+            sequestered: self.sequestered.clone(),
+            display_timezone: self.display_timezone,
+            business_calendar: self.business_calendar.clone(),
+            stop_words: self.stop_words.clone(),
+            language_options: self.language_options.clone(),
+            mass_mail: self.mass_mail.clone(),
+            locked_threads: self.locked_threads.clone(),
+        };
+        let payload =
+            serde_json::to_vec(&snapshot).map_err(|e| JsValue::from_str(&format!("Error saving state: {}", e)))?;
+
+        let mut data = Vec::with_capacity(HEADER_LEN + payload.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        data.extend_from_slice(&payload);
+        Ok(data)
+    }
+
+    /// Reads a snapshot's header and counts without loading it.
+    #[wasm_bindgen]
+    pub fn get_snapshot_info(&self, data: &[u8]) -> Result<JsValue, JsValue> {
+        let (version, snapshot) = read_snapshot(data).map_err(SnapshotError::into_js)?;
+        let info = SnapshotInfo {
+            version,
+            current_version: SNAPSHOT_VERSION,
+            needs_migration: version < SNAPSHOT_VERSION,
+            created_by: snapshot.created_by,
+            email_count: snapshot.emails.len(),
+            thread_count: snapshot.threads.len(),
+        };
+        self.to_js(&info)
+    }
+
+    /// Restores a `save_state` snapshot, migrating older versions. Failures
+    /// are `SnapshotError` objects with a `kind` field.
+    #[wasm_bindgen]
+    pub fn load_state(&mut self, data: &[u8]) -> Result<usize, JsValue> {
+        console_log!("Loading state snapshot, length: {}", data.len());
+
+        let (version, snapshot) = read_snapshot(data).map_err(SnapshotError::into_js)?;
+        if version < SNAPSHOT_VERSION {
+            console_log!("Migrated snapshot from version {} to {}", version, SNAPSHOT_VERSION);
+        }
+
+        let by_id: HashMap<&str, &EmailMessage> = snapshot.emails.iter().map(|e| (e.id.as_str(), e)).collect();
+        let mut threads = IndexMap::new();
+        for (thread_id, ids) in &snapshot.threads {
+            let emails = ids
+                .iter()
+                .map(|id| {
+                    by_id
+                        .get(id.as_str())
+                        .map(|e| (*e).clone())
+                        .ok_or_else(|| {
+                            SnapshotError::Corrupt {
+                                version,
+                                message: format!("Thread {} references unknown email {}", thread_id, id),
+                            }
+                            .into_js()
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            threads.insert(thread_id.clone(), emails);
+        }
+
+        let count = snapshot.emails.len();
+        self.replace_emails(snapshot.emails);
+        self.threads = threads;
+        self.threading_mode = snapshot.threading_mode;
+        self.subject_fallback = snapshot.subject_fallback;
+        self.dedup_policy = snapshot.dedup_policy;
+        self.deterministic = snapshot.deterministic;
+        self.stable_thread_ids = snapshot.stable_thread_ids;
+        self.thread_aliases = snapshot.thread_aliases;
+        self.confidentiality_map = snapshot.confidentiality_map;
+        self.identity_map = snapshot.identity_map;
+        self.scoring_config = snapshot.scoring_config;
+        self.topic_model = snapshot.topic_model;
+        self.reattachments = snapshot.reattachments;
+        self.production = snapshot.production;
+        self.append_loads = snapshot.append_loads;
+        self.sequestered = snapshot.sequestered;
+        self.display_timezone = snapshot.display_timezone;
+        self.business_calendar = snapshot.business_calendar;
+        self.stop_words = snapshot.stop_words;
+        self.language_options = snapshot.language_options;
+        self.mass_mail = snapshot.mass_mail;
+        self.locked_threads = snapshot.locked_threads;
+        self.index_thread_locks();
+        self.refresh_thread_copies();
+        self.load_report = snapshot.load_report;
+        self.column_mapping = snapshot.column_mapping;
+        self.date_formats = snapshot.date_formats;
+        self.include_singletons = snapshot.include_singletons;
+        self.saved_searches = snapshot.saved_searches;
+        self.thread_labels = snapshot.thread_labels;
+        self.type_filter = snapshot.type_filter;
+        self.exclusion_rules = snapshot.exclusion_rules;
+        self.internal_domains = snapshot.internal_domains;
+        self.global_filter = snapshot.global_filter;
+        // The snapshot's history carries on, with the restore as its next entry
+        self.audit_log = snapshot.audit_log;
+        self.audit_load("load_state", format!("Snapshot version {} restored", version), &[("snapshot", data)]);
+
+        console_log!("Restored {} emails in {} threads", count, self.threads.len());
+        Ok(count)
+    }
+}
+
+fn read_snapshot(data: &[u8]) -> Result<(u32, Snapshot), SnapshotError> {
+    let (version, payload) = match data.strip_prefix(MAGIC.as_slice()) {
+        Some(rest) if rest.len() >= 4 => (u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]), &rest[4..]),
+        Some(_) => {
+            return Err(SnapshotError::InvalidFormat {
+                message: "Snapshot header is truncated".to_string(),
+            })
+        }
+        None if data.first() == Some(&b'{') => (0, data),
+        None => {
+            return Err(SnapshotError::InvalidFormat {
+                message: "Data is not an email thread snapshot".to_string(),
+            })
+        }
+    };
+
+    if version > SNAPSHOT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion {
+            found: version,
+            supported: SNAPSHOT_VERSION,
+        });
+    }
+
+    let corrupt = |e: serde_json::Error| SnapshotError::Corrupt {
+        version,
+        message: e.to_string(),
+    };
+    let value: serde_json::Value = serde_json::from_slice(payload).map_err(corrupt)?;
+    let snapshot = serde_json::from_value(migrate(version, value)).map_err(corrupt)?;
+    Ok((version, snapshot))
+}
+
+// Upgrades a payload one version at a time to SNAPSHOT_VERSION. Add a step
+// here whenever a change to `Snapshot` cannot be covered by serde defaults.
+fn migrate(version: u32, mut value: serde_json::Value) -> serde_json::Value {
+    if version < 1 {
+        // Version 0 was the headerless JSON of the first release; the payload
+        // only gained the optional `created_by` field
+        if let Some(object) = value.as_object_mut() {
+            object
+                .entry("created_by")
+                .or_insert_with(|| serde_json::Value::String("unknown (version 0)".to_string()));
+        }
+    }
+    value
+}
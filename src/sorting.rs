@@ -0,0 +1,91 @@
+use crate::{topics, EmailMessage, EmailThreadProcessor};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ThreadSortKey {
+    LastActivity,
+    StartDate,
+    Size,
+    Depth,
+    ParticipantCount,
+    Subject,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum SortValue {
+    Number(i64),
+    Text(String),
+}
+
+impl ThreadSortKey {
+    fn parse(name: &str) -> Result<ThreadSortKey, JsValue> {
+        Ok(match name {
+            "last_activity" => ThreadSortKey::LastActivity,
+            "start_date" => ThreadSortKey::StartDate,
+            "size" => ThreadSortKey::Size,
+            "depth" => ThreadSortKey::Depth,
+            "participant_count" => ThreadSortKey::ParticipantCount,
+            "subject" => ThreadSortKey::Subject,
+            _ => return Err(JsValue::from_str(&format!("Unknown thread sort: {}", name))),
+        })
+    }
+}
+
+impl EmailThreadProcessor {
+    /// Listed threads in the order `sort` asks for: "last_activity",
+    /// "start_date", "size", "depth", "participant_count" or "subject",
+    /// ascending, or descending with a leading `-` ("-last_activity" for most
+    /// recently active first). None keeps grouping order.
+    pub(crate) fn sorted_threads(&self, sort: Option<&str>) -> Result<Vec<(&String, &Vec<EmailMessage>)>, JsValue> {
+        let threads: Vec<(&String, &Vec<EmailMessage>)> = self.visible_threads().collect();
+        let Some(sort) = sort.map(str::trim).filter(|s| !s.is_empty()) else {
+            return Ok(threads);
+        };
+        let (descending, name) = match sort.strip_prefix('-') {
+            Some(name) => (true, name),
+            None => (false, sort),
+        };
+        let key = ThreadSortKey::parse(name)?;
+
+        let values: Vec<SortValue> = threads.iter().map(|(_, emails)| self.sort_value(key, emails)).collect();
+        let mut order: Vec<usize> = (0..threads.len()).collect();
+        // Stable, so ties keep grouping order in both directions
+        order.sort_by(|&a, &b| {
+            let ordering = values[a].cmp(&values[b]);
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        Ok(order.into_iter().map(|i| threads[i]).collect())
+    }
+
+    fn sort_value(&self, key: ThreadSortKey, emails: &[EmailMessage]) -> SortValue {
+        match key {
+            ThreadSortKey::LastActivity => {
+                SortValue::Number(emails.iter().map(|e| e.date_sent.timestamp()).max().unwrap_or_default())
+            }
+            ThreadSortKey::StartDate => {
+                SortValue::Number(emails.iter().map(|e| e.date_sent.timestamp()).min().unwrap_or_default())
+            }
+            ThreadSortKey::Size => SortValue::Number(emails.len() as i64),
+            ThreadSortKey::Depth => SortValue::Number(self.level_counts(emails).len().saturating_sub(1) as i64),
+            ThreadSortKey::ParticipantCount => {
+                let participants: HashSet<&str> = emails
+                    .iter()
+                    .flat_map(|e| std::iter::once(&e.from).chain(&e.to).chain(&e.cc))
+                    .map(|a| a.as_str())
+                    .collect();
+                SortValue::Number(participants.len() as i64)
+            }
+            ThreadSortKey::Subject => SortValue::Text(
+                emails
+                    .first()
+                    .map(|e| topics::normalize_subject(&e.subject))
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+}
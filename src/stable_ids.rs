@@ -0,0 +1,62 @@
+use crate::{audit, topics, EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+
+// Hex digits of the SHA-256 kept in a stable id
+const STABLE_ID_DIGITS: usize = 16;
+
+impl EmailThreadProcessor {
+    /// A content-derived id for every thread, keyed by its grouping key:
+    /// "T-" and a hash of the root's Message-ID, or of its normalized subject
+    /// when it has none, so reprocessing an updated corpus keeps the ids tags
+    /// and work product refer to. The root is the earliest email without a
+    /// parent among every loaded email with the thread's key, filtered out or
+    /// duplicate alike, so changing filters or the dedup policy does not
+    /// rename threads. Threads whose roots hash alike take "-2", "-3" and so
+    /// on in grouping order; locked threads keep the id they have.
+    pub(crate) fn stable_thread_ids(&self) -> HashMap<String, String> {
+        let mut unfiltered: HashMap<String, Vec<&EmailMessage>> = HashMap::new();
+        for email in &self.emails {
+            if let Some(key) = self.thread_key(email).filter(|key| self.threads.contains_key(key)) {
+                unfiltered.entry(key).or_default().push(email);
+            }
+        }
+
+        let mut taken: IndexMap<String, usize> = self.locked_threads.keys().map(|id| (id.clone(), 1)).collect();
+        let mut ids = HashMap::new();
+        for key in self.threads.keys() {
+            if self.locked_threads.contains_key(key) {
+                ids.insert(key.clone(), key.clone());
+                continue;
+            }
+            let emails = unfiltered.get(key).map_or(&[][..], Vec::as_slice);
+            let parents = self.resolve_parents(emails);
+            let root = emails
+                .iter()
+                .copied()
+                .filter(|e| !parents.contains_key(&e.id))
+                .min_by(|a, b| a.date_sent.cmp(&b.date_sent).then_with(|| a.id.cmp(&b.id)));
+            let base = format!("T-{}", &audit::sha256_hex(root_content(root, key).as_bytes())[..STABLE_ID_DIGITS]);
+            let seen = taken.entry(base.clone()).or_default();
+            *seen += 1;
+            let id = if *seen == 1 { base } else { format!("{}-{}", base, seen) };
+            ids.insert(key.clone(), id);
+        }
+        ids
+    }
+}
+
+// What a thread's id is derived from, tagged so a Message-ID and a subject
+// with the same text hash apart
+fn root_content(root: Option<&EmailMessage>, key: &str) -> String {
+    let message_id = root.map(|e| e.message_id.trim().trim_start_matches('<').trim_end_matches('>')).unwrap_or("");
+    if !message_id.is_empty() {
+        return format!("message_id:{}", message_id);
+    }
+    let subject = root.map(|e| topics::normalize_subject(&e.subject)).unwrap_or_default();
+    if !subject.is_empty() {
+        format!("subject:{}", subject)
+    } else {
+        format!("key:{}", key)
+    }
+}
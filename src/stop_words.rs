@@ -0,0 +1,166 @@
+use crate::{inclusive, languages, search, EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+// Words too common in business email to tell documents apart
+const DEFAULT_STOP_WORDS: &[&str] = &[
+    "a", "about", "all", "also", "am", "an", "and", "any", "are", "as", "at", "be", "been", "but", "by", "can", "could",
+    "do", "for", "from", "get", "had", "has", "have", "he", "her", "hi", "his", "i", "if", "in", "is", "it", "its",
+    "just", "know", "let", "me", "more", "my", "no", "not", "of", "on", "or", "our", "please", "re", "she", "so",
+    "thanks", "that", "the", "their", "them", "then", "there", "these", "they", "this", "to", "up", "us", "was", "we",
+    "were", "what", "when", "which", "will", "with", "would", "you", "your",
+];
+const DEFAULT_TERM_LIMIT: usize = 50;
+
+/// Noise kept out of text analytics: single words, and phrases such as
+/// confidentiality footers or company boilerplate that would otherwise
+/// dominate term counts and topics. Search is not affected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StopWordConfig {
+    // Added to the built-in list, or replacing it without use_defaults
+    pub stop_words: Vec<String>,
+    pub stop_phrases: Vec<String>,
+    pub use_defaults: bool,
+}
+
+impl Default for StopWordConfig {
+    fn default() -> Self {
+        StopWordConfig {
+            stop_words: Vec::new(),
+            stop_phrases: Vec::new(),
+            use_defaults: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TermStat {
+    pub term: String,
+    pub document_count: usize,
+    pub occurrences: usize,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Sets the stop words and stop phrases for term stats and topic
+    /// clustering from a `StopWordConfig` object: `stop_words`,
+    /// `stop_phrases` (matched word by word, so line wrapping and punctuation
+    /// do not matter) and `use_defaults` (default true) to keep the built-in
+    /// English list. Topics built earlier keep their terms until rebuilt.
+    #[wasm_bindgen]
+    pub fn set_stop_words(&mut self, config: JsValue) -> Result<(), JsValue> {
+        let config: StopWordConfig = if config.is_undefined() || config.is_null() {
+            StopWordConfig::default()
+        } else {
+            serde_wasm_bindgen::from_value(config)?
+        };
+        self.audit(
+            "set_stop_words",
+            format!(
+                "{} stop words, {} stop phrases, defaults {}",
+                config.stop_words.len(),
+                config.stop_phrases.len(),
+                if config.use_defaults { "on" } else { "off" }
+            ),
+        );
+        self.stop_words = config;
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_stop_words(&self) -> Result<JsValue, JsValue> {
+        self.to_js(&self.stop_words)
+    }
+
+    /// The most widespread terms over each email's own new text, stop words
+    /// and stop phrases removed, for the whole scope or one thread. Top
+    /// `limit` (default 50) by the number of emails using them.
+    #[wasm_bindgen]
+    pub fn get_term_stats(&self, thread_id: Option<String>, limit: Option<usize>) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let emails: Vec<&EmailMessage> = match &thread_id {
+            Some(id) => self.threads.get(id).ok_or_else(|| JsValue::from_str("Thread not found"))?.iter().collect(),
+            None => self.included_emails().collect(),
+        };
+        let filter = self.stop_word_filter();
+        let mut stats: IndexMap<String, TermStat> = IndexMap::new();
+        for email in emails {
+            self.check_cancelled()?;
+            let terms = filter.terms(&analysis_text(email));
+            let mut seen = HashSet::new();
+            for term in terms {
+                let stat = stats.entry(term.clone()).or_insert_with(|| TermStat {
+                    term: term.clone(),
+                    document_count: 0,
+                    occurrences: 0,
+                });
+                stat.occurrences += 1;
+                if seen.insert(term) {
+                    stat.document_count += 1;
+                }
+            }
+        }
+
+        let mut stats: Vec<TermStat> = stats.into_values().collect();
+        stats.sort_by(|a, b| {
+            b.document_count.cmp(&a.document_count).then(b.occurrences.cmp(&a.occurrences)).then(a.term.cmp(&b.term))
+        });
+        stats.truncate(limit.unwrap_or(DEFAULT_TERM_LIMIT));
+        self.to_js(&stats)
+    }
+}
+
+/// The stop word configuration compiled for matching.
+pub(crate) struct StopWordFilter {
+    words: HashSet<String>,
+    phrases: Vec<Vec<String>>,
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn stop_word_filter(&self) -> StopWordFilter {
+        let config = &self.stop_words;
+        let mut words: HashSet<String> = config.stop_words.iter().map(|w| w.trim().to_lowercase()).collect();
+        if config.use_defaults {
+            words.extend(DEFAULT_STOP_WORDS.iter().map(|w| w.to_string()));
+        }
+        let phrases = config
+            .stop_phrases
+            .iter()
+            .map(|p| search::tokenize(p).into_iter().map(|(t, _)| t).collect::<Vec<_>>())
+            .filter(|p| !p.is_empty())
+            .collect();
+        StopWordFilter { words, phrases }
+    }
+}
+
+impl StopWordFilter {
+    /// Lowercased terms of `text` worth analyzing: stop phrases cut out, then
+    /// stop words, numbers and Latin words of one or two letters dropped.
+    pub(crate) fn terms(&self, text: &str) -> Vec<String> {
+        let tokens: Vec<String> = search::tokenize(text).into_iter().map(|(t, _)| t).collect();
+        let mut kept = Vec::with_capacity(tokens.len());
+        let mut i = 0;
+        while i < tokens.len() {
+            if let Some(phrase) = self.phrases.iter().find(|p| tokens[i..].starts_with(p)) {
+                i += phrase.len();
+                continue;
+            }
+            let term = &tokens[i];
+            // CJK pairs are two characters but carry a word's meaning
+            let long_enough = term.chars().count() > 2 || term.chars().any(languages::is_cjk);
+            if long_enough && !term.chars().all(|c| c.is_ascii_digit()) && !self.words.contains(term) {
+                kept.push(term.clone());
+            }
+            i += 1;
+        }
+        kept
+    }
+}
+
+/// Subject and the text the email adds itself, what text analytics work on.
+pub(crate) fn analysis_text(email: &EmailMessage) -> String {
+    format!("{}\n{}", email.subject, inclusive::new_content(&email.full_text))
+}
@@ -0,0 +1,171 @@
+use crate::rollups::BranchRollup;
+use crate::tree_options::ChildOrder;
+use crate::{highlight, EmailMessage, EmailThreadProcessor};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubtreeNode {
+    pub email: EmailMessage,
+    pub children: Vec<SubtreeNode>,
+    // Depth in the full thread tree, not relative to the subtree root
+    pub depth: usize,
+    #[serde(default)]
+    pub highlights: highlight::Highlights,
+    // Counted even when the children themselves were cut off
+    pub child_count: usize,
+    pub descendant_count: usize,
+    // Children left out by max_depth; expand with get_subtree on this node
+    pub truncated: bool,
+    // Over all descendants, including any cut off
+    pub rollup: BranchRollup,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subtree {
+    pub thread_id: String,
+    pub root: SubtreeNode,
+    pub node_count: usize,
+    pub total_emails: usize,
+}
+
+struct Links<'a> {
+    by_id: HashMap<&'a str, &'a EmailMessage>,
+    children: HashMap<&'a str, Vec<&'a str>>,
+    order: ChildOrder,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// The part of a thread's tree below one email, `max_depth` levels deep
+    /// (0 returns the email alone). Nodes at the cut-off report how many
+    /// children and descendants they hold so the UI can expand them on
+    /// demand. `message_id` may be the email's id or its Message-ID.
+    /// `child_order` is as for `build_thread_tree`, chronological by default.
+    #[wasm_bindgen]
+    pub fn get_subtree(
+        &self,
+        thread_id: &str,
+        message_id: &str,
+        max_depth: usize,
+        child_order: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let order = match child_order.as_deref() {
+            Some(name) => ChildOrder::parse(name)?,
+            None => ChildOrder::default(),
+        };
+        let subtree = self.subtree(thread_id, message_id, max_depth, order)?;
+        self.to_js(&subtree)
+    }
+}
+
+impl EmailThreadProcessor {
+    fn subtree(
+        &self,
+        thread_id: &str,
+        message_id: &str,
+        max_depth: usize,
+        order: ChildOrder,
+    ) -> Result<Subtree, JsValue> {
+        let emails = self
+            .threads
+            .get(thread_id)
+            .ok_or_else(|| JsValue::from_str("Thread not found"))?;
+        let start = emails
+            .iter()
+            .find(|e| e.id == message_id || (!e.message_id.is_empty() && e.message_id == message_id))
+            .ok_or_else(|| JsValue::from_str(&format!("Email not found in thread: {}", message_id)))?;
+
+        let parents = self.resolve_parents(emails);
+        let mut links = Links {
+            by_id: emails.iter().map(|e| (e.id.as_str(), e)).collect(),
+            children: HashMap::new(),
+            order,
+        };
+        for email in emails {
+            if let Some(parent) = parents.get(&email.id) {
+                links.children.entry(parent.as_str()).or_default().push(email.id.as_str());
+            }
+        }
+
+        let mut depth = 0;
+        let mut seen = HashSet::from([start.id.as_str()]);
+        let mut current = start.id.as_str();
+        while let Some(parent) = parents.get(current).filter(|p| seen.insert(p.as_str())) {
+            depth += 1;
+            current = parent;
+        }
+
+        let mut rollups = HashMap::new();
+        let root = self.subtree_node(&links, &mut rollups, start, depth, max_depth, &mut HashSet::new());
+        Ok(Subtree {
+            thread_id: thread_id.to_string(),
+            node_count: count_nodes(&root),
+            root,
+            total_emails: emails.len(),
+        })
+    }
+
+    fn subtree_node(
+        &self,
+        links: &Links,
+        rollups: &mut HashMap<String, BranchRollup>,
+        email: &EmailMessage,
+        depth: usize,
+        remaining: usize,
+        path: &mut HashSet<String>,
+    ) -> SubtreeNode {
+        let child_ids = links.children.get(email.id.as_str()).map(Vec::as_slice).unwrap_or_default();
+        path.insert(email.id.clone());
+        let mut children = Vec::new();
+        if remaining > 0 {
+            for id in child_ids {
+                if !path.contains(*id) {
+                    children.push(self.subtree_node(links, rollups, links.by_id[id], depth + 1, remaining - 1, path));
+                }
+            }
+        }
+        children.sort_by(|a, b| links.order.compare((&a.email, a.descendant_count), (&b.email, b.descendant_count)));
+        path.remove(&email.id);
+
+        let rollup = branch_rollup(links, rollups, email, &mut HashSet::new());
+        SubtreeNode {
+            highlights: self.highlights_for(&email.id),
+            email: email.clone(),
+            depth,
+            child_count: child_ids.len(),
+            descendant_count: rollup.descendant_count,
+            truncated: remaining == 0 && !child_ids.is_empty(),
+            children,
+            rollup,
+        }
+    }
+}
+
+// Memoized; only an email inside a parent cycle (see integrity) can reach
+// itself, and the walk stops there
+fn branch_rollup(
+    links: &Links,
+    memo: &mut HashMap<String, BranchRollup>,
+    email: &EmailMessage,
+    path: &mut HashSet<String>,
+) -> BranchRollup {
+    if let Some(rollup) = memo.get(&email.id) {
+        return rollup.clone();
+    }
+    path.insert(email.id.clone());
+    let mut rollup = BranchRollup::of_email(email);
+    for child in links.children.get(email.id.as_str()).into_iter().flatten() {
+        if !path.contains(*child) {
+            rollup.add_child(&branch_rollup(links, memo, links.by_id[child], path));
+        }
+    }
+    path.remove(&email.id);
+    memo.insert(email.id.clone(), rollup.clone());
+    rollup
+}
+
+fn count_nodes(node: &SubtreeNode) -> usize {
+    1 + node.children.iter().map(count_nodes).sum::<usize>()
+}
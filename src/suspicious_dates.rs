@@ -0,0 +1,129 @@
+use crate::{EmailMessage, EmailThreadProcessor, ThreadNode};
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// Widest gap a wrong UTC offset explains
+const MAX_ZONE_SHIFT_HOURS: i64 = 14;
+
+/// A DateSent that is likely wrong, with a better value where one can be
+/// inferred.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuspiciousDate {
+    pub email_id: String,
+    pub thread_id: String,
+    // "vendor_default", "timezone_shift" or "reply_before_parent"
+    pub kind: String,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub date_sent: DateTime<Utc>,
+    pub parent_id: Option<String>,
+    #[serde(serialize_with = "crate::display::serialize_option")]
+    pub parent_date: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "crate::display::serialize_option")]
+    pub suggested_date: Option<DateTime<Utc>>,
+    // "date_created", "date_last_modified", "parent" or "zone_shift"
+    pub suggestion_source: Option<String>,
+    pub message: String,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Emails whose DateSent looks wrong: a vendor placeholder such as
+    /// 1/1/1900, or a reply dated before the message it answers, told apart
+    /// into a likely time zone shift (within 14 hours, corrected by whole
+    /// half-hours) and anything else. Each comes with a suggested date where
+    /// the other date fields or the parent allow one. Limited to `thread_id`
+    /// when given. Trees flag the same emails with `suspicious_date`.
+    #[wasm_bindgen]
+    pub fn get_suspicious_dates(&self, thread_id: Option<String>) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let threads: Vec<(&String, &Vec<EmailMessage>)> = match &thread_id {
+            Some(id) => vec![self.threads.get_key_value(id).ok_or_else(|| JsValue::from_str("Thread not found"))?],
+            None => self.threads.iter().collect(),
+        };
+
+        let mut found = Vec::new();
+        for (thread_id, emails) in threads {
+            self.check_cancelled()?;
+            let parents = self.resolve_parents(emails);
+            for email in emails {
+                let parent = parents.get(&email.id).and_then(|p| emails.iter().find(|e| &e.id == p));
+                if let Some(issue) = date_issue(email, parent) {
+                    found.push(SuspiciousDate {
+                        thread_id: thread_id.clone(),
+                        ..issue
+                    });
+                }
+            }
+        }
+
+        console_log!("Found {} suspicious dates", found.len());
+        self.to_js(&found)
+    }
+}
+
+/// Sets `suspicious_date` on `node` and everything below it.
+pub(crate) fn mark_suspicious_dates(node: &mut ThreadNode, parent: Option<&EmailMessage>) {
+    node.suspicious_date = date_issue(&node.email, parent).is_some();
+    for child in &mut node.children {
+        mark_suspicious_dates(child, Some(&node.email));
+    }
+}
+
+// The issue with an email's DateSent given its resolved parent; thread_id is
+// left for the caller
+fn date_issue(email: &EmailMessage, parent: Option<&EmailMessage>) -> Option<SuspiciousDate> {
+    let issue = |kind: &str, suggested: Option<(DateTime<Utc>, &str)>, message: String| SuspiciousDate {
+        email_id: email.id.clone(),
+        thread_id: String::new(),
+        kind: kind.to_string(),
+        date_sent: email.date_sent,
+        parent_id: parent.map(|p| p.id.clone()),
+        parent_date: parent.map(|p| p.date_sent),
+        suggested_date: suggested.map(|(date, _)| date),
+        suggestion_source: suggested.map(|(_, source)| source.to_string()),
+        message,
+    };
+
+    if is_vendor_default(email.date_sent) {
+        let suggested = [(email.date_created, "date_created"), (email.date_last_modified, "date_last_modified")]
+            .into_iter()
+            .find(|(date, _)| !is_vendor_default(*date))
+            .or_else(|| parent.filter(|p| !is_vendor_default(p.date_sent)).map(|p| (p.date_sent, "parent")));
+        let message = format!("DateSent {} is a processing placeholder", email.date_sent.format("%Y-%m-%d"));
+        return Some(issue("vendor_default", suggested, message));
+    }
+
+    let parent = parent.filter(|p| !is_vendor_default(p.date_sent))?;
+    if email.date_sent >= parent.date_sent {
+        return None;
+    }
+    let gap = parent.date_sent - email.date_sent;
+    if gap <= Duration::hours(MAX_ZONE_SHIFT_HOURS) {
+        // Round up to the half hour, which covers the :30 zones
+        let half_hours = (gap.num_minutes() + 29) / 30;
+        let shift = Duration::minutes(half_hours.max(1) * 30);
+        let message = format!(
+            "Dated {} before its parent {}; likely recorded in another time zone",
+            format_gap(gap),
+            parent.id
+        );
+        return Some(issue("timezone_shift", Some((email.date_sent + shift, "zone_shift")), message));
+    }
+    let message = format!("Dated {} before its parent {}; one of the two dates is wrong", format_gap(gap), parent.id);
+    Some(issue("reply_before_parent", None, message))
+}
+
+// 1/1/1900 and similar fill-ins, the Unix epoch and the DOS epoch
+pub(crate) fn is_vendor_default(date: DateTime<Utc>) -> bool {
+    let epoch = |year| Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap();
+    date.year() <= 1900 || date == epoch(1970) || date == epoch(1980)
+}
+
+fn format_gap(gap: Duration) -> String {
+    match gap.num_minutes() {
+        m if m < 60 => format!("{} minutes", m),
+        m if m < 48 * 60 => format!("{}h{:02}m", m / 60, m % 60),
+        m => format!("{} days", m / (24 * 60)),
+    }
+}
@@ -0,0 +1,227 @@
+use crate::{audit, EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+const FIRST_NAMES: &[&str] = &[
+    "Alice", "Bob", "Carol", "David", "Erin", "Frank", "Grace", "Heidi", "Ivan", "Judy", "Mallory", "Oscar",
+];
+const LAST_NAMES: &[&str] = &["Adams", "Baker", "Chen", "Diaz", "Evans", "Fischer", "Garcia", "Hughes"];
+const TOPICS: &[&str] = &[
+    "Q3 budget review",
+    "Supplier contract renewal",
+    "Board meeting agenda",
+    "Project Falcon status",
+    "Pricing proposal",
+    "Audit follow-up",
+    "Site visit logistics",
+    "Draft press release",
+    "Headcount plan",
+    "Licensing terms",
+];
+const SENTENCES: &[&str] = &[
+    "Please see the attached figures before our call.",
+    "I have a few concerns about the timeline.",
+    "Can we push this to next week?",
+    "Legal has signed off on the revised wording.",
+    "Let me check with the team and get back to you.",
+    "The numbers do not match what finance sent over.",
+    "Agreed, let's go ahead on that basis.",
+    "Adding a few more people for visibility.",
+    "We should keep this between us for now.",
+    "Thanks, that works for me.",
+];
+
+/// Knobs for `generate_test_corpus`; rates are probabilities per email.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CorpusParams {
+    // Same seed and params, same corpus
+    pub seed: u64,
+    pub max_thread_size: usize,
+    pub people: usize,
+    pub custodians: usize,
+    pub internal_domain: String,
+    pub external_domains: Vec<String>,
+    pub start: DateTime<Utc>,
+    pub forward_rate: f64,
+    // Extra copies of an email held by another custodian
+    pub duplicate_rate: f64,
+    // In-Reply-To pointing at a message that is not in the corpus
+    pub broken_reference_rate: f64,
+}
+
+impl Default for CorpusParams {
+    fn default() -> Self {
+        CorpusParams {
+            seed: 1,
+            max_thread_size: 12,
+            people: 20,
+            custodians: 3,
+            internal_domain: "example.com".to_string(),
+            external_domains: vec!["partner.example".to_string(), "vendor.example".to_string()],
+            start: Utc.with_ymd_and_hms(2020, 1, 6, 9, 0, 0).unwrap(),
+            forward_rate: 0.1,
+            duplicate_rate: 0.15,
+            broken_reference_rate: 0.05,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Replaces the corpus with `n_threads` fabricated conversations: branching
+    /// reply chains, forwards, duplicates across custodians and broken
+    /// references, with no real client data. `params` is an optional
+    /// `CorpusParams` object. Built with the `test-corpus` feature.
+    #[wasm_bindgen]
+    pub fn generate_test_corpus(&mut self, n_threads: usize, params: JsValue) -> Result<usize, JsValue> {
+        let params: CorpusParams = if params.is_undefined() || params.is_null() {
+            CorpusParams::default()
+        } else {
+            serde_wasm_bindgen::from_value(params)?
+        };
+        if params.people < 2 || params.custodians == 0 || params.max_thread_size == 0 {
+            return Err(JsValue::from_str("Corpus needs at least 2 people, 1 custodian and threads of 1 email"));
+        }
+
+        let emails = generate(n_threads, &params);
+        let count = self.finish_load(emails, "synthetic");
+        self.audit("generate_test_corpus", format!("{} emails in {} threads, seed {}", count, n_threads, params.seed));
+        console_log!("Generated {} synthetic emails in {} threads", count, n_threads);
+        Ok(count)
+    }
+}
+
+// SplitMix64: small, fast and identical on every platform
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+fn generate(n_threads: usize, params: &CorpusParams) -> Vec<EmailMessage> {
+    let mut rng = Rng(params.seed);
+    let people: Vec<String> = (0..params.people)
+        .map(|i| {
+            let first = FIRST_NAMES[i % FIRST_NAMES.len()];
+            let last = LAST_NAMES[(i / FIRST_NAMES.len() + i) % LAST_NAMES.len()];
+            // Roughly a third of people are outside the organization
+            let domain = if i % 3 == 2 && !params.external_domains.is_empty() {
+                params.external_domains[i % params.external_domains.len()].as_str()
+            } else {
+                params.internal_domain.as_str()
+            };
+            format!("{} {} <{}.{}@{}>", first, last, first.to_lowercase(), last.to_lowercase(), domain)
+        })
+        .collect();
+    let custodians: Vec<String> = (0..params.custodians)
+        .map(|i| format!("{}, {}", LAST_NAMES[i % LAST_NAMES.len()], FIRST_NAMES[i % FIRST_NAMES.len()]))
+        .collect();
+
+    let mut emails: Vec<EmailMessage> = Vec::new();
+    let mut date = params.start;
+    for t in 0..n_threads {
+        let thread_id = format!("SYNTH-T{:06}", t + 1);
+        let topic = rng.pick(TOPICS).to_string();
+        let cast_size = (2 + rng.below(4)).min(people.len());
+        let mut cast: Vec<&String> = Vec::with_capacity(cast_size);
+        while cast.len() < cast_size {
+            let person = rng.pick(&people);
+            if !cast.contains(&person) {
+                cast.push(person);
+            }
+        }
+        let size = 1 + rng.below(params.max_thread_size);
+        let first = emails.len();
+        date += Duration::hours(1 + rng.below(72) as i64);
+
+        for n in 0..size {
+            let from = cast[n % cast.len()].clone();
+            let mut to: Vec<String> = cast.iter().filter(|p| **p != &from).map(|p| p.to_string()).collect();
+            date += Duration::minutes(5 + rng.below(600) as i64);
+
+            let mut email = EmailMessage {
+                message_id: format!("<synth.{}.{}@{}>", t + 1, n + 1, params.internal_domain),
+                thread_id: thread_id.clone(),
+                subject: topic.clone(),
+                date_sent: date,
+                custodian: rng.pick(&custodians).clone(),
+                file_type: "Email".to_string(),
+                date_created: date,
+                date_last_modified: date,
+                ..Default::default()
+            };
+            let mut text = format!("{}\n\n{}", rng.pick(SENTENCES), rng.pick(SENTENCES));
+
+            if n > 0 {
+                // Reply to any earlier email, so chains branch as well as grow
+                let parent = &emails[first + rng.below(n)];
+                let forward = rng.chance(params.forward_rate);
+                email.subject = format!("{}{}", if forward { "FW: " } else { "RE: " }, topic);
+                email.is_forward = forward;
+                email.references = parent.references.iter().cloned().chain([parent.message_id.clone()]).collect();
+                email.in_reply_to = Some(parent.message_id.clone());
+                if rng.chance(params.broken_reference_rate) {
+                    let missing = format!("<synth.{}.missing{}@{}>", t + 1, n + 1, params.internal_domain);
+                    email.references.push(missing.clone());
+                    email.in_reply_to = Some(missing);
+                }
+                if forward {
+                    to = vec![rng.pick(&people).clone()];
+                }
+                text.push_str(&format!(
+                    "\n\n-----Original Message-----\nFrom: {}\nSubject: {}\n\n{}",
+                    parent.from, parent.subject, parent.full_text
+                ));
+            }
+
+            email.is_external = std::iter::once(&from)
+                .chain(&to)
+                .any(|p| !p.ends_with(&format!("@{}>", params.internal_domain)));
+            email.from = from;
+            email.to = to;
+            email.full_text = text;
+            email.hash = audit::sha256_hex(format!("{}\n{}", email.message_id, email.full_text).as_bytes());
+            email.all_custodians = vec![email.custodian.clone()];
+            emails.push(email);
+        }
+
+        // Same message collected from another custodian's mailbox
+        for i in first..emails.len() {
+            if params.custodians > 1 && rng.chance(params.duplicate_rate) {
+                let mut copy = emails[i].clone();
+                while copy.custodian == emails[i].custodian {
+                    copy.custodian = rng.pick(&custodians).clone();
+                }
+                copy.all_custodians = vec![copy.custodian.clone()];
+                emails.push(copy);
+            }
+        }
+    }
+
+    for (i, email) in emails.iter_mut().enumerate() {
+        email.id = format!("SYNTH{:07}", i + 1);
+        email.beg_bates = format!("SYN{:07}", i + 1);
+        email.end_bates = email.beg_bates.clone();
+    }
+    emails
+}
@@ -0,0 +1,99 @@
+use crate::{coding, EmailThreadProcessor};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TermHits {
+    pub term: String,
+    pub document_hits: usize,
+    // Hits plus their family members (parent email and attachments)
+    pub family_hits: usize,
+    // Documents no other term hits
+    pub unique_hits: usize,
+    // Set when the term does not parse; its counts are then zero
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchTermReport {
+    pub terms: Vec<TermHits>,
+    pub total_document_hits: usize,
+    pub total_family_hits: usize,
+    // overlap[i][j]: documents hit by both term i and term j; the diagonal
+    // repeats each term's document hits
+    pub overlap: Vec<Vec<usize>>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Search term report (STR): per-term document and family hit counts,
+    /// unique hits and the pairwise overlap between terms. Each term uses the
+    /// `search` query syntax.
+    #[wasm_bindgen]
+    pub fn generate_search_term_report(&self, terms: Vec<String>) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let report = self.search_term_report(&terms)?;
+        console_log!("Search term report: {} terms, {} documents hit", terms.len(), report.total_document_hits);
+        self.to_js(&report)
+    }
+}
+
+impl EmailThreadProcessor {
+    fn search_term_report(&self, terms: &[String]) -> Result<SearchTermReport, JsValue> {
+        let family_keys = coding::family_keys(&self.emails);
+        let mut families: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (doc, key) in family_keys.iter().enumerate() {
+            families.entry(key.as_str()).or_default().push(doc);
+        }
+        let with_family = |hits: &BTreeSet<usize>| -> BTreeSet<usize> {
+            hits.iter()
+                .flat_map(|&doc| families[family_keys[doc].as_str()].iter().copied())
+                .filter(|&doc| self.in_scope(&self.emails[doc]))
+                .collect()
+        };
+
+        let mut hits: Vec<BTreeSet<usize>> = Vec::new();
+        let mut errors: Vec<Option<String>> = Vec::new();
+        for term in terms {
+            self.check_cancelled()?;
+            match self.run_query(term) {
+                Ok(docs) => {
+                    hits.push(docs.into_iter().collect());
+                    errors.push(None);
+                }
+                Err(e) => {
+                    hits.push(BTreeSet::new());
+                    errors.push(Some(e.as_string().unwrap_or_else(|| format!("Invalid query: {}", term))));
+                }
+            }
+        }
+
+        let mut hit_counts: HashMap<usize, usize> = HashMap::new();
+        for doc in hits.iter().flatten() {
+            *hit_counts.entry(*doc).or_default() += 1;
+        }
+        let all_hits: BTreeSet<usize> = hit_counts.keys().copied().collect();
+
+        Ok(SearchTermReport {
+            terms: terms
+                .iter()
+                .zip(&hits)
+                .zip(errors)
+                .map(|((term, docs), error)| TermHits {
+                    term: term.clone(),
+                    document_hits: docs.len(),
+                    family_hits: with_family(docs).len(),
+                    unique_hits: docs.iter().filter(|d| hit_counts[d] == 1).count(),
+                    error,
+                })
+                .collect(),
+            total_document_hits: all_hits.len(),
+            total_family_hits: with_family(&all_hits).len(),
+            overlap: hits
+                .iter()
+                .map(|a| hits.iter().map(|b| a.intersection(b).count()).collect())
+                .collect(),
+        })
+    }
+}
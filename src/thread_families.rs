@@ -0,0 +1,194 @@
+use crate::{inclusive, topics, DateRange, EmailMessage, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use wasm_bindgen::prelude::*;
+
+/// Thresholds for `get_thread_families`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FamilyOptions {
+    // Score two same-subject threads need to be linked, 0 to 1: half
+    // participant overlap, half quoted content
+    pub min_score: f64,
+    // Subjects shared by more threads than this ("hi", "update") are too
+    // generic to go by
+    pub max_threads_per_subject: usize,
+}
+
+impl Default for FamilyOptions {
+    fn default() -> Self {
+        FamilyOptions {
+            min_score: 0.5,
+            max_threads_per_subject: 100,
+        }
+    }
+}
+
+/// Why two threads were put in one family.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FamilyLink {
+    pub thread_a: String,
+    pub thread_b: String,
+    pub score: f64,
+    // Shared over all participants (Jaccard), mapped addresses as their person
+    pub participant_overlap: f64,
+    // Most of one thread's new content the other repeats, 0 to 1
+    pub quoted_similarity: f64,
+}
+
+/// Threads that look like fragments of one conversation, proposed for review.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadFamily {
+    // "F-1", "F-2"... in the order returned
+    pub family_id: String,
+    pub subject: String,
+    // In grouping order
+    pub thread_ids: Vec<String>,
+    pub email_count: usize,
+    pub date_range: DateRange,
+    pub links: Vec<FamilyLink>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Groups visible threads into proposed "thread families": threads that
+    /// share a normalized subject and are linked by participant overlap and
+    /// quoted content (see `FamilyOptions`), chained transitively. Meant for
+    /// vendors that split one conversation over many thread ids; nothing is
+    /// regrouped. Largest families first.
+    #[wasm_bindgen]
+    pub fn get_thread_families(&self, options: JsValue) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let options: FamilyOptions = if options.is_undefined() || options.is_null() {
+            FamilyOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options)?
+        };
+        let families = self.thread_families(&options)?;
+        console_log!("Found {} thread families", families.len());
+        self.to_js(&families)
+    }
+}
+
+impl EmailThreadProcessor {
+    fn thread_families(&self, options: &FamilyOptions) -> Result<Vec<ThreadFamily>, JsValue> {
+        let threads: Vec<(&String, &Vec<EmailMessage>)> = self.visible_threads().collect();
+        let mut by_subject: IndexMap<String, Vec<usize>> = IndexMap::new();
+        for (i, (_, emails)) in threads.iter().enumerate() {
+            let subjects: BTreeSet<String> =
+                emails.iter().map(|e| topics::normalize_subject(&e.subject)).filter(|s| !s.is_empty()).collect();
+            for subject in subjects {
+                by_subject.entry(subject).or_default().push(i);
+            }
+        }
+
+        let mut pairs: BTreeSet<(usize, usize)> = BTreeSet::new();
+        for members in by_subject.values().filter(|m| m.len() > 1 && m.len() <= options.max_threads_per_subject) {
+            for (n, &a) in members.iter().enumerate() {
+                pairs.extend(members[n + 1..].iter().map(|&b| (a, b)));
+            }
+        }
+
+        let people: Vec<HashSet<String>> = threads.iter().map(|(_, emails)| self.thread_people(emails)).collect();
+        let mut links = Vec::new();
+        let mut family_of: Vec<usize> = (0..threads.len()).collect();
+        for (n, &(a, b)) in pairs.iter().enumerate() {
+            self.check_cancelled()?;
+            let shared = people[a].intersection(&people[b]).count();
+            let all = people[a].union(&people[b]).count();
+            let participant_overlap = if all == 0 { 0.0 } else { shared as f64 / all as f64 };
+            let quoted_similarity = quoted_similarity(threads[a].1, threads[b].1);
+            let score = (participant_overlap + quoted_similarity) / 2.0;
+            if score >= options.min_score {
+                let (root_a, root_b) = (find(&mut family_of, a), find(&mut family_of, b));
+                family_of[root_a.max(root_b)] = root_a.min(root_b);
+                links.push((a, b, participant_overlap, quoted_similarity, score));
+            }
+            self.emit_progress("families", n + 1, Some(pairs.len()));
+        }
+
+        let roots: Vec<usize> = (0..threads.len()).map(|i| find(&mut family_of, i)).collect();
+        let mut members: IndexMap<usize, Vec<usize>> = IndexMap::new();
+        for (i, &root) in roots.iter().enumerate() {
+            members.entry(root).or_default().push(i);
+        }
+        let mut families: Vec<ThreadFamily> = members
+            .into_iter()
+            .filter(|(_, members)| members.len() > 1)
+            .map(|(root, members)| {
+                let emails = || members.iter().flat_map(|&i| threads[i].1.iter());
+                let subject = emails()
+                    .min_by_key(|e| e.date_sent)
+                    .map(|e| topics::normalize_subject(&e.subject))
+                    .unwrap_or_default();
+                ThreadFamily {
+                    family_id: String::new(),
+                    subject,
+                    thread_ids: members.iter().map(|&i| threads[i].0.clone()).collect(),
+                    email_count: emails().count(),
+                    date_range: DateRange {
+                        start: emails().map(|e| e.date_sent).min().unwrap_or_default(),
+                        end: emails().map(|e| e.date_sent).max().unwrap_or_default(),
+                    },
+                    links: links
+                        .iter()
+                        .filter(|link| roots[link.0] == root)
+                        .map(|&(a, b, participant_overlap, quoted_similarity, score)| FamilyLink {
+                            thread_a: threads[a].0.clone(),
+                            thread_b: threads[b].0.clone(),
+                            score,
+                            participant_overlap,
+                            quoted_similarity,
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        families.sort_by(|a, b| {
+            b.thread_ids
+                .len()
+                .cmp(&a.thread_ids.len())
+                .then(b.email_count.cmp(&a.email_count))
+                .then_with(|| a.thread_ids.cmp(&b.thread_ids))
+        });
+        for (i, family) in families.iter_mut().enumerate() {
+            family.family_id = format!("F-{}", i + 1);
+        }
+        Ok(families)
+    }
+
+    fn thread_people(&self, emails: &[EmailMessage]) -> HashSet<String> {
+        emails
+            .iter()
+            .flat_map(|e| std::iter::once(&e.from).chain(&e.to).chain(&e.cc))
+            .flat_map(|field| self.identity_map.identities(field))
+            .collect()
+    }
+}
+
+// The most of any earlier email's new content a later email in the other
+// thread repeats
+fn quoted_similarity(a: &[EmailMessage], b: &[EmailMessage]) -> f64 {
+    let quotable = |e: &EmailMessage| !inclusive::normalized_words(inclusive::new_content(&e.full_text)).is_empty();
+    let mut best: f64 = 0.0;
+    for x in a.iter().filter(|e| quotable(e)) {
+        for y in b.iter().filter(|e| quotable(e)) {
+            let (earlier, later) = if x.date_sent <= y.date_sent { (x, y) } else { (y, x) };
+            best = best.max(inclusive::containment_score(&earlier.full_text, &later.full_text));
+            if best >= 1.0 {
+                return best;
+            }
+        }
+    }
+    best
+}
+
+fn find(family_of: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while family_of[root] != root {
+        root = family_of[root];
+    }
+    family_of[i] = root;
+    root
+}
@@ -0,0 +1,112 @@
+use crate::{EmailMessage, EmailThreadProcessor};
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+// Appended to a locked thread's id for emails that would join it after it
+// was locked, so they can be reviewed apart
+pub(crate) const NEWCOMER_SUFFIX: &str = ":new";
+
+/// A thread's structure as frozen by `lock_thread`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadLock {
+    pub thread_id: String,
+    pub reason: String,
+    #[serde(serialize_with = "crate::display::serialize")]
+    pub locked_at: DateTime<Utc>,
+    // Every email the thread held, in thread order
+    pub email_ids: Vec<String>,
+    // Email id -> parent id, as resolved when locked
+    pub parents: IndexMap<String, String>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Freezes a thread after manual curation: its emails and reply structure
+    /// stay as they are now through later loads, `group_by_threads`, threading
+    /// mode changes, `reattach_orphans` and `update_email`. Emails that would
+    /// otherwise join it later are grouped into "<thread id>:new" instead.
+    /// Locking a locked thread again records it as it now stands; emails
+    /// removed with `remove_emails` drop out of the lock.
+    #[wasm_bindgen]
+    pub fn lock_thread(&mut self, thread_id: &str, reason: Option<String>) -> Result<(), JsValue> {
+        let emails = self
+            .threads
+            .get(thread_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Thread not found: {}", thread_id)))?;
+        let resolved = self.resolve_parents(emails);
+        let parents: IndexMap<String, String> =
+            emails.iter().filter_map(|e| Some((e.id.clone(), resolved.get(&e.id)?.clone()))).collect();
+        let email_ids: Vec<String> = emails.iter().map(|e| e.id.clone()).collect();
+
+        let lock = ThreadLock {
+            thread_id: thread_id.to_string(),
+            reason: reason.unwrap_or_default().trim().to_string(),
+            locked_at: Utc::now(),
+            email_ids,
+            parents,
+        };
+        let details = format!("{}: {} emails ({})", thread_id, lock.email_ids.len(), lock.reason);
+        self.locked_threads.insert(thread_id.to_string(), lock);
+        self.index_thread_locks();
+        self.audit("lock_thread", details);
+        Ok(())
+    }
+
+    /// Lifts a lock and regroups, so the thread takes whatever structure the
+    /// current settings give it. Returns false if it was not locked.
+    #[wasm_bindgen]
+    pub fn unlock_thread(&mut self, thread_id: &str) -> bool {
+        if self.locked_threads.shift_remove(thread_id).is_none() {
+            return false;
+        }
+        self.index_thread_locks();
+        self.audit("unlock_thread", thread_id.to_string());
+        self.group_by_threads();
+        true
+    }
+
+    #[wasm_bindgen]
+    pub fn is_thread_locked(&self, thread_id: &str) -> bool {
+        self.locked_threads.contains_key(thread_id)
+    }
+
+    /// Every lock, in the order made.
+    #[wasm_bindgen]
+    pub fn get_thread_locks(&self) -> Result<JsValue, JsValue> {
+        let locks: Vec<&ThreadLock> = self.locked_threads.values().collect();
+        self.to_js(&locks)
+    }
+}
+
+impl EmailThreadProcessor {
+    /// The locked thread holding an email, if any.
+    pub(crate) fn thread_lock_of(&self, email_id: &str) -> Option<&ThreadLock> {
+        self.locked_emails.get(email_id).and_then(|thread_id| self.locked_threads.get(thread_id))
+    }
+
+    // Grouping asks for every email's lock, so it is looked up by email id
+    pub(crate) fn index_thread_locks(&mut self) {
+        self.locked_emails.clear();
+        for lock in self.locked_threads.values() {
+            for id in &lock.email_ids {
+                self.locked_emails.entry(id.clone()).or_insert_with(|| lock.thread_id.clone());
+            }
+        }
+    }
+
+    /// The frozen parent links among `emails`, when they are a locked thread.
+    pub(crate) fn locked_parents(&self, emails: &[&EmailMessage]) -> Option<HashMap<String, String>> {
+        let lock = emails.first().and_then(|e| self.thread_lock_of(&e.id))?;
+        let ids: HashSet<&str> = emails.iter().map(|e| e.id.as_str()).collect();
+        Some(
+            lock.parents
+                .iter()
+                .filter(|(id, parent)| ids.contains(id.as_str()) && ids.contains(parent.as_str()))
+                .map(|(id, parent)| (id.clone(), parent.clone()))
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{stop_words, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+// Vocabulary size, most widespread terms first
+const MAX_TERMS: usize = 2000;
+const MAX_ITERATIONS: usize = 25;
+const TOP_TERMS: usize = 10;
+
+/// One cluster of emails about the same subject matter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Topic {
+    pub topic_id: usize,
+    // The three heaviest terms, e.g. "pricing / discount / tier"
+    pub label: String,
+    // Heaviest first
+    pub top_terms: Vec<String>,
+    pub email_count: usize,
+    pub thread_count: usize,
+}
+
+/// Topics with the emails and threads assigned to them. Emails with no
+/// usable text after stop words are left unassigned.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopicModel {
+    pub topics: Vec<Topic>,
+    // Email id -> topic id
+    pub email_topics: IndexMap<String, usize>,
+    // Thread id -> topic of most of its emails
+    pub thread_topics: IndexMap<String, usize>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Clusters the in-scope emails into `k` topics (by default about the
+    /// square root of half the email count, 2 to 20) by TF-IDF over each
+    /// email's own new text, less stop words and phrases (see
+    /// `set_stop_words`), and spherical k-means. The result is kept until
+    /// rebuilt, and topics can then be used in the global filter. Returns the
+    /// number of topics.
+    #[wasm_bindgen]
+    pub fn build_topics(&mut self, k: Option<usize>) -> Result<usize, JsValue> {
+        self.reset_cancellation();
+        let model = self.topic_model(k)?;
+        let count = model.topics.len();
+        console_log!("Clustered {} emails into {} topics", model.email_topics.len(), count);
+        self.audit("build_topics", format!("{} emails in {} topics", model.email_topics.len(), count));
+        self.topic_model = Some(model);
+        Ok(count)
+    }
+
+    /// The topics from the last `build_topics`, largest first.
+    #[wasm_bindgen]
+    pub fn get_topics(&self) -> Result<JsValue, JsValue> {
+        let topics = self.topic_model.as_ref().map(|m| m.topics.as_slice()).unwrap_or_default();
+        self.to_js(topics)
+    }
+
+    /// Email and thread topic assignments from the last `build_topics`.
+    #[wasm_bindgen]
+    pub fn get_topic_assignments(&self) -> Result<JsValue, JsValue> {
+        let model = self.topic_model.clone().unwrap_or_default();
+        self.to_js(&model)
+    }
+}
+
+impl EmailThreadProcessor {
+    pub(crate) fn email_topic(&self, email_id: &str) -> Option<usize> {
+        self.topic_model.as_ref()?.email_topics.get(email_id).copied()
+    }
+
+    fn topic_model(&self, k: Option<usize>) -> Result<TopicModel, JsValue> {
+        let emails: Vec<_> = self.included_emails().collect();
+        let filter = self.stop_word_filter();
+        let docs: Vec<Vec<String>> = emails.iter().map(|e| filter.terms(&stop_words::analysis_text(e))).collect();
+
+        let (vectors, vocabulary) = tfidf(&docs);
+        let usable: Vec<usize> = (0..vectors.len()).filter(|&i| !vectors[i].is_empty()).collect();
+        if usable.is_empty() {
+            return Ok(TopicModel::default());
+        }
+        let k = k
+            .unwrap_or_else(|| (((usable.len() / 2) as f64).sqrt().round() as usize).clamp(2, 20))
+            .clamp(1, usable.len());
+
+        let mut centroids = initial_centroids(&vectors, &usable, k);
+        let mut assignment: HashMap<usize, usize> = HashMap::new();
+        for iteration in 0..MAX_ITERATIONS {
+            self.check_cancelled()?;
+            let mut changed = false;
+            for &doc in &usable {
+                let best = nearest(&vectors[doc], &centroids);
+                if assignment.insert(doc, best) != Some(best) {
+                    changed = true;
+                }
+            }
+            centroids = (0..k)
+                .map(|c| centroid(usable.iter().filter(|d| assignment[d] == c).map(|&d| &vectors[d])))
+                .collect();
+            self.emit_progress("topics", iteration + 1, Some(MAX_ITERATIONS));
+            if !changed {
+                break;
+            }
+        }
+
+        // Empty clusters are dropped and the rest numbered largest first
+        let mut sizes: Vec<(usize, usize)> = (0..k).map(|c| (c, assignment.values().filter(|&&a| a == c).count())).collect();
+        sizes.retain(|&(_, size)| size > 0);
+        sizes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let renumber: HashMap<usize, usize> = sizes.iter().enumerate().map(|(id, &(c, _))| (c, id)).collect();
+
+        let mut model = TopicModel::default();
+        for &doc in &usable {
+            model.email_topics.insert(emails[doc].id.clone(), renumber[&assignment[&doc]]);
+        }
+        let mut thread_votes: IndexMap<String, BTreeMap<usize, usize>> = IndexMap::new();
+        for (thread_id, thread_emails) in self.visible_threads() {
+            for email in thread_emails {
+                if let Some(&topic) = model.email_topics.get(&email.id) {
+                    *thread_votes.entry(thread_id.clone()).or_default().entry(topic).or_default() += 1;
+                }
+            }
+        }
+        for (thread_id, votes) in thread_votes {
+            // Ties go to the larger topic, which has the lower id
+            if let Some((&topic, _)) = votes.iter().max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0))) {
+                model.thread_topics.insert(thread_id, topic);
+            }
+        }
+
+        for (topic_id, &(c, email_count)) in sizes.iter().enumerate() {
+            let mut weights: Vec<(usize, f64)> = centroids[c].iter().map(|(&t, &w)| (t, w)).collect();
+            weights.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            let top_terms: Vec<String> = weights.iter().take(TOP_TERMS).map(|&(t, _)| vocabulary[t].clone()).collect();
+            model.topics.push(Topic {
+                topic_id,
+                label: top_terms.iter().take(3).cloned().collect::<Vec<_>>().join(" / "),
+                top_terms,
+                email_count,
+                thread_count: model.thread_topics.values().filter(|&&t| t == topic_id).count(),
+            });
+        }
+        Ok(model)
+    }
+}
+
+type SparseVector = BTreeMap<usize, f64>;
+
+// Unit-length TF-IDF vectors over terms found in at least two documents,
+// with the vocabulary they index into
+fn tfidf(docs: &[Vec<String>]) -> (Vec<SparseVector>, Vec<String>) {
+    let mut df: HashMap<&str, usize> = HashMap::new();
+    for doc in docs {
+        for term in doc.iter().map(String::as_str).collect::<HashSet<_>>() {
+            *df.entry(term).or_default() += 1;
+        }
+    }
+    let mut terms: Vec<(&str, usize)> = df.into_iter().filter(|&(_, n)| n >= 2 && n < docs.len().max(3)).collect();
+    terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    terms.truncate(MAX_TERMS);
+    let index: HashMap<&str, usize> = terms.iter().enumerate().map(|(i, &(t, _))| (t, i)).collect();
+
+    let n = docs.len() as f64;
+    let vectors = docs
+        .iter()
+        .map(|doc| {
+            let mut vector = SparseVector::new();
+            for term in doc {
+                if let Some(&i) = index.get(term.as_str()) {
+                    *vector.entry(i).or_default() += 1.0;
+                }
+            }
+            for (&i, weight) in vector.iter_mut() {
+                *weight = (1.0 + weight.ln()) * (n / terms[i].1 as f64).ln();
+            }
+            normalize(&mut vector);
+            vector
+        })
+        .collect();
+    (vectors, terms.iter().map(|&(t, _)| t.to_string()).collect())
+}
+
+fn normalize(vector: &mut SparseVector) {
+    let norm = vector.values().map(|w| w * w).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        vector.values_mut().for_each(|w| *w /= norm);
+    }
+    vector.retain(|_, w| *w > 0.0);
+}
+
+fn dot(a: &SparseVector, b: &SparseVector) -> f64 {
+    a.iter().filter_map(|(i, w)| b.get(i).map(|v| w * v)).sum()
+}
+
+fn nearest(vector: &SparseVector, centroids: &[SparseVector]) -> usize {
+    (1..centroids.len()).fold(0, |best, c| if dot(vector, &centroids[c]) > dot(vector, &centroids[best]) { c } else { best })
+}
+
+fn centroid<'a>(members: impl Iterator<Item = &'a SparseVector>) -> SparseVector {
+    let mut sum = SparseVector::new();
+    for vector in members {
+        for (&i, &w) in vector {
+            *sum.entry(i).or_default() += w;
+        }
+    }
+    normalize(&mut sum);
+    sum
+}
+
+// Deterministic farthest-first seeding: the first usable email, then each
+// time the email least similar to every centroid chosen so far
+fn initial_centroids(vectors: &[SparseVector], usable: &[usize], k: usize) -> Vec<SparseVector> {
+    let mut centroids = vec![vectors[usable[0]].clone()];
+    let mut closest: Vec<f64> = usable.iter().map(|&d| dot(&vectors[d], &centroids[0])).collect();
+    while centroids.len() < k {
+        let next = (1..usable.len()).fold(0, |best, i| if closest[i] < closest[best] { i } else { best });
+        let seed = vectors[usable[next]].clone();
+        for (i, &d) in usable.iter().enumerate() {
+            closest[i] = closest[i].max(dot(&vectors[d], &seed));
+        }
+        centroids.push(seed);
+    }
+    centroids
+}
@@ -0,0 +1,96 @@
+use crate::{EmailThreadProcessor, ThreadNode};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// Reply and forward markers mail clients put in front of a subject, in the
+// languages we see most in productions
+const SUBJECT_PREFIXES: &[&str] = &["re", "fw", "fwd", "aw", "wg", "sv", "vs", "tr", "rv", "antw"];
+
+/// A point where a branch of the thread moves to a new subject; the email
+/// and its replies could be reviewed as a thread of their own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitCandidate {
+    pub email_id: String,
+    pub parent_id: String,
+    pub depth: usize,
+    pub root_subject: String,
+    pub previous_subject: String,
+    pub subject: String,
+    // The email plus its descendants
+    pub branch_size: usize,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Emails whose subject departs from their parent's and from the root's,
+    /// ignoring Re:/Fwd: style prefixes, in tree order.
+    #[wasm_bindgen]
+    pub fn get_split_candidates(&self, thread_id: &str) -> Result<JsValue, JsValue> {
+        let tree = self.thread_tree(thread_id)?;
+        let mut candidates = Vec::new();
+        for root in &tree.roots {
+            collect_splits(root, root, &mut candidates);
+        }
+        self.to_js(&candidates)
+    }
+}
+
+/// Subject with reply/forward prefixes ("Re:", "FW:", "RE[2]:", "[EXT] Re:")
+/// stripped, lowercased and with whitespace collapsed.
+pub(crate) fn normalize_subject(subject: &str) -> String {
+    let mut rest = subject.trim();
+    loop {
+        let before = rest;
+        if rest.starts_with('[') {
+            if let Some(end) = rest.find(']') {
+                rest = rest[end + 1..].trim_start();
+            }
+        }
+        if let Some(colon) = rest.find(':') {
+            let prefix = rest[..colon].trim().to_lowercase();
+            let word = prefix.split('[').next().unwrap_or_default();
+            if SUBJECT_PREFIXES.contains(&word) && (word == prefix || prefix.ends_with(']')) {
+                rest = rest[colon + 1..].trim_start();
+            }
+        }
+        if rest == before {
+            break;
+        }
+    }
+    rest.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Sets `subject_changed` on every node below `root` whose normalized subject
+/// differs from the root's. Emails without a subject are never flagged.
+pub(crate) fn mark_subject_changes(root: &mut ThreadNode) {
+    let root_subject = normalize_subject(&root.email.subject);
+    let mut stack: Vec<&mut ThreadNode> = root.children.iter_mut().collect();
+    while let Some(node) = stack.pop() {
+        let subject = normalize_subject(&node.email.subject);
+        node.subject_changed = !subject.is_empty() && subject != root_subject;
+        stack.extend(node.children.iter_mut());
+    }
+}
+
+fn collect_splits(node: &ThreadNode, root: &ThreadNode, out: &mut Vec<SplitCandidate>) {
+    let parent_subject = normalize_subject(&node.email.subject);
+    for child in &node.children {
+        let subject = normalize_subject(&child.email.subject);
+        if child.subject_changed && subject != parent_subject {
+            out.push(SplitCandidate {
+                email_id: child.email.id.clone(),
+                parent_id: node.email.id.clone(),
+                depth: child.depth,
+                root_subject: root.email.subject.clone(),
+                previous_subject: node.email.subject.clone(),
+                subject: child.email.subject.clone(),
+                branch_size: branch_size(child),
+            });
+        }
+        collect_splits(child, root, out);
+    }
+}
+
+fn branch_size(node: &ThreadNode) -> usize {
+    1 + node.children.iter().map(branch_size).sum::<usize>()
+}
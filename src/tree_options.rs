@@ -0,0 +1,184 @@
+use crate::{opticon, EmailMessage, ThreadNode};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+// Id of the placeholder email a virtual root carries, before the thread id
+pub(crate) const VIRTUAL_ROOT_PREFIX: &str = "virtual:";
+
+/// How `build_thread_tree` and `build_thread_trees` shape a tree.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TreeOptions {
+    // Hang the roots of a multi-root thread under one placeholder node
+    pub virtual_root: bool,
+    // Order of the replies under each node, and of the roots
+    pub child_order: ChildOrder,
+    // Deepest level kept, roots being 0
+    pub max_depth: Option<usize>,
+    // Most emails kept, shallowest levels first
+    pub max_nodes: Option<usize>,
+}
+
+/// How siblings are ordered in a built tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChildOrder {
+    // By DateSent, oldest first
+    #[default]
+    Chronological,
+    // By BegBates prefix and number, emails without one last
+    Bates,
+    // Largest branch first
+    SubtreeSize,
+}
+
+impl ChildOrder {
+    pub(crate) fn parse(name: &str) -> Result<ChildOrder, JsValue> {
+        Ok(match name {
+            "chronological" => ChildOrder::Chronological,
+            "bates" => ChildOrder::Bates,
+            "subtree_size" => ChildOrder::SubtreeSize,
+            _ => return Err(JsValue::from_str(&format!("Unknown child order: {}", name))),
+        })
+    }
+
+    /// Compares two siblings given their emails and descendant counts; ties
+    /// fall back to date, then id, so every order is total.
+    pub(crate) fn compare(self, a: (&EmailMessage, usize), b: (&EmailMessage, usize)) -> Ordering {
+        let primary = match self {
+            ChildOrder::Chronological => Ordering::Equal,
+            ChildOrder::Bates => {
+                let key = |e: &EmailMessage| match opticon::split_bates(e.beg_bates.trim()) {
+                    Some((prefix, number)) => (false, prefix.to_string(), number),
+                    None => (true, String::new(), 0),
+                };
+                key(a.0).cmp(&key(b.0))
+            }
+            ChildOrder::SubtreeSize => b.1.cmp(&a.1),
+        };
+        primary.then_with(|| a.0.date_sent.cmp(&b.0.date_sent)).then_with(|| a.0.id.cmp(&b.0.id))
+    }
+}
+
+/// Orders the children of `node` and everything below it. Rollups must be in
+/// place for subtree size ordering.
+pub(crate) fn order_children(node: &mut ThreadNode, order: ChildOrder) {
+    order_nodes(&mut node.children, order);
+    for child in &mut node.children {
+        order_children(child, order);
+    }
+}
+
+pub(crate) fn order_nodes(nodes: &mut [ThreadNode], order: ChildOrder) {
+    nodes.sort_by(|a, b| {
+        order.compare((&a.email, a.rollup.descendant_count), (&b.email, b.rollup.descendant_count))
+    });
+}
+
+impl TreeOptions {
+    pub(crate) fn from_js(options: JsValue) -> Result<TreeOptions, JsValue> {
+        if options.is_undefined() || options.is_null() {
+            Ok(TreeOptions::default())
+        } else {
+            let options: TreeOptions = serde_wasm_bindgen::from_value(options)?;
+            if options.max_nodes == Some(0) {
+                return Err(JsValue::from_str("max_nodes must be at least 1"));
+            }
+            Ok(options)
+        }
+    }
+}
+
+/// One node above all of a thread's roots, primary root first, so a
+/// multi-root thread renders as a single tree. Its email is a placeholder
+/// with id "virtual:<thread id>" and the primary root's subject and date;
+/// roots cut by `limit` count as its `more_replies`.
+pub(crate) fn virtual_root(thread_id: &str, mut roots: Vec<ThreadNode>, more_roots: usize) -> ThreadNode {
+    for root in &mut roots {
+        deepen(root);
+    }
+    let primary = roots.first().map(|r| &r.email);
+    let date = primary.map(|e| e.date_sent).unwrap_or_default();
+    let email = EmailMessage {
+        id: format!("{}{}", VIRTUAL_ROOT_PREFIX, thread_id),
+        thread_id: thread_id.to_string(),
+        subject: primary.map(|e| e.subject.clone()).unwrap_or_default(),
+        date_sent: date,
+        date_created: date,
+        date_last_modified: date,
+        ..Default::default()
+    };
+    let mut node = ThreadNode {
+        email,
+        children: roots,
+        depth: 0,
+        highlights: Default::default(),
+        subject_changed: false,
+        rollup: Default::default(),
+        metrics: Default::default(),
+        heuristic_parent: false,
+        sequestered: false,
+        suspicious_date: false,
+        recipient_count: 0,
+        mass_mail: false,
+        is_virtual: true,
+        more_replies: more_roots,
+    };
+    for child in &node.children {
+        node.rollup.add_child(&child.rollup);
+    }
+    node
+}
+
+/// Cuts the tree down to `max_depth` and `max_nodes`, the node budget going
+/// to whole levels from the roots down in sibling order. Each node keeps a
+/// `more_replies` count of the emails cut from under it; returns the number
+/// cut with the roots that did not fit.
+pub(crate) fn limit(roots: &mut Vec<ThreadNode>, options: &TreeOptions) -> usize {
+    let max_depth = options.max_depth.unwrap_or(usize::MAX);
+    let budget = options.max_nodes.map(|max_nodes| {
+        let mut kept = HashSet::new();
+        let mut level: Vec<&ThreadNode> = roots.iter().collect();
+        let mut depth = 0;
+        while !level.is_empty() && depth <= max_depth && kept.len() < max_nodes {
+            for node in level.iter().take(max_nodes - kept.len()) {
+                kept.insert(node.email.id.clone());
+            }
+            level = level.into_iter().flat_map(|n| &n.children).collect();
+            depth += 1;
+        }
+        kept
+    });
+    let keep = |node: &ThreadNode| {
+        node.depth <= max_depth && budget.as_ref().is_none_or(|kept| kept.contains(&node.email.id))
+    };
+    prune(roots, &keep)
+}
+
+fn prune(nodes: &mut Vec<ThreadNode>, keep: &impl Fn(&ThreadNode) -> bool) -> usize {
+    let mut cut = 0;
+    nodes.retain(|node| {
+        let kept = keep(node);
+        if !kept {
+            cut += 1 + node.rollup.descendant_count;
+        }
+        kept
+    });
+    for node in nodes {
+        node.more_replies = prune(&mut node.children, keep);
+    }
+    cut
+}
+
+pub(crate) fn any_cut(nodes: &[ThreadNode]) -> bool {
+    nodes.iter().any(|node| node.more_replies > 0 || any_cut(&node.children))
+}
+
+fn deepen(node: &mut ThreadNode) {
+    node.depth += 1;
+    for child in &mut node.children {
+        deepen(child);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{EmailMessage, EmailThreadProcessor, LoadReport};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Drops all loaded emails and threads so the processor can take a new
+    /// dataset, along with thread labels, topics, reattachments, thread locks
+    /// and sequestrations. Settings (column mapping, date formats, threading mode, saved
+    /// searches, highlight terms and callbacks) are kept, as is the audit log.
+    #[wasm_bindgen]
+    pub fn clear(&mut self) {
+        console_log!("Clearing {} emails and {} threads", self.emails.len(), self.threads.len());
+        self.replace_emails(Vec::new());
+        self.threads.clear();
+        self.thread_aliases.clear();
+        self.thread_labels.clear();
+        self.topic_model = None;
+        self.reattachments.clear();
+        self.locked_threads.clear();
+        self.locked_emails.clear();
+        self.sequestered.clear();
+        self.load_report = LoadReport::default();
+        self.audit("clear", "All emails and threads dropped".to_string());
+    }
+
+    /// Removes emails by id (e.g. clawed-back documents) and returns how many
+    /// were removed. Threads are regrouped without them, and their
+    /// sequestrations, reattachments and places in thread locks are dropped,
+    /// as are the labels of threads they emptied.
+    #[wasm_bindgen]
+    pub fn remove_emails(&mut self, ids: Vec<String>) -> usize {
+        let wanted: HashSet<&str> = ids.iter().map(|id| id.as_str()).collect();
+        let removed = self.remove_where(|email| wanted.contains(email.id.as_str()));
+        self.audit("remove_emails", format!("{} of {} requested emails removed: {}", removed, ids.len(), ids.join(", ")));
+        removed
+    }
+
+    /// Removes a custodian from the corpus. Documents only that custodian held
+    /// are removed; deduplicated documents other custodians also held stay,
+    /// with the next custodian promoted to primary where needed.
+    #[wasm_bindgen]
+    pub fn remove_custodian(&mut self, custodian: &str) -> usize {
+        let held_by = |email: &EmailMessage| {
+            email.custodian.eq_ignore_ascii_case(custodian)
+                || email.all_custodians.iter().any(|c| c.eq_ignore_ascii_case(custodian))
+        };
+
+        let mut orphaned = HashSet::new();
+        for email in self.emails.iter_mut().filter(|e| held_by(e)) {
+            email.all_custodians.retain(|c| !c.eq_ignore_ascii_case(custodian));
+            if email.custodian.eq_ignore_ascii_case(custodian) {
+                email.custodian = email.all_custodians.first().cloned().unwrap_or_default();
+            }
+            if email.custodian.is_empty() {
+                orphaned.insert(email.id.clone());
+            }
+        }
+        let removed = self.remove_where(|email| orphaned.contains(&email.id));
+        // Promoted custodians can move emails in or out of a custodian filter
+        if removed == 0 && !self.threads.is_empty() {
+            self.group_by_threads();
+        }
+        self.audit("remove_custodian", format!("{}: {} emails removed", custodian, removed));
+        removed
+    }
+}
+
+impl EmailThreadProcessor {
+    fn remove_where(&mut self, remove: impl Fn(&EmailMessage) -> bool) -> usize {
+        let removed: HashSet<String> = self.emails.iter().filter(|e| remove(e)).map(|e| e.id.clone()).collect();
+        if removed.is_empty() {
+            return 0;
+        }
+
+        let mut emails = std::mem::take(&mut self.emails);
+        emails.retain(|e| !removed.contains(&e.id));
+        // Family links to removed documents would otherwise dangle
+        for email in emails.iter_mut() {
+            email.attachment_ids.retain(|id| !removed.contains(id));
+            if email.parent_id.as_ref().is_some_and(|p| removed.contains(p)) {
+                email.parent_id = None;
+            }
+        }
+        self.replace_emails(emails);
+
+        self.sequestered.retain(|id, _| !removed.contains(id));
+        self.reattachments.retain(|id, r| !removed.contains(id) && !removed.contains(&r.parent_id));
+        for lock in self.locked_threads.values_mut() {
+            lock.email_ids.retain(|id| !removed.contains(id));
+            lock.parents.retain(|id, parent| !removed.contains(id) && !removed.contains(parent));
+        }
+        self.locked_threads.retain(|_, lock| !lock.email_ids.is_empty());
+        self.index_thread_locks();
+        let emptied: HashSet<&String> = self
+            .threads
+            .iter()
+            .filter(|(_, emails)| emails.iter().all(|e| removed.contains(&e.id)))
+            .map(|(id, _)| id)
+            .collect();
+        self.thread_labels.retain(|id, _| !emptied.contains(id));
+        if !self.threads.is_empty() {
+            self.group_by_threads();
+        }
+
+        console_log!("Removed {} emails", removed.len());
+        removed.len()
+    }
+}
@@ -0,0 +1,267 @@
+use crate::dialect::{self, LoadFileDialect};
+use crate::opticon::split_bates;
+use crate::schema::{self, ColumnKind, ColumnSpec};
+use crate::{rfc5322, CsvRecord, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+const DEFAULT_SAMPLE_SIZE: usize = 1000;
+const MAX_REPORTED_ISSUES: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub row: usize,
+    pub column: String,
+    pub severity: String,
+    pub kind: String,
+    pub message: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub dialect: LoadFileDialect,
+    pub headers: Vec<String>,
+    pub missing_required: Vec<String>,
+    pub missing_optional: Vec<String>,
+    pub unmapped_columns: Vec<String>,
+    pub duplicate_columns: Vec<String>,
+    pub total_rows: usize,
+    pub rows_checked: usize,
+    pub valid_rows: usize,
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub issue_counts: IndexMap<String, usize>,
+    // Capped; the counts above cover every issue found
+    pub issues: Vec<ValidationIssue>,
+    pub is_valid: bool,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Dry-run QC of a CSV/DAT load file: checks headers against the column
+    /// mapping and validates dates, Bates numbers and addresses on the first
+    /// `sample_size` rows (default 1000) without touching loaded data.
+    #[wasm_bindgen]
+    pub fn validate_csv(&self, csv_data: &str, sample_size: Option<usize>) -> Result<JsValue, JsValue> {
+        console_log!("Validating CSV data, length: {}", csv_data.len());
+
+        if csv_data.is_empty() {
+            return Err(JsValue::from_str("CSV data is empty"));
+        }
+
+        let report = self.validate_load_file(csv_data, sample_size.unwrap_or(DEFAULT_SAMPLE_SIZE))?;
+        console_log!(
+            "Validation finished: {} rows, {} errors, {} warnings",
+            report.total_rows,
+            report.error_count,
+            report.warning_count
+        );
+        self.to_js(&report)
+    }
+}
+
+impl EmailThreadProcessor {
+    fn validate_load_file(&self, text: &str, sample_size: usize) -> Result<ValidationReport, JsValue> {
+        let (delimiter, quote) = dialect::sniff_text(text);
+        let dialect = LoadFileDialect {
+            delimiter,
+            quote,
+            has_bom: text.starts_with('\u{feff}'),
+            ..Default::default()
+        };
+
+        let prepared = dialect::prepare_text(text, &dialect);
+        let mut rdr = dialect::csv_reader(&prepared, &dialect);
+        let raw_headers = rdr
+            .headers()
+            .map_err(|e| JsValue::from_str(&format!("Error reading CSV headers: {}", e)))?
+            .clone();
+        let headers = self.map_headers(&raw_headers);
+
+        let mut report = ValidationReport {
+            dialect,
+            headers: raw_headers.iter().map(|h| h.to_string()).collect(),
+            missing_required: Vec::new(),
+            missing_optional: Vec::new(),
+            unmapped_columns: Vec::new(),
+            duplicate_columns: Vec::new(),
+            total_rows: 0,
+            rows_checked: 0,
+            valid_rows: 0,
+            error_count: 0,
+            warning_count: 0,
+            issue_counts: IndexMap::new(),
+            issues: Vec::new(),
+            is_valid: true,
+        };
+
+        for spec in schema::COLUMNS {
+            if !headers.iter().any(|h| h == spec.name) {
+                if spec.required {
+                    report.missing_required.push(spec.name.to_string());
+                } else {
+                    report.missing_optional.push(spec.name.to_string());
+                }
+            }
+        }
+        let mut seen = HashSet::new();
+        for (header, raw) in headers.iter().zip(raw_headers.iter()) {
+            if !seen.insert(header) && !report.duplicate_columns.contains(&raw.to_string()) {
+                report.duplicate_columns.push(raw.to_string());
+            }
+            if !schema::is_mapped(header) {
+                report.unmapped_columns.push(raw.to_string());
+            }
+        }
+        for missing in report.missing_required.clone() {
+            add_issue(&mut report, 0, &missing, "error", "missing_column", "Required column is missing", "");
+        }
+
+        let mut seen_bates = HashSet::new();
+        for (i, result) in rdr.records().enumerate() {
+            report.total_rows += 1;
+            if i >= sample_size {
+                continue;
+            }
+            report.rows_checked += 1;
+            let row_no = i + 1;
+            let errors_before = report.error_count;
+
+            let mut row = match result {
+                Ok(row) => row,
+                Err(e) => {
+                    add_issue(&mut report, row_no, "", "error", "unreadable_row", &e.to_string(), "");
+                    continue;
+                }
+            };
+            match dialect::fit_row(&mut row, headers.len()) {
+                Ok(Some(kind)) => {
+                    let message = format!("Row has the wrong number of fields ({}) and will be fitted on load", kind.replace('_', " "));
+                    add_issue(&mut report, row_no, "", "warning", "ragged_row", &message, "");
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    add_issue(&mut report, row_no, "", "error", "ragged_row", &e, "");
+                    continue;
+                }
+            }
+
+            for (header, value) in headers.iter().zip(row.iter()) {
+                let Some(spec) = schema::COLUMNS.iter().find(|c| c.name == header) else {
+                    continue;
+                };
+                self.check_value(&mut report, row_no, spec, value);
+            }
+
+            let get = |name: &str| headers.iter().position(|h| h == name).and_then(|i| row.get(i));
+            if let (Some(beg), Some(end)) = (get("BegBates"), get("EndBates")) {
+                check_bates_range(&mut report, row_no, beg, end);
+                if !beg.is_empty() && !seen_bates.insert(beg.to_string()) {
+                    add_issue(&mut report, row_no, "BegBates", "error", "duplicate_bates", "BegBates appears more than once", beg);
+                }
+            }
+            if let Some(history) = get("column_history") {
+                if !history.contains("THREAD:") && !history.contains("MSG-ID:") {
+                    add_issue(&mut report, row_no, "column_history", "warning", "no_thread_metadata", "Row has no thread id or Message-ID and will not be threaded", history);
+                }
+            }
+
+            // Whatever the column checks missed, the real parser will not
+            if report.error_count == errors_before {
+                let parsed = row
+                    .deserialize::<CsvRecord>(Some(&headers))
+                    .map_err(|e| e.to_string())
+                    .and_then(|record| self.parse_csv_record(record));
+                if let Err(e) = parsed {
+                    add_issue(&mut report, row_no, "", "error", "unparseable_row", &e, "");
+                }
+            }
+
+            if report.error_count == errors_before {
+                report.valid_rows += 1;
+            }
+        }
+
+        report.is_valid = report.error_count == 0;
+        Ok(report)
+    }
+
+    fn check_value(&self, report: &mut ValidationReport, row: usize, spec: &ColumnSpec, value: &str) {
+        let column = spec.name;
+        let value = value.trim();
+        if value.is_empty() {
+            if spec.required && matches!(spec.kind, ColumnKind::Date | ColumnKind::Bates | ColumnKind::Address) {
+                add_issue(report, row, column, "error", "empty_value", "Required value is empty", value);
+            }
+            return;
+        }
+
+        match spec.kind {
+            ColumnKind::Text => {}
+            ColumnKind::Date => {
+                if let Err(e) = self.parse_date(value, column) {
+                    add_issue(report, row, column, "error", "invalid_date", &e, value);
+                }
+            }
+            ColumnKind::Bates => {
+                if split_bates(value).is_none() {
+                    add_issue(report, row, column, "warning", "invalid_bates", "Bates number has no numeric suffix", value);
+                }
+            }
+            ColumnKind::Address | ColumnKind::AddressList => {
+                for address in rfc5322::parse_addresses(value) {
+                    if !looks_like_address(&address) {
+                        add_issue(report, row, column, "warning", "invalid_address", "Not an SMTP address", &address);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn check_bates_range(report: &mut ValidationReport, row: usize, beg: &str, end: &str) {
+    if let (Some((beg_prefix, lo)), Some((end_prefix, hi))) = (split_bates(beg), split_bates(end)) {
+        if beg_prefix != end_prefix {
+            add_issue(report, row, "EndBates", "warning", "bates_prefix_mismatch", "EndBates prefix differs from BegBates", end);
+        } else if hi < lo {
+            add_issue(report, row, "EndBates", "error", "bates_range_reversed", "EndBates is before BegBates", end);
+        }
+    }
+}
+
+pub(crate) fn looks_like_address(address: &str) -> bool {
+    match address.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !domain.contains('@')
+                && !address.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+fn add_issue(report: &mut ValidationReport, row: usize, column: &str, severity: &str, kind: &str, message: &str, value: &str) {
+    if severity == "error" {
+        report.error_count += 1;
+    } else {
+        report.warning_count += 1;
+    }
+    *report.issue_counts.entry(kind.to_string()).or_default() += 1;
+
+    if report.issues.len() < MAX_REPORTED_ISSUES {
+        report.issues.push(ValidationIssue {
+            row,
+            column: column.to_string(),
+            severity: severity.to_string(),
+            kind: kind.to_string(),
+            message: message.to_string(),
+            value: value.to_string(),
+        });
+    }
+}
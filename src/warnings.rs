@@ -0,0 +1,171 @@
+use crate::{integrity, rfc5322, validation, EmailThreadProcessor};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+// Every kind of warning, in the order they are reported
+const WARNING_KINDS: &[&str] = &[
+    "load_repair",
+    "subject_fallback",
+    "inferred_parent",
+    "reattached_orphan",
+    "parent_cycle",
+    "address_normalized",
+    "address_malformed",
+];
+
+/// Which warnings `get_warnings` returns; empty or absent criteria match
+/// everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WarningFilter {
+    pub kinds: Vec<String>,
+    pub thread_id: Option<String>,
+    pub email_id: Option<String>,
+}
+
+/// A judgment call the engine made instead of failing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Warning {
+    pub kind: String,
+    // None for warnings about a load or a whole thread
+    pub email_id: Option<String>,
+    pub thread_id: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarningReport {
+    pub total: usize,
+    // Matching warnings per kind, every kind listed
+    pub counts: IndexMap<String, usize>,
+    pub warnings: Vec<Warning>,
+}
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Every non-fatal judgment behind the current threads, for QC: input
+    /// repairs from the last load, threads grouped by subject, parents
+    /// inferred from References rather than In-Reply-To, orphans reattached
+    /// by heuristic, parent chains that loop (left out of trees), and
+    /// addresses lowercased or kept although malformed. `filter` is a
+    /// `WarningFilter` (`kinds`, `thread_id`, `email_id`). Load errors stay
+    /// in `get_load_report`.
+    #[wasm_bindgen]
+    pub fn get_warnings(&self, filter: JsValue) -> Result<JsValue, JsValue> {
+        self.reset_cancellation();
+        let filter: WarningFilter = if filter.is_undefined() || filter.is_null() {
+            WarningFilter::default()
+        } else {
+            serde_wasm_bindgen::from_value(filter)?
+        };
+        if let Some(kind) = filter.kinds.iter().find(|k| !WARNING_KINDS.contains(&k.as_str())) {
+            return Err(JsValue::from_str(&format!("Unknown warning kind: {}", kind)));
+        }
+
+        let warnings: Vec<Warning> = self
+            .warnings()?
+            .into_iter()
+            .filter(|w| filter.kinds.is_empty() || filter.kinds.contains(&w.kind))
+            .filter(|w| filter.thread_id.is_none() || w.thread_id == filter.thread_id)
+            .filter(|w| filter.email_id.is_none() || w.email_id == filter.email_id)
+            .collect();
+        let mut counts: IndexMap<String, usize> = WARNING_KINDS.iter().map(|k| (k.to_string(), 0)).collect();
+        for warning in &warnings {
+            counts[&warning.kind] += 1;
+        }
+        let report = WarningReport {
+            total: warnings.len(),
+            counts,
+            warnings,
+        };
+        self.to_js(&report)
+    }
+}
+
+impl EmailThreadProcessor {
+    fn warnings(&self) -> Result<Vec<Warning>, JsValue> {
+        let mut warnings = Vec::new();
+        let mut warn = |kind: &str, email_id: Option<&str>, thread_id: Option<&str>, message: String| {
+            warnings.push(Warning {
+                kind: kind.to_string(),
+                email_id: email_id.map(str::to_string),
+                thread_id: thread_id.map(str::to_string),
+                message,
+            })
+        };
+
+        for repair in &self.load_report.repairs {
+            warn("load_repair", None, None, repair.clone());
+        }
+
+        for (thread_id, emails) in &self.threads {
+            self.check_cancelled()?;
+            let thread = Some(thread_id.as_str());
+            if emails.iter().any(|e| matches!(self.thread_key_source(e), Some((_, "subject_fallback")))) {
+                warn("subject_fallback", None, thread, "Grouped by normalized subject, no thread id".to_string());
+            }
+            let parents = self.resolve_parents(emails);
+            for email in emails {
+                let Some(parent) = parents.get(&email.id).and_then(|p| emails.iter().find(|e| &e.id == p)) else {
+                    continue;
+                };
+                match self.parent_source(email, parent) {
+                    "references" => warn(
+                        "inferred_parent",
+                        Some(&email.id),
+                        thread,
+                        format!("Parent {} inferred from References; In-Reply-To names no email held", parent.id),
+                    ),
+                    "reattached" => {
+                        let signals = self.reattachments.get(&email.id).map(|r| r.signals.join(", "));
+                        warn(
+                            "reattached_orphan",
+                            Some(&email.id),
+                            thread,
+                            format!("Reattached under {} by heuristic ({})", parent.id, signals.unwrap_or_default()),
+                        )
+                    }
+                    _ => {}
+                }
+            }
+            for email in emails.iter().filter(|e| integrity::in_cycle(&parents, &e.id)) {
+                warn(
+                    "parent_cycle",
+                    Some(&email.id),
+                    thread,
+                    "Parent chain loops back on itself; left out of the thread tree".to_string(),
+                );
+            }
+        }
+
+        let mut seen = HashSet::new();
+        for email in self.included_emails() {
+            let fields: [(&str, &[String]); 4] = [
+                ("from", std::slice::from_ref(&email.from)),
+                ("to", &email.to),
+                ("cc", &email.cc),
+                ("bcc", &email.bcc),
+            ];
+            for (field, values) in fields {
+                for written in values.iter().flat_map(|v| rfc5322::parse_addresses(v)) {
+                    let identity = written.to_lowercase();
+                    // Once per written form, not per email it appears in
+                    if !seen.insert(written.clone()) {
+                        continue;
+                    }
+                    if !validation::looks_like_address(&identity) {
+                        let message =
+                            format!("\"{}\" in {} is not a well-formed address; kept as written", written, field);
+                        warn("address_malformed", Some(&email.id), None, message);
+                    } else if identity != written {
+                        let message = format!("\"{}\" in {} read as {}", written, field, identity);
+                        warn("address_normalized", Some(&email.id), None, message);
+                    }
+                }
+            }
+        }
+        Ok(warnings)
+    }
+}
@@ -0,0 +1,91 @@
+use crate::EmailThreadProcessor;
+use calamine::{Data, DataType, Reader};
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+
+// Columns whose numeric cells are Excel serial dates when the workbook lost
+// its number format
+const DATE_COLUMNS: &[&str] = &["DateSent", "DateCreated", "DateLastModified"];
+
+#[wasm_bindgen]
+impl EmailThreadProcessor {
+    /// Loads a load file delivered as an Excel workbook. Reads the named sheet,
+    /// or the first sheet when none is given; the header row uses the same
+    /// column names as the CSV path.
+    #[wasm_bindgen]
+    pub fn load_emails_from_xlsx(&mut self, data: &[u8], sheet_name: Option<String>) -> Result<usize, JsValue> {
+        console_log!("Loading emails from XLSX data, {} bytes", data.len());
+        self.reset_cancellation();
+
+        if data.is_empty() {
+            return Err(JsValue::from_str("XLSX data is empty"));
+        }
+
+        let mut workbook = calamine::open_workbook_auto_from_rs(Cursor::new(data.to_vec()))
+            .map_err(|e| JsValue::from_str(&format!("Could not open workbook: {}", e)))?;
+
+        let sheet = match sheet_name {
+            Some(name) => name,
+            None => workbook
+                .sheet_names()
+                .first()
+                .cloned()
+                .ok_or_else(|| JsValue::from_str("Workbook has no sheets"))?,
+        };
+        let range = workbook
+            .worksheet_range(&sheet)
+            .map_err(|e| JsValue::from_str(&format!("Could not read sheet {}: {}", sheet, e)))?;
+
+        let mut rows = range.rows();
+        let headers: csv::StringRecord = rows
+            .next()
+            .ok_or_else(|| JsValue::from_str("Sheet is empty"))?
+            .iter()
+            .map(|cell| cell_to_string(cell, false))
+            .collect();
+        console_log!("XLSX headers ({}): {:?}", sheet, headers);
+
+        let date_columns: Vec<bool> = self
+            .map_headers(&headers)
+            .iter()
+            .map(|h| DATE_COLUMNS.contains(&h))
+            .collect();
+        let records = rows
+            .filter(|row| row.iter().any(|cell| !cell.is_empty()))
+            .map(|row| {
+                Ok(row
+                    .iter()
+                    .enumerate()
+                    .map(|(i, cell)| cell_to_string(cell, date_columns.get(i).copied().unwrap_or(false)))
+                    .collect::<csv::StringRecord>())
+            });
+
+        let count = self.load_rows(&headers, records, "XLSX", None)?;
+        self.audit_load("load_emails_from_xlsx", format!("{} emails loaded from sheet {}", count, sheet), &[("XLSX", data)]);
+        Ok(count)
+    }
+}
+
+fn cell_to_string(cell: &Data, is_date_column: bool) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::String(s) => s.clone(),
+        Data::DateTimeIso(s) => s.clone(),
+        Data::DateTime(_) => cell
+            .as_datetime()
+            .map(|dt| dt.and_utc().format("%Y-%m-%dT%H:%M:%SZ").to_string())
+            .unwrap_or_default(),
+        Data::Float(f) if is_date_column => excel_serial_to_rfc3339(*f).unwrap_or_else(|| f.to_string()),
+        Data::Int(i) if is_date_column => excel_serial_to_rfc3339(*i as f64).unwrap_or_else(|| i.to_string()),
+        Data::Float(f) if f.fract() == 0.0 => format!("{}", *f as i64),
+        other => other.to_string(),
+    }
+}
+
+// Excel's 1900 date system, with day 0 at 1899-12-30 to absorb the 1900 leap-year bug
+fn excel_serial_to_rfc3339(serial: f64) -> Option<String> {
+    let epoch = chrono::NaiveDate::from_ymd_opt(1899, 12, 30)?.and_hms_opt(0, 0, 0)?;
+    let millis = (serial * 86_400_000.0).round() as i64;
+    let dt = epoch.checked_add_signed(chrono::Duration::milliseconds(millis))?;
+    Some(dt.and_utc().format("%Y-%m-%dT%H:%M:%SZ").to_string())
+}